@@ -1,7 +1,9 @@
+pub use bounding_box::BoundingBox;
 pub use quaternion::Quaternion;
 pub use transform::Transform;
 pub use vec3::Vec3;
 
+mod bounding_box;
 mod quaternion;
 mod transform;
 mod vec3;
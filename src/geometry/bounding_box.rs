@@ -0,0 +1,28 @@
+/// Axis-aligned box.
+#[derive(Clone, Copy)]
+pub struct BoundingBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl BoundingBox {
+    /// Smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        BoundingBox {
+            min: [0, 1, 2].map(|i| self.min[i].min(other.min[i])),
+            max: [0, 1, 2].map(|i| self.max[i].max(other.max[i])),
+        }
+    }
+
+    pub fn corners(&self) -> [[f32; 3]; 8] {
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|corner| {
+            [0, 1, 2].map(|i| {
+                if corner & (1 << i) == 0 {
+                    self.min[i]
+                } else {
+                    self.max[i]
+                }
+            })
+        })
+    }
+}
@@ -336,7 +336,7 @@ impl Transform {
     }
 
     pub fn project_perspective(&self, fov: f32, aspect: f32, near: f32, far: f32) -> [[f32; 4]; 4] {
-        let fov_coeff = -(fov / 2.0).tan();
+        let fov_coeff = -1.0 / (fov / 2.0).tan();
         let perspective_coeff = far / (far - near);
         [
             [
@@ -366,6 +366,24 @@ impl Transform {
         ]
     }
 
+    pub fn apply_vector(&self, vector: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = vector;
+        [0, 1, 2].map(|i| {
+            self.rotation_scale[0][i] * x
+                + self.rotation_scale[1][i] * y
+                + self.rotation_scale[2][i] * z
+        })
+    }
+
+    pub fn apply_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = self.apply_vector(point);
+        [
+            x + self.translation[0],
+            y + self.translation[1],
+            z + self.translation[2],
+        ]
+    }
+
     pub fn to_homogeneous(&self) -> [[f32; 4]; 4] {
         [
             [
@@ -19,6 +19,14 @@ impl Vec3 {
         }
     }
 
+    pub fn normalize_or_zero(&self) -> Self {
+        let norm = self.dot(*self).sqrt();
+        if norm == 0.0 {
+            return *self;
+        }
+        *self * (1.0 / norm)
+    }
+
     pub fn dot(self, other: Self) -> f32 {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
@@ -118,3 +126,9 @@ impl From<[f32; 3]> for Vec3 {
         }
     }
 }
+
+impl From<Vec3> for [f32; 3] {
+    fn from(value: Vec3) -> Self {
+        [value.x, value.y, value.z]
+    }
+}
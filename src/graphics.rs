@@ -1,6 +1,22 @@
 pub mod allocators;
+pub mod attribute_generator;
+pub mod camera;
 pub mod engine;
+pub mod environment;
 pub mod format_converter;
+pub mod frame_resources;
+pub mod light;
+pub mod load_error;
 pub mod load_gltf;
+pub mod load_handle;
+pub mod load_options;
+pub mod lod;
+pub mod mesh_data;
 pub mod pipeline;
+pub mod post_process;
+pub mod scene;
 pub mod shaders;
+pub mod shadow;
+pub mod texture;
+pub mod uploader;
+pub mod view;
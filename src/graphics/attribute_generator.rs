@@ -0,0 +1,2 @@
+pub mod normal;
+pub mod tangent;
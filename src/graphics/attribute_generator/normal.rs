@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use crate::geometry::Vec3;
+
+/// Expects unwelded triangles, every corner gets the normal of its face.
+pub fn flat(positions: &[[f32; 3]], indices: &[u32]) -> Vec<[f32; 3]> {
+    let mut normals = vec![[0.0; 3]; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let normal = face_normal(positions, triangle).normalize_or_zero();
+        for &i in triangle {
+            normals[i as usize] = normal.into();
+        }
+    }
+    normals
+}
+
+/// Expects unwelded triangles, every corner gets the angle-weighted average of the normals of the
+/// faces sharing its position, leaving out the faces beyond `crease_angle`.
+pub fn smooth(positions: &[[f32; 3]], indices: &[u32], crease_angle: f32) -> Vec<[f32; 3]> {
+    let face_normals: Vec<_> = indices
+        .chunks_exact(3)
+        .map(|triangle| face_normal(positions, triangle).normalize_or_zero())
+        .collect();
+    let corner_count = face_normals.len() * 3;
+    let mut corners_by_position: HashMap<_, Vec<_>> = HashMap::new();
+    for (corner, &i) in indices[..corner_count].iter().enumerate() {
+        corners_by_position
+            .entry(position_key(positions[i as usize]))
+            .or_default()
+            .push(corner);
+    }
+    let min_cos = crease_angle.cos();
+    let mut normals = vec![[0.0; 3]; positions.len()];
+    for (corner, &i) in indices[..corner_count].iter().enumerate() {
+        let face_normal = face_normals[corner / 3];
+        let normal = corners_by_position[&position_key(positions[i as usize])]
+            .iter()
+            .filter(|&&other| face_normals[other / 3].dot(face_normal) >= min_cos)
+            .fold(Vec3::from([0.0; 3]), |sum, &other| {
+                sum + face_normals[other / 3] * corner_angle(positions, indices, other)
+            });
+        normals[i as usize] = normal.normalize_or_zero().into();
+    }
+    normals
+}
+
+fn face_normal(positions: &[[f32; 3]], triangle: &[u32]) -> Vec3 {
+    let a = Vec3::from(positions[triangle[0] as usize]);
+    let b = Vec3::from(positions[triangle[1] as usize]);
+    let c = Vec3::from(positions[triangle[2] as usize]);
+    (b - a).cross(c - a)
+}
+
+fn corner_angle(positions: &[[f32; 3]], indices: &[u32], corner: usize) -> f32 {
+    let triangle = corner - corner % 3;
+    let position =
+        |offset| Vec3::from(positions[indices[triangle + (corner + offset) % 3] as usize]);
+    let a = position(0);
+    let ab = (position(1) - a).normalize_or_zero();
+    let ac = (position(2) - a).normalize_or_zero();
+    ab.dot(ac).clamp(-1.0, 1.0).acos()
+}
+
+// -0.0 and 0.0 are the same position
+fn position_key(position: [f32; 3]) -> [u32; 3] {
+    position.map(|x| (x + 0.0).to_bits())
+}
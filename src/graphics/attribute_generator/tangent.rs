@@ -0,0 +1,85 @@
+use bevy_mikktspace::Geometry;
+
+use crate::geometry::Vec3;
+
+struct MikkTSpaceGeometry<'a> {
+    positions: &'a [[f32; 3]],
+    normals: &'a [[f32; 3]],
+    tex_coords: &'a [[f32; 2]],
+    indices: &'a [u32],
+    tangents: Vec<[f32; 4]>,
+}
+
+/// Expects unwelded triangles, as MikkTSpace can give different tangents to the corners sharing a vertex.
+/// The w component is the bitangent sign, as for the glTF TANGENT attribute.
+pub fn mikktspace(
+    positions: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    tex_coords: &[[f32; 2]],
+    indices: &[u32],
+) -> Vec<[f32; 4]> {
+    let mut geometry = MikkTSpaceGeometry {
+        positions,
+        normals,
+        tex_coords,
+        indices,
+        // kept for the degenerate triangles that MikkTSpace skips
+        tangents: fallback(normals),
+    };
+    bevy_mikktspace::generate_tangents(&mut geometry);
+    geometry.tangents
+}
+
+/// Any tangent orthogonal to the normal, for the meshes without texture coordinates.
+pub fn fallback(normals: &[[f32; 3]]) -> Vec<[f32; 4]> {
+    normals
+        .iter()
+        .map(|&normal| {
+            let normal = Vec3::from(normal);
+            // the world axis least aligned with the normal is never parallel to it
+            let [x, y, z] = [normal.x.abs(), normal.y.abs(), normal.z.abs()];
+            let axis = if x <= y && x <= z {
+                [1.0, 0.0, 0.0]
+            } else if y <= z {
+                [0.0, 1.0, 0.0]
+            } else {
+                [0.0, 0.0, 1.0]
+            };
+            let tangent = normal.cross(Vec3::from(axis)).normalize_or_zero();
+            [tangent.x, tangent.y, tangent.z, 1.0]
+        })
+        .collect()
+}
+
+impl<'a> MikkTSpaceGeometry<'a> {
+    fn vertex(&self, face: usize, vert: usize) -> usize {
+        self.indices[face * 3 + vert] as usize
+    }
+}
+
+impl<'a> Geometry for MikkTSpaceGeometry<'a> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.positions[self.vertex(face, vert)]
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.normals[self.vertex(face, vert)]
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.tex_coords[self.vertex(face, vert)]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        let vertex = self.vertex(face, vert);
+        self.tangents[vertex] = tangent;
+    }
+}
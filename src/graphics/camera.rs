@@ -0,0 +1,204 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::geometry::{BoundingBox, Transform, Vec3};
+
+#[derive(Clone, Copy)]
+pub enum Projection {
+    /// `fov` is the vertical field of view in radians, nothing is clipped far away if `far` is `None`.
+    Perspective {
+        fov: f32,
+        near: f32,
+        far: Option<f32>,
+    },
+    /// `size` is half the height of the view, in world units.
+    Orthographic { size: f32, near: f32, far: f32 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub transform: Transform,
+    pub projection: Projection,
+    /// Maps the near plane to the depth 1 and the far plane to 0, which is more precise far away.
+    pub reverse_z: bool,
+}
+
+/// The half-spaces whose intersection a camera sees.
+#[derive(Clone, Copy)]
+pub struct Frustum {
+    /// (a, b, c, d) for the world points where a * x + b * y + c * z + d >= 0.
+    pub planes: [[f32; 4]; 6],
+}
+
+impl Camera {
+    /// A perspective camera with a 90° field of view, seeing from 0.1 to 100 units away.
+    pub fn new(transform: Transform) -> Self {
+        Camera {
+            transform,
+            projection: Projection::Perspective {
+                fov: FRAC_PI_2,
+                near: 0.1,
+                far: Some(100.0),
+            },
+            reverse_z: false,
+        }
+    }
+
+    pub fn near(&self) -> f32 {
+        match self.projection {
+            Projection::Perspective { near, .. } | Projection::Orthographic { near, .. } => near,
+        }
+    }
+
+    /// `f32::INFINITY` without a far plane.
+    pub fn far(&self) -> f32 {
+        match self.projection {
+            Projection::Perspective { far, .. } => far.unwrap_or(f32::INFINITY),
+            Projection::Orthographic { far, .. } => far,
+        }
+    }
+
+    /// Depth of the far plane, where the depth buffer is cleared to.
+    pub fn far_depth(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// `aspect` is the width of the view divided by its height.
+    pub fn view_projection(&self, aspect: f32) -> [[f32; 4]; 4] {
+        multiply(
+            self.projection_matrix(aspect),
+            self.transform.reverse().to_homogeneous(),
+        )
+    }
+
+    pub fn frustum(&self, aspect: f32) -> Frustum {
+        Frustum::new(self.view_projection(aspect))
+    }
+
+    /// Half the width and height of the view at a distance of 1 for perspective cameras,
+    /// half the width and height of the view for orthographic ones.
+    pub fn view_scale(&self, aspect: f32) -> [f32; 2] {
+        let half_height = match self.projection {
+            Projection::Perspective { fov, .. } => (fov / 2.0).tan(),
+            Projection::Orthographic { size, .. } => size,
+        };
+        [half_height * aspect, half_height]
+    }
+
+    /// Corners of the part of the view between the distances `start` and `end`, in world space.
+    pub fn frustum_corners(&self, aspect: f32, start: f32, end: f32) -> Vec<Vec3> {
+        let [scale_x, scale_y] = self.view_scale(aspect);
+        [start, end]
+            .into_iter()
+            .flat_map(|distance| {
+                let (half_width, half_height) = match self.projection {
+                    Projection::Perspective { .. } => (scale_x * distance, scale_y * distance),
+                    Projection::Orthographic { .. } => (scale_x, scale_y),
+                };
+                [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)].map(|(x, y)| {
+                    Vec3::from(self.transform.apply_point([
+                        x * half_width,
+                        y * half_height,
+                        distance,
+                    ]))
+                })
+            })
+            .collect()
+    }
+
+    /// Share of the view height covered by the sphere around `bounds` moved by `transform`,
+    /// `f32::INFINITY` when a perspective camera is inside it.
+    pub fn screen_coverage(&self, bounds: &BoundingBox, transform: &Transform) -> f32 {
+        let corners = bounds
+            .corners()
+            .map(|corner| Vec3::from(transform.apply_point(corner)));
+        let center = corners
+            .iter()
+            .fold(Vec3::from([0.0; 3]), |sum, &corner| sum + corner)
+            * 0.125;
+        let radius = corners
+            .iter()
+            .map(|&corner| (corner - center).dot(corner - center))
+            .fold(0.0, f32::max)
+            .sqrt();
+        let [_, half_height] = self.view_scale(1.0);
+        match self.projection {
+            Projection::Perspective { .. } => {
+                let offset = center - Vec3::from(self.transform.translation);
+                let distance = offset.dot(offset).sqrt();
+                if distance <= radius {
+                    return f32::INFINITY;
+                }
+                radius / (distance * half_height)
+            }
+            Projection::Orthographic { .. } => radius / half_height,
+        }
+    }
+
+    // maps the view space, where X is left, Y up and Z forward, to the clip space
+    fn projection_matrix(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let [scale_x, scale_y] = self.view_scale(aspect);
+        let near = self.near();
+        let far = self.far();
+        // the clip depth is `depth_coeff * z + depth_offset`
+        let (depth_coeff, depth_offset, perspective) = match (self.projection, self.reverse_z) {
+            (Projection::Perspective { far: None, .. }, false) => (1.0, -near, 1.0),
+            (Projection::Perspective { far: None, .. }, true) => (0.0, near, 1.0),
+            (Projection::Perspective { .. }, false) => {
+                (far / (far - near), -near * far / (far - near), 1.0)
+            }
+            (Projection::Perspective { .. }, true) => {
+                (-near / (far - near), near * far / (far - near), 1.0)
+            }
+            (Projection::Orthographic { .. }, false) => {
+                (1.0 / (far - near), -near / (far - near), 0.0)
+            }
+            (Projection::Orthographic { .. }, true) => {
+                (-1.0 / (far - near), far / (far - near), 0.0)
+            }
+        };
+        [
+            [-1.0 / scale_x, 0.0, 0.0, 0.0],
+            [0.0, -1.0 / scale_y, 0.0, 0.0],
+            [0.0, 0.0, depth_coeff, perspective],
+            [0.0, 0.0, depth_offset, 1.0 - perspective],
+        ]
+    }
+}
+
+impl Frustum {
+    /// `view_projection` maps the world to the clip space, where the depth goes from 0 to 1.
+    pub fn new(view_projection: [[f32; 4]; 4]) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|row| view_projection.map(|column| column[row]));
+        let add = |a: [f32; 4], b: [f32; 4]| [0, 1, 2, 3].map(|i| a[i] + b[i]);
+        let sub = |a: [f32; 4], b: [f32; 4]| [0, 1, 2, 3].map(|i| a[i] - b[i]);
+        Frustum {
+            planes: [add(w, x), sub(w, x), add(w, y), sub(w, y), z, sub(w, z)],
+        }
+    }
+
+    /// Whether `bounds` moved by `transform` may be seen.
+    /// Some boxes near the edges are kept although they are not seen.
+    pub fn intersects(&self, bounds: &BoundingBox, transform: &Transform) -> bool {
+        let corners = bounds.corners().map(|corner| transform.apply_point(corner));
+        self.planes.iter().all(|plane| {
+            corners.iter().any(|corner| {
+                plane[0] * corner[0] + plane[1] * corner[1] + plane[2] * corner[2] + plane[3] >= 0.0
+            })
+        })
+    }
+}
+
+// column-major product of two 4x4 matrices
+fn multiply(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut product = [[0.0; 4]; 4];
+    for (column, b_column) in product.iter_mut().zip(b) {
+        for (row, value) in column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b_column[k]).sum();
+        }
+    }
+    product
+}
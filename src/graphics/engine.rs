@@ -1,27 +1,31 @@
-use std::{f32::consts::FRAC_PI_2, mem, sync::Arc};
+use image::RgbaImage;
+use std::{mem, ops::Range, sync::Arc};
 use vulkano::{
     buffer::{
         allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
         Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer,
     },
     command_buffer::{
-        AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
-        RenderPassBeginInfo, SubpassBeginInfo, SubpassContents,
+        AutoCommandBufferBuilder, ClearColorImageInfo, CommandBufferUsage, CopyImageToBufferInfo,
+        DrawIndexedIndirectCommand, PrimaryAutoCommandBuffer, RenderPassBeginInfo,
+        SubpassBeginInfo, SubpassContents,
     },
-    descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet},
+    descriptor_set::PersistentDescriptorSet,
     device::{
         physical::{PhysicalDevice, PhysicalDeviceType},
         Device, DeviceCreateInfo, DeviceExtensions, Features, Queue, QueueCreateInfo, QueueFlags,
     },
     format::Format,
     image::{
-        sampler::{Sampler, SamplerCreateInfo},
-        view::ImageView,
-        Image, ImageCreateInfo, ImageType, ImageUsage, SampleCount,
+        sampler::Sampler, view::ImageView, Image, ImageCreateInfo, ImageType, ImageUsage,
+        SampleCount,
     },
     instance::{Instance, InstanceCreateFlags, InstanceCreateInfo, InstanceExtensions},
     memory::allocator::{AllocationCreateInfo, MemoryAllocator, MemoryTypeFilter},
-    pipeline::{graphics::vertex_input::Vertex, Pipeline, PipelineBindPoint},
+    pipeline::{
+        graphics::{vertex_input::Vertex, viewport::Viewport},
+        ComputePipeline, Pipeline, PipelineBindPoint,
+    },
     render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass},
     swapchain::{
         acquire_next_image, Surface, SurfaceCapabilities, Swapchain, SwapchainCreateInfo,
@@ -34,17 +38,33 @@ use winit::window::Window;
 
 use crate::{
     animation_system::animator::Animator,
-    geometry::Transform,
+    geometry::{BoundingBox, Transform, Vec3},
     graphics::{
         allocators::AllocatorCollection,
-        pipeline::PipelineCollection,
-        shaders::{animated_vertex_shader, fragment_shader, vertex_shader},
+        camera::{Camera, Frustum, Projection},
+        environment::{self, Environment, DEFAULT_RADIANCE},
+        frame_resources::{self, CullingResources, ShadowResources, ViewResources},
+        light::Light,
+        lod::{self, Lod},
+        pipeline::{PipelineCollection, PipelineKey},
+        post_process::{self, Bloom, Tonemapping, Vignette, HDR_FORMAT},
+        shaders::{
+            bloom_downsample_shader, cull_instances_shader, fragment_shader,
+            shadow_animated_vertex_shader, shadow_vertex_shader, skybox_vertex_shader,
+            spread_instance_count_shader, tonemap_fragment_shader, vertex_shader,
+        },
+        shadow::{self, ShadowMaps, SHADOW_LAYER_COUNT},
+        uploader::Uploader,
+        view::{View, ViewTarget, ViewTargets},
     },
     DisplayRequest, Drawer,
 };
 
 pub const IMAGE_FORMAT: Format = Format::R8G8B8A8_SRGB;
-pub const DEPTH_FORMAT: Format = Format::D16_UNORM;
+/// Floating point, for reverse-Z cameras to be precise far away.
+pub const DEPTH_FORMAT: Format = Format::D32_SFLOAT;
+const WORKGROUP_SIZE: u32 = 8;
+const CULLING_WORKGROUP_SIZE: u32 = 64;
 
 #[derive(BufferContents, Vertex)]
 #[repr(C)]
@@ -60,10 +80,12 @@ pub struct Normal {
     pub normal: [f32; 3],
 }
 
+#[derive(Clone)]
 pub struct BaseVertex {
     pub positions: Subbuffer<[Position]>,
     pub normals: Subbuffer<[Normal]>,
     pub tangents: Subbuffer<[Tangent]>,
+    pub indices: Subbuffer<[u32]>,
 }
 
 #[derive(BufferContents, Vertex)]
@@ -80,16 +102,18 @@ pub struct TextureMetalCoord {
     pub tex_metal_coords_in: [f32; 2],
 }
 
+#[derive(Clone)]
 pub struct Texture {
     pub coordinates: Subbuffer<[TextureCoord]>,
     pub image: Arc<ImageView>,
+    pub sampler: Arc<Sampler>,
 }
 
 #[derive(BufferContents, Vertex)]
 #[repr(C)]
 pub struct Tangent {
-    #[format(R32G32B32_SFLOAT)]
-    pub tangent: [f32; 3],
+    #[format(R32G32B32A32_SFLOAT)]
+    pub tangent: [f32; 4],
 }
 
 #[derive(BufferContents, Vertex)]
@@ -99,17 +123,38 @@ pub struct TextureNormalCoord {
     pub tex_normal_coords_in: [f32; 2],
 }
 
-pub struct PBRFactors {
-    pub color: [f32; 4],
-    pub metalness: f32,
-    pub roughness: f32,
+#[derive(BufferContents, Vertex)]
+#[repr(C)]
+pub struct TextureOcclusionCoord {
+    #[format(R32G32_SFLOAT)]
+    pub tex_occlusion_coords_in: [f32; 2],
 }
 
 #[derive(BufferContents, Vertex)]
 #[repr(C)]
-pub struct LightPosition {
-    #[format(R32G32B32_SFLOAT)]
-    pub light_position: [f32; 3],
+pub struct TextureEmissiveCoord {
+    #[format(R32G32_SFLOAT)]
+    pub tex_emissive_coords_in: [f32; 2],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+#[derive(Clone)]
+pub struct PBRFactors {
+    pub color: [f32; 4],
+    pub metalness: f32,
+    pub roughness: f32,
+    pub occlusion_strength: f32,
+    pub emissive: [f32; 3],
+    pub alpha_mode: AlphaMode,
+    /// Fragments below it are discarded, 0 unless the alpha mode is `Mask`.
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
 }
 
 #[derive(BufferContents, Vertex)]
@@ -133,51 +178,219 @@ pub struct Joint {
     pub joints: [u32; 4],
 }
 
+#[derive(Clone)]
 pub struct Skin {
     pub joints: Subbuffer<[Joint]>,
     pub weights: Subbuffer<[Weight]>,
 }
 
+/// Clones share the buffers and textures of the original, each with its own animator.
+#[derive(Clone)]
 pub enum Asset {
     Animated(Vec<AnimatedPrimitive>, Animator),
     Still(Vec<Primitive>),
 }
 
+#[derive(Clone)]
 pub struct AnimatedPrimitive {
     pub primitive: Primitive,
     pub skin: Skin,
 }
 
+#[derive(Clone)]
 pub struct Primitive {
     pub vertex: BaseVertex,
     pub color: Texture,
     pub metalness: Texture,
     pub normal: Texture,
+    pub occlusion: Texture,
+    pub emissive: Texture,
     pub pbr: PBRFactors,
+    /// Blended primitives never cast shadows.
+    pub cast_shadows: bool,
+    /// Binds the textures, made by `Uploader::create_material` from `textures`.
+    pub material: Arc<PersistentDescriptorSet>,
+    /// Contains the vertices before skinning.
+    pub bounds: BoundingBox,
+    /// `Lod::single()` for assets without levels of detail.
+    pub lod: Lod,
+}
+
+impl Asset {
+    pub fn set_cast_shadows(&mut self, cast_shadows: bool) {
+        match self {
+            Asset::Still(primitives) => primitives
+                .iter_mut()
+                .for_each(|primitive| primitive.cast_shadows = cast_shadows),
+            Asset::Animated(primitives, _) => primitives
+                .iter_mut()
+                .for_each(|primitive| primitive.primitive.cast_shadows = cast_shadows),
+        }
+    }
+
+    /// Contains the vertices of all the primitives before skinning.
+    pub fn bounds(&self) -> BoundingBox {
+        let bounds = match self {
+            Asset::Still(primitives) => primitives
+                .iter()
+                .map(|primitive| primitive.bounds)
+                .reduce(|a, b| a.union(&b)),
+            Asset::Animated(primitives, _) => primitives
+                .iter()
+                .map(|primitive| primitive.primitive.bounds)
+                .reduce(|a, b| a.union(&b)),
+        };
+        bounds.unwrap_or(BoundingBox {
+            min: [0.0; 3],
+            max: [0.0; 3],
+        })
+    }
+
+    /// Screen coverage of each level of detail, from the most detailed.
+    pub fn screen_coverages(&self) -> Vec<f32> {
+        let lods: Vec<Lod> = match self {
+            Asset::Still(primitives) => primitives.iter().map(|primitive| primitive.lod).collect(),
+            Asset::Animated(primitives, _) => primitives
+                .iter()
+                .map(|primitive| primitive.primitive.lod)
+                .collect(),
+        };
+        let mut screen_coverages = Vec::new();
+        for lod in lods {
+            if screen_coverages.len() <= lod.level {
+                screen_coverages.resize(lod.level + 1, 0.0);
+            }
+            screen_coverages[lod.level] = lod.screen_coverage;
+        }
+        screen_coverages
+    }
+}
+
+impl Primitive {
+    /// Color, metalness, normal, occlusion and emissive textures.
+    pub fn textures(&self) -> [&Texture; 5] {
+        [
+            &self.color,
+            &self.metalness,
+            &self.normal,
+            &self.occlusion,
+            &self.emissive,
+        ]
+    }
+
+    fn casts_shadows(&self) -> bool {
+        self.cast_shadows && self.pbr.alpha_mode != AlphaMode::Blend
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DrawnPrimitive<'a> {
+    Still(&'a Primitive),
+    Animated(&'a AnimatedPrimitive),
+}
+
+struct DrawnAsset<'a> {
+    asset: &'a Asset,
+    item_pos: Vec<Transform>,
+    pose: Option<Vec<Transform>>,
+    /// Level of detail of the drawn primitives.
+    lod: usize,
+    /// See `LodDraw::fade`.
+    lod_fade: f32,
+}
+
+/// Model transforms of instances drawn together, with their poses.
+#[derive(Clone)]
+struct Instances {
+    item_pos: Subbuffer<[[[f32; 4]; 4]]>,
+    /// Binds the joint transforms of each instance of `item_pos` in turn,
+    /// missing for primitives drawn without a pose.
+    pose_set: Option<Arc<PersistentDescriptorSet>>,
+    /// Joint transforms of each instance.
+    transform_length: u32,
+    /// Written by the culling pass, one per primitive of the asset or only the one of the drawn primitive.
+    /// The `drawn` instances are drawn when there are none.
+    commands: Option<Subbuffer<[DrawIndexedIndirectCommand]>>,
+    drawn: Range<u32>,
+    /// See `LodDraw::fade`.
+    lod_fade: f32,
+}
+
+/// Instances of an asset after the culling pass.
+struct CulledInstances {
+    /// The ones the view may see, counted in the draw commands.
+    visible: Instances,
+    /// All of them, to draw some one by one.
+    all: Instances,
+}
+
+/// A camera with the aspect ratio of the view it is drawn to.
+#[derive(Clone, Copy)]
+struct ViewCamera {
+    camera: Camera,
+    aspect: f32,
+    frustum: Frustum,
+}
+
+struct BlendedDraw<'a> {
+    primitive: DrawnPrimitive<'a>,
+    /// Only the blended instance.
+    instances: Instances,
+    distance: f32,
 }
 
 pub struct Engine {
-    pub surface: Arc<Surface>,
-    pub swapchain: Arc<Swapchain>,
-    pub caps: SurfaceCapabilities,
+    surface: Option<Arc<Surface>>,
+    swapchain: Option<Arc<Swapchain>>,
+    caps: Option<SurfaceCapabilities>,
     pub device: Arc<Device>,
     pub queue: Arc<Queue>,
+    /// Renders the scene of each view to its HDR image, multisampled unless turned off with `Drawer::set_msaa`.
     pub render_pass: Arc<RenderPass>,
+    /// Tonemaps the HDR image of each view to one of `images` or to a render texture,
+    /// through the FXAA pass when it is on.
+    pub post_process_render_pass: Arc<RenderPass>,
     pub pipelines: PipelineCollection,
-    pub allocators: AllocatorCollection,
+    pub allocators: Arc<AllocatorCollection>,
     pub images: Vec<Arc<Image>>,
+    /// One per image of `images`.
     pub framebuffers: Vec<Arc<Framebuffer>>,
+    /// One per view drawn in the last frame, sized like it.
+    pub view_targets: Vec<ViewTargets>,
     pub previous_frame_end: Box<dyn GpuFuture>,
-    pub uniform_buffer: SubbufferAllocator,
-    pub sampler: Arc<Sampler>,
+    /// Instance transforms, its arenas are reused once the frames using them are done.
+    pub instance_buffer: SubbufferAllocator,
+    pub uploader: Uploader,
+    pub shadow_maps: ShadowMaps,
+    pub environment_sampler: Arc<Sampler>,
+    pub brdf_lut: Arc<ImageView>,
+    /// Lights frames displaying no environment.
+    pub default_environment: Environment,
+    /// Multiplies the scene colors before tonemapping.
+    pub exposure: f32,
+    pub tonemapping: Tonemapping,
+    pub bloom: Option<Bloom>,
+    /// Antialiases the tonemapped frame, usually with MSAA turned off.
+    pub fxaa: bool,
+    /// Made by `Loader::load_color_grading_lut`.
+    pub color_grading_lut: Option<Arc<ImageView>>,
+    pub vignette: Option<Vignette>,
+    /// Share of the screen coverage of each level of detail over which it is dithered out for the next one.
+    pub lod_fade: Option<f32>,
     pub recreate_swapchain: bool,
+    /// One pool per culling dispatch of a frame, in order.
+    culling_resources: Vec<Vec<CullingResources>>,
+    /// One pool per view drawn in the last frame.
+    view_resources: Vec<Vec<ViewResources>>,
+    /// Shared by the animated shadow casters of a frame.
+    shadow_resources: Vec<ShadowResources>,
+    shadowless_lights: usize,
 }
 
 impl Engine {
     pub fn new(window: Arc<Window>, required_extensions: InstanceExtensions) -> Self {
         let (surface, caps, device, queue, render_pass) =
             engine_init(window.clone(), required_extensions);
-        let allocators = AllocatorCollection::new(device.clone());
         let composite_alpha = caps.supported_composite_alpha.into_iter().next().unwrap();
         let (swapchain, images) = Swapchain::new(
             device.clone(),
@@ -193,16 +406,43 @@ impl Engine {
         )
         .unwrap();
 
-        let dimensions = images[0].extent();
-        let pipelines =
-            PipelineCollection::init(device.clone(), render_pass.clone(), &dimensions[0..2]);
-        let framebuffers =
-            get_framebuffers(allocators.memory.clone(), &images, render_pass.clone());
-        let uniform_buffer = SubbufferAllocator::new(
+        let allocators = Arc::new(AllocatorCollection::new(device.clone()));
+        Engine {
+            surface: Some(surface),
+            swapchain: Some(swapchain),
+            caps: Some(caps),
+            ..Self::from_images(device, queue, render_pass, allocators, images)
+        }
+    }
+
+    pub fn new_headless(dimensions: [u32; 2]) -> Self {
+        let (device, queue, render_pass) = headless_init();
+        let allocators = Arc::new(AllocatorCollection::new(device.clone()));
+        let images = vec![get_offscreen_image(allocators.memory.clone(), dimensions)];
+        Self::from_images(device, queue, render_pass, allocators, images)
+    }
+
+    fn from_images(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        render_pass: Arc<RenderPass>,
+        allocators: Arc<AllocatorCollection>,
+        images: Vec<Arc<Image>>,
+    ) -> Self {
+        let shadow_maps = ShadowMaps::new(device.clone(), allocators.memory.clone());
+        let post_process_render_pass = post_process::get_post_process_render_pass(device.clone());
+        let pipelines = PipelineCollection::init(
+            device.clone(),
+            render_pass.clone(),
+            shadow_maps.render_pass.clone(),
+            post_process_render_pass.clone(),
+        );
+        let framebuffers = get_framebuffers(&images, post_process_render_pass.clone());
+        let instance_buffer = SubbufferAllocator::new(
             allocators.memory.clone(),
             SubbufferAllocatorCreateInfo {
-                buffer_usage: BufferUsage::UNIFORM_BUFFER,
-
+                // also read and written by the culling pass
+                buffer_usage: BufferUsage::VERTEX_BUFFER | BufferUsage::STORAGE_BUFFER,
                 memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
                     | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
                 ..Default::default()
@@ -210,60 +450,250 @@ impl Engine {
         );
         let previous_frame_end = sync::now(device.clone()).boxed();
 
-        let sampler =
-            Sampler::new(device.clone(), SamplerCreateInfo::simple_repeat_linear()).unwrap();
+        let uploader = Uploader::new(
+            device.clone(),
+            queue.clone(),
+            allocators.clone(),
+            pipelines.scene_set_layout(1).clone(),
+            pipelines.environment.clone(),
+        );
+        let environment_sampler = environment::environment_sampler(device.clone());
+        let brdf_lut = environment::compute_brdf_lut(&queue, &allocators, &pipelines).unwrap();
+        let default_environment = environment::uniform_environment(
+            &queue,
+            &allocators,
+            &pipelines.environment,
+            [DEFAULT_RADIANCE; 3],
+        )
+        .unwrap();
         Engine {
-            surface,
-            swapchain,
-            caps,
+            surface: None,
+            swapchain: None,
+            caps: None,
             device,
             queue,
             render_pass,
+            post_process_render_pass,
             pipelines,
             allocators,
             images,
             framebuffers,
+            view_targets: Vec::new(),
             previous_frame_end,
-            uniform_buffer,
-            sampler,
+            instance_buffer,
+            culling_resources: Vec::new(),
+            view_resources: Vec::new(),
+            shadow_resources: Vec::new(),
+            shadowless_lights: 0,
+            uploader,
+            shadow_maps,
+            environment_sampler,
+            brdf_lut,
+            default_environment,
+            exposure: 1.0,
+            tonemapping: Tonemapping::Aces,
+            bloom: None,
+            fxaa: false,
+            color_grading_lut: None,
+            vignette: None,
+            lod_fade: None,
             recreate_swapchain: false,
         }
     }
 
     pub fn resize_window(&mut self, dimensions: [u32; 2]) {
-        let (new_swapchain, new_images) = match self.swapchain.recreate(SwapchainCreateInfo {
-            image_extent: dimensions,
-            ..self.swapchain.create_info()
-        }) {
-            Ok(r) => r,
-            Err(e) => panic!("Failed to recreate swapchain: {:?}", e),
-        };
-        self.swapchain = new_swapchain;
-        self.images = new_images;
-        self.pipelines
-            .recreate(self.device.clone(), self.render_pass.clone(), &dimensions);
-        self.framebuffers = get_framebuffers(
-            self.allocators.memory.clone(),
-            &self.images,
-            self.render_pass.clone(),
-        );
+        match &self.swapchain {
+            Some(swapchain) => {
+                let (new_swapchain, new_images) = match swapchain.recreate(SwapchainCreateInfo {
+                    image_extent: dimensions,
+                    ..swapchain.create_info()
+                }) {
+                    Ok(r) => r,
+                    Err(e) => panic!("Failed to recreate swapchain: {:?}", e),
+                };
+                self.swapchain = Some(new_swapchain);
+                self.images = new_images;
+            }
+            None => {
+                self.images = vec![get_offscreen_image(
+                    self.allocators.memory.clone(),
+                    dimensions,
+                )];
+            }
+        }
+        self.framebuffers = get_framebuffers(&self.images, self.post_process_render_pass.clone());
     }
 
-    fn init_command_buffer(
-        &self,
-        image_index: usize,
-    ) -> AutoCommandBufferBuilder<PrimaryAutoCommandBuffer> {
-        let framebuffer = self.framebuffers[image_index].clone();
+    /// None for engines created with `new_headless`.
+    pub fn surface(&self) -> Option<&Arc<Surface>> {
+        self.surface.as_ref()
+    }
+
+    /// None for engines created with `new_headless`.
+    pub fn swapchain(&self) -> Option<&Arc<Swapchain>> {
+        self.swapchain.as_ref()
+    }
+
+    /// None for engines created with `new_headless`.
+    pub fn caps(&self) -> Option<&SurfaceCapabilities> {
+        self.caps.as_ref()
+    }
+
+    /// Lights casting shadows that the last frame drew without them, as there were more shadow maps
+    /// to draw than `SHADOW_LAYER_COUNT`.
+    pub fn shadowless_lights(&self) -> usize {
+        self.shadowless_lights
+    }
+
+    /// Width divided by height of the rendered images.
+    pub fn aspect_ratio(&self) -> f32 {
+        let [width, height, _] = self.images[0].extent();
+        width as f32 / height as f32
+    }
+
+    /// Size in pixels of the image `target` covers.
+    pub fn target_dimensions(&self, target: &ViewTarget) -> [u32; 2] {
+        match target {
+            ViewTarget::Screen { extent, .. } => {
+                let [width, height, _] = self.images[0].extent();
+                [
+                    ((width as f32 * extent[0]).round() as u32).max(1),
+                    ((height as f32 * extent[1]).round() as u32).max(1),
+                ]
+            }
+            ViewTarget::Texture(texture) => texture.dimensions(),
+        }
+    }
+
+    pub fn msaa(&self) -> bool {
+        self.render_pass.attachments()[0].samples != SampleCount::Sample1
+    }
+
+    /// Copies the last rendered frame back to the host.
+    /// Only meaningful for engines created with `new_headless`.
+    pub fn read_frame(&self) -> RgbaImage {
+        let image = self.images[0].clone();
+        let [width, height, _] = image.extent();
+        let buffer = Buffer::new_slice::<u8>(
+            self.allocators.memory.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                    | MemoryTypeFilter::HOST_RANDOM_ACCESS,
+                ..Default::default()
+            },
+            (width * height * 4) as u64,
+        )
+        .unwrap();
         let mut builder = AutoCommandBufferBuilder::primary(
             &self.allocators.command_buffer,
             self.queue.queue_family_index(),
             CommandBufferUsage::OneTimeSubmit,
         )
         .unwrap();
+        builder
+            .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(image, buffer.clone()))
+            .unwrap();
+        let command_buffer = builder.build().unwrap();
+        let future = sync::now(self.device.clone())
+            .then_execute(self.queue.clone(), command_buffer)
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap();
+        future.wait(None).unwrap();
+        let pixels = buffer.read().unwrap().to_vec();
+        RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+
+    fn acquire_image(&mut self) -> Option<(usize, Box<dyn GpuFuture>)> {
+        let Some(swapchain) = self.swapchain.clone() else {
+            return Some((0, sync::now(self.device.clone()).boxed()));
+        };
+        let (image_i, suboptimal, acquire_future) = match acquire_next_image(swapchain, None) {
+            Ok(r) => (r.0 as usize, r.1, r.2),
+            Err(Validated::Error(VulkanError::OutOfDate)) => {
+                self.recreate_swapchain = true;
+                return None;
+            }
+            Err(e) => panic!("Failed to acquire next image: {:?}", e),
+        };
+        if suboptimal {
+            self.recreate_swapchain = true;
+            return None;
+        }
+        Some((image_i, acquire_future.boxed()))
+    }
+
+    /// Makes `view_targets` match the sizes of `views`, keeping those of the last frame which still do
+    /// and binding them the current `color_grading_lut`.
+    fn prepare_view_targets(&mut self, views: &[View]) {
+        self.view_targets.truncate(views.len());
+        for (i, view) in views.iter().enumerate() {
+            let dimensions = self.target_dimensions(&view.target);
+            if let Some(targets) = self
+                .view_targets
+                .get_mut(i)
+                .filter(|targets| targets.dimensions() == dimensions)
+            {
+                targets.post_process.set_color_grading_lut(
+                    &self.allocators.descriptor_set,
+                    &self.pipelines,
+                    &targets.hdr_image,
+                    self.color_grading_lut.as_ref(),
+                );
+                continue;
+            }
+            let targets = ViewTargets::new(
+                self.device.clone(),
+                &self.allocators,
+                &self.pipelines,
+                dimensions,
+                self.render_pass.clone(),
+                self.post_process_render_pass.clone(),
+                self.color_grading_lut.clone(),
+            );
+            if i < self.view_targets.len() {
+                self.view_targets[i] = targets;
+            } else {
+                self.view_targets.push(targets);
+            }
+        }
+    }
+
+    fn init_command_buffer(&self) -> AutoCommandBufferBuilder<PrimaryAutoCommandBuffer> {
+        AutoCommandBufferBuilder::primary(
+            &self.allocators.command_buffer,
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap()
+    }
+
+    fn begin_render_pass(
+        &self,
+        view: &ViewCamera,
+        targets: &ViewTargets,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let framebuffer = targets.hdr_framebuffer.clone();
+        let far_depth = view.camera.far_depth();
+        // the multisampled scene is resolved to an attachment which isn't cleared
+        let clear_values = if self.msaa() {
+            vec![
+                Some([0.0, 0.0, 0.0, 1.0].into()),
+                None,
+                Some(far_depth.into()),
+            ]
+        } else {
+            vec![Some([0.0, 0.0, 0.0, 1.0].into()), Some(far_depth.into())]
+        };
         builder
             .begin_render_pass(
                 RenderPassBeginInfo {
-                    clear_values: vec![Some([0.0, 0.0, 0.0, 1.0].into()), None, Some(1f32.into())],
+                    clear_values,
                     ..RenderPassBeginInfo::framebuffer(framebuffer)
                 },
                 SubpassBeginInfo {
@@ -271,296 +701,1019 @@ impl Engine {
                     ..Default::default()
                 },
             )
+            .unwrap()
+            .set_viewport(
+                0,
+                [full_viewport(targets.dimensions())].into_iter().collect(),
+            )
             .unwrap();
-        builder
     }
 
-    fn end_command_buffer(
+    /// Ends the scene render pass and post-processes the view into `viewport` of `framebuffer`.
+    fn add_post_process_to_command_buffer(
         &self,
-        mut builder: AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-    ) -> Arc<PrimaryAutoCommandBuffer> {
+        targets: &ViewTargets,
+        framebuffer: Arc<Framebuffer>,
+        viewport: Viewport,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        builder.end_render_pass(Default::default()).unwrap();
+        if let Some(bloom) = self.bloom {
+            self.add_bloom_to_command_buffer(targets, bloom.threshold, builder);
+        }
+        if self.fxaa {
+            self.add_tonemap_to_command_buffer(
+                targets,
+                targets.post_process.ldr_framebuffer.clone(),
+                full_viewport(targets.dimensions()),
+                builder,
+            );
+            builder.end_render_pass(Default::default()).unwrap();
+            self.add_fxaa_to_command_buffer(targets, framebuffer, viewport, builder);
+        } else {
+            self.add_tonemap_to_command_buffer(targets, framebuffer, viewport, builder);
+        }
         builder.end_render_pass(Default::default()).unwrap();
-        builder.build().unwrap()
     }
 
-    fn add_still_primitive_to_command_buffer(
+    /// Blurs the bright parts of the scene down the bloom levels, then adds each level to the larger one.
+    fn add_bloom_to_command_buffer(
         &self,
-        primitive: &Primitive,
-        camera_transform: Transform,
-        item_pos: Subbuffer<[[[f32; 4]; 4]]>,
-        light_position: [f32; 3],
+        targets: &ViewTargets,
+        threshold: f32,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
     ) {
-        let view_proj =
-            camera_transform
-                .reverse()
-                .project_perspective(FRAC_PI_2, 16.0 / 9.0, 0.1, 100.0);
-        let camera_position = camera_transform.translation;
-        let instance_count = item_pos.len() as u32;
-        let vertex_count = primitive.vertex.positions.len() as u32;
-        let vertex_uniform = self.uniform_buffer.allocate_sized().unwrap();
-        *vertex_uniform.write().unwrap() = vertex_shader::UniformBufferObject {
-            view_proj,
-            light_position: light_position.into(),
-            camera_position,
-        };
-        let fragment_uniform = self.uniform_buffer.allocate_sized().unwrap();
-        *fragment_uniform.write().unwrap() = fragment_shader::UniformBufferObject {
-            color: primitive.pbr.color,
-            metalness: primitive.pbr.metalness,
-            roughness: primitive.pbr.roughness,
-        };
-        let layout = self
-            .pipelines
-            .graphic
-            .layout()
-            .set_layouts()
-            .first()
-            .unwrap();
-        let descriptor_set = PersistentDescriptorSet::new(
-            &self.allocators.descriptor_set,
-            layout.clone(),
-            [
-                WriteDescriptorSet::buffer(0, vertex_uniform),
-                WriteDescriptorSet::buffer(1, fragment_uniform),
-                WriteDescriptorSet::image_view_sampler(
-                    3,
-                    primitive.color.image.clone(),
-                    self.sampler.clone(),
-                ),
-                WriteDescriptorSet::image_view_sampler(
-                    4,
-                    primitive.metalness.image.clone(),
-                    self.sampler.clone(),
-                ),
-                WriteDescriptorSet::image_view_sampler(
-                    5,
-                    primitive.normal.image.clone(),
-                    self.sampler.clone(),
-                ),
-            ],
-            [],
-        )
-        .unwrap();
+        let post_process = &targets.post_process;
+        let levels = &post_process.bloom_levels;
+        for (i, (level, descriptor_set)) in
+            levels.iter().zip(&post_process.downsample_sets).enumerate()
+        {
+            let threshold = if i == 0 { threshold } else { 0.0 };
+            builder
+                .push_constants(
+                    self.pipelines.bloom_downsample.layout().clone(),
+                    0,
+                    bloom_downsample_shader::PushConstants { threshold },
+                )
+                .unwrap();
+            self.add_dispatch_to_command_buffer(
+                &self.pipelines.bloom_downsample,
+                descriptor_set.clone(),
+                level,
+                builder,
+            );
+        }
+        for (level, descriptor_set) in levels.iter().zip(&post_process.upsample_sets).rev() {
+            self.add_dispatch_to_command_buffer(
+                &self.pipelines.bloom_upsample,
+                descriptor_set.clone(),
+                level,
+                builder,
+            );
+        }
+    }
+
+    /// Runs `pipeline` once per texel of `target`.
+    fn add_dispatch_to_command_buffer(
+        &self,
+        pipeline: &Arc<ComputePipeline>,
+        descriptor_set: Arc<PersistentDescriptorSet>,
+        target: &ImageView,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let [width, height, _] = target.image().extent();
         builder
-            .bind_pipeline_graphics(self.pipelines.graphic.clone())
+            .bind_pipeline_compute(pipeline.clone())
             .unwrap()
             .bind_descriptor_sets(
-                PipelineBindPoint::Graphics,
-                self.pipelines.graphic.layout().clone(),
+                PipelineBindPoint::Compute,
+                pipeline.layout().clone(),
                 0,
                 descriptor_set,
             )
             .unwrap()
-            .bind_vertex_buffers(
-                0,
-                (
-                    primitive.vertex.positions.clone(),
-                    primitive.vertex.normals.clone(),
-                    primitive.vertex.tangents.clone(),
-                    item_pos,
-                    primitive.color.coordinates.clone(),
-                    primitive.metalness.coordinates.clone(),
-                    primitive.normal.coordinates.clone(),
-                ),
+            .dispatch([
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            ])
+            .unwrap();
+    }
+
+    fn begin_post_process_render_pass(
+        &self,
+        framebuffer: Arc<Framebuffer>,
+        viewport: Viewport,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![None],
+                    ..RenderPassBeginInfo::framebuffer(framebuffer)
+                },
+                SubpassBeginInfo {
+                    contents: SubpassContents::Inline,
+                    ..Default::default()
+                },
             )
             .unwrap()
-            .draw(vertex_count, instance_count, 0, 0)
+            .set_viewport(0, [viewport].into_iter().collect())
             .unwrap();
     }
 
-    fn add_animated_primitive_to_command_buffer(
+    fn add_tonemap_to_command_buffer(
         &self,
-        primitive: &AnimatedPrimitive,
-        camera_transform: Transform,
-        item_pos: Subbuffer<[[[f32; 4]; 4]]>,
-        pose_option: Option<&[Transform]>,
-        light_position: [f32; 3],
+        targets: &ViewTargets,
+        framebuffer: Arc<Framebuffer>,
+        viewport: Viewport,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
     ) {
-        let Some(pose) = pose_option else {
-            return self.add_still_primitive_to_command_buffer(
-                &primitive.primitive,
-                camera_transform,
-                item_pos,
-                light_position,
-                builder,
-            );
-        };
-        let view_proj =
-            camera_transform
-                .reverse()
-                .project_perspective(FRAC_PI_2, 16.0 / 9.0, 0.1, 100.0);
-        let camera_position = camera_transform.translation;
-        let instance_count = item_pos.len() as u32;
-        let pose_buffer = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER,
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            pose.iter().map(|pose| pose.to_homogeneous()),
-        )
-        .unwrap();
-        let vertex_count = primitive.primitive.vertex.positions.len() as u32;
-        let vertex_uniform = self.uniform_buffer.allocate_sized().unwrap();
-        *vertex_uniform.write().unwrap() = animated_vertex_shader::UniformBufferObject {
-            view_proj,
-            light_position: light_position.into(),
-            camera_position,
-            transform_length: pose_buffer.len() as u32 / instance_count,
-        };
-        let fragment_uniform = self.uniform_buffer.allocate_sized().unwrap();
-        *fragment_uniform.write().unwrap() = fragment_shader::UniformBufferObject {
-            color: primitive.primitive.pbr.color,
-            metalness: primitive.primitive.pbr.metalness,
-            roughness: primitive.primitive.pbr.roughness,
-        };
-        let layout = self
-            .pipelines
-            .graphic_animated
-            .layout()
-            .set_layouts()
-            .first()
-            .unwrap();
-        let descriptor_set = PersistentDescriptorSet::new(
-            &self.allocators.descriptor_set,
-            layout.clone(),
-            [
-                WriteDescriptorSet::buffer(0, vertex_uniform),
-                WriteDescriptorSet::buffer(1, fragment_uniform),
-                WriteDescriptorSet::buffer(2, pose_buffer),
-                WriteDescriptorSet::image_view_sampler(
-                    3,
-                    primitive.primitive.color.image.clone(),
-                    self.sampler.clone(),
-                ),
-                WriteDescriptorSet::image_view_sampler(
-                    4,
-                    primitive.primitive.metalness.image.clone(),
-                    self.sampler.clone(),
-                ),
-                WriteDescriptorSet::image_view_sampler(
-                    5,
-                    primitive.primitive.normal.image.clone(),
-                    self.sampler.clone(),
-                ),
-            ],
-            [],
-        )
-        .unwrap();
+        let bloom_intensity = self.bloom.map_or(0.0, |bloom| bloom.intensity);
+        let vignette = self.vignette.unwrap_or(Vignette {
+            intensity: 0.0,
+            smoothness: 1.0,
+        });
+        let pipeline = &self.pipelines.tonemap;
+        self.begin_post_process_render_pass(framebuffer, viewport, builder);
         builder
-            .bind_pipeline_graphics(self.pipelines.graphic_animated.clone())
+            .bind_pipeline_graphics(pipeline.clone())
             .unwrap()
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
-                self.pipelines.graphic_animated.layout().clone(),
+                pipeline.layout().clone(),
                 0,
-                descriptor_set,
+                targets.post_process.tonemap_set.clone(),
             )
             .unwrap()
-            .bind_vertex_buffers(
+            .push_constants(
+                pipeline.layout().clone(),
                 0,
-                (
-                    primitive.primitive.vertex.positions.clone(),
-                    primitive.primitive.vertex.normals.clone(),
-                    primitive.primitive.vertex.tangents.clone(),
-                    item_pos,
-                    primitive.skin.weights.clone(),
-                    primitive.skin.joints.clone(),
-                    primitive.primitive.color.coordinates.clone(),
-                    primitive.primitive.metalness.coordinates.clone(),
-                    primitive.primitive.normal.coordinates.clone(),
-                ),
+                tonemap_fragment_shader::PushConstants {
+                    exposure: self.exposure,
+                    tonemapping: self.tonemapping.shader_id(),
+                    bloom_intensity,
+                    color_grading: self.color_grading_lut.is_some() as u32,
+                    vignette_intensity: vignette.intensity,
+                    vignette_smoothness: vignette.smoothness,
+                },
             )
             .unwrap()
-            .draw(vertex_count, instance_count, 0, 0)
+            .draw(3, 1, 0, 0)
             .unwrap();
     }
-}
 
-impl Drawer for Engine {
-    fn draw(
-        &mut self,
-        camera_transform: Transform,
-        light_position: [f32; 3],
-        display_request: &[DisplayRequest],
+    fn add_fxaa_to_command_buffer(
+        &self,
+        targets: &ViewTargets,
+        framebuffer: Arc<Framebuffer>,
+        viewport: Viewport,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
     ) {
-        let (image_i, suboptimal, acquire_future) =
-            match acquire_next_image(self.swapchain.clone(), None) {
-                Ok(r) => (r.0 as usize, r.1, r.2),
-                Err(Validated::Error(VulkanError::OutOfDate)) => {
-                    self.recreate_swapchain = true;
-                    return;
-                }
-                Err(e) => panic!("Failed to acquire next image: {:?}", e),
-            };
-        if suboptimal {
-            self.recreate_swapchain = true;
-            return;
-        }
-        let mut builder = self.init_command_buffer(image_i);
-        for displayed_item in display_request {
-            match *displayed_item {
-                DisplayRequest::In3D(asset, item_pos, pose_option) => {
-                    let item_pos = Buffer::from_iter(
-                        self.allocators.memory.clone(),
-                        BufferCreateInfo {
-                            usage: BufferUsage::VERTEX_BUFFER,
-                            ..Default::default()
-                        },
-                        AllocationCreateInfo {
-                            memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                                | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                            ..Default::default()
-                        },
-                        item_pos.iter().map(|pos| pos.to_homogeneous()),
-                    )
-                    .unwrap();
-                    match asset {
-                        Asset::Still(still_primitives) => {
-                            for primive in still_primitives.iter() {
-                                self.add_still_primitive_to_command_buffer(
-                                    primive,
-                                    camera_transform,
-                                    item_pos.clone(),
-                                    light_position,
-                                    &mut builder,
-                                );
-                            }
-                        }
-                        Asset::Animated(animated_primitives, _) => {
-                            for primive in animated_primitives.iter() {
-                                self.add_animated_primitive_to_command_buffer(
-                                    primive,
-                                    camera_transform,
-                                    item_pos.clone(),
-                                    pose_option,
-                                    light_position,
-                                    &mut builder,
-                                );
-                            }
-                        }
-                    }
+        let pipeline = &self.pipelines.fxaa;
+        self.begin_post_process_render_pass(framebuffer, viewport, builder);
+        builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                targets.post_process.fxaa_set.clone(),
+            )
+            .unwrap()
+            .draw(3, 1, 0, 0)
+            .unwrap();
+    }
+
+    /// Keeps the instances of `drawn_asset` that `view` may see, on the GPU, and counts them in a draw command per primitive.
+    /// Posed instances are culled with the bounds moved by each of their joints.
+    /// The buffers and descriptor sets are taken from `pool`, reused from frame to frame.
+    fn add_culling_to_command_buffer(
+        &self,
+        drawn_asset: &DrawnAsset,
+        view: &ViewCamera,
+        pool: &mut Vec<CullingResources>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Option<CulledInstances> {
+        let instance_count = drawn_asset.item_pos.len() as u32;
+        if instance_count == 0 {
+            return None;
+        }
+        let index_counts: Vec<_> = match drawn_asset.asset {
+            Asset::Still(primitives) => primitives
+                .iter()
+                .map(|primitive| primitive.vertex.indices.len() as u32)
+                .collect(),
+            Asset::Animated(primitives, _) => primitives
+                .iter()
+                .map(|primitive| primitive.primitive.vertex.indices.len() as u32)
+                .collect(),
+        };
+        if index_counts.is_empty() {
+            return None;
+        }
+        let command_count = index_counts.len() as u64;
+        let pose = drawn_asset.pose.as_deref().unwrap_or(&[]);
+        let pose_length = pose.len().max(1) as u64;
+        let resources = frame_resources::reuse(
+            pool,
+            CullingResources::in_use,
+            |resources| resources.fits(instance_count as u64, pose_length, command_count),
+            || {
+                CullingResources::new(
+                    &self.allocators,
+                    &self.pipelines,
+                    instance_count as u64,
+                    pose_length,
+                    command_count,
+                )
+            },
+        );
+        for (model, pos) in resources
+            .models
+            .write()
+            .unwrap()
+            .iter_mut()
+            .zip(&drawn_asset.item_pos)
+        {
+            *model = pos.to_homogeneous();
+        }
+        for (transform, pose) in resources.poses.write().unwrap().iter_mut().zip(pose) {
+            *transform = pose.to_homogeneous();
+        }
+        for (command, index_count) in resources
+            .commands
+            .write()
+            .unwrap()
+            .iter_mut()
+            .zip(index_counts)
+        {
+            *command = DrawIndexedIndirectCommand {
+                index_count,
+                instance_count: 0,
+                first_index: 0,
+                vertex_offset: 0,
+                first_instance: 0,
+            };
+        }
+        let transform_length = pose.len() as u32 / instance_count;
+        let bounds = drawn_asset.asset.bounds();
+        *resources.culling.write().unwrap() = cull_instances_shader::Culling {
+            planes: view.frustum.planes,
+            bounds_min: bounds.min,
+            instance_count,
+            bounds_max: bounds.max,
+            transform_length,
+        };
+        let posed = drawn_asset.pose.is_some();
+        let culled = CulledInstances {
+            visible: Instances {
+                item_pos: resources
+                    .visible_models
+                    .clone()
+                    .slice(0..instance_count as u64),
+                pose_set: posed.then(|| resources.visible_pose_set.clone()),
+                transform_length,
+                commands: Some(resources.commands.clone().slice(0..command_count)),
+                drawn: 0..instance_count,
+                lod_fade: drawn_asset.lod_fade,
+            },
+            all: Instances {
+                item_pos: resources.models.clone().slice(0..instance_count as u64),
+                pose_set: posed.then(|| resources.pose_set.clone()),
+                transform_length,
+                commands: None,
+                drawn: 0..instance_count,
+                lod_fade: drawn_asset.lod_fade,
+            },
+        };
+        let pipeline = &self.pipelines.cull_instances;
+        builder
+            .bind_pipeline_compute(pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                pipeline.layout().clone(),
+                0,
+                resources.culling_set.clone(),
+            )
+            .unwrap()
+            .dispatch([instance_count.div_ceil(CULLING_WORKGROUP_SIZE), 1, 1])
+            .unwrap();
+        if command_count > 1 {
+            let pipeline = &self.pipelines.spread_instance_count;
+            builder
+                .bind_pipeline_compute(pipeline.clone())
+                .unwrap()
+                .bind_descriptor_sets(
+                    PipelineBindPoint::Compute,
+                    pipeline.layout().clone(),
+                    0,
+                    resources.spread_set.clone(),
+                )
+                .unwrap()
+                .push_constants(
+                    pipeline.layout().clone(),
+                    0,
+                    spread_instance_count_shader::PushConstants {
+                        command_count: command_count as u32,
+                    },
+                )
+                .unwrap()
+                .dispatch([
+                    (command_count as u32 - 1).div_ceil(CULLING_WORKGROUP_SIZE),
+                    1,
+                    1,
+                ])
+                .unwrap();
+        }
+        Some(culled)
+    }
+
+    fn add_asset_to_command_buffer<'a>(
+        &self,
+        drawn_asset: &'a DrawnAsset,
+        culled: &CulledInstances,
+        view: &ViewCamera,
+        view_set: Arc<PersistentDescriptorSet>,
+        blended_draws: &mut Vec<BlendedDraw<'a>>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let primitives: Vec<_> = match drawn_asset.asset {
+            Asset::Still(still_primitives) => {
+                still_primitives.iter().map(DrawnPrimitive::Still).collect()
+            }
+            Asset::Animated(animated_primitives, _) => animated_primitives
+                .iter()
+                .map(DrawnPrimitive::Animated)
+                .collect(),
+        };
+        let bounds = drawn_asset.asset.bounds();
+        let camera_position = Vec3::from(view.camera.transform.translation);
+        for (primitive_index, primitive) in primitives.into_iter().enumerate() {
+            if primitive.lod().level != drawn_asset.lod {
+                continue;
+            }
+            if primitive.pbr().alpha_mode != AlphaMode::Blend {
+                let command = culled.visible.commands.clone().map(|commands| {
+                    commands.slice(primitive_index as u64..primitive_index as u64 + 1)
+                });
+                self.add_primitive_to_command_buffer(
+                    primitive,
+                    view,
+                    Instances {
+                        commands: command,
+                        ..culled.visible.clone()
+                    },
+                    view_set.clone(),
+                    builder,
+                );
+                continue;
+            }
+            // blended instances are sorted one by one on the CPU, and culled there
+            for (i, transform) in (0..).zip(&drawn_asset.item_pos) {
+                // skinned primitives leave their bounds, only the still ones are culled
+                if drawn_asset.pose.is_none() && !view.frustum.intersects(&bounds, transform) {
+                    continue;
                 }
+                let offset = Vec3::from(transform.translation) - camera_position;
+                blended_draws.push(BlendedDraw {
+                    primitive,
+                    instances: Instances {
+                        drawn: i..i + 1,
+                        ..culled.all.clone()
+                    },
+                    distance: offset.dot(offset),
+                });
+            }
+        }
+    }
+
+    fn add_blended_draws_to_command_buffer(
+        &self,
+        mut blended_draws: Vec<BlendedDraw>,
+        view: &ViewCamera,
+        view_set: Arc<PersistentDescriptorSet>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        blended_draws.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+        for blended_draw in blended_draws {
+            self.add_primitive_to_command_buffer(
+                blended_draw.primitive,
+                view,
+                blended_draw.instances,
+                view_set.clone(),
+                builder,
+            );
+        }
+    }
+
+    fn create_instance_buffer(&self, item_pos: &[Transform]) -> Subbuffer<[[[f32; 4]; 4]]> {
+        let buffer = self
+            .instance_buffer
+            .allocate_slice(item_pos.len() as u64)
+            .unwrap();
+        for (model, pos) in buffer.write().unwrap().iter_mut().zip(item_pos) {
+            *model = pos.to_homogeneous();
+        }
+        buffer
+    }
+
+    /// Binds what the scene shaders share for the whole view: the camera, the lights and the environment,
+    /// returned with the set binding the skybox of the environment.
+    /// The sets and their buffers are taken from `pool`, reused from frame to frame.
+    fn view_descriptor_set(
+        &self,
+        view: &ViewCamera,
+        mut lights: Vec<fragment_shader::Light>,
+        shadow_layers: &[[[f32; 4]; 4]],
+        environment: &Environment,
+        pool: &mut Vec<ViewResources>,
+    ) -> (Arc<PersistentDescriptorSet>, Arc<PersistentDescriptorSet>) {
+        // a storage buffer can't be empty, an unlit frame gets a single black light
+        if lights.is_empty() {
+            let black_light = Light::Directional {
+                direction: [0.0, 0.0, 1.0],
+                color: [0.0; 3],
+                intensity: 0.0,
+                cast_shadows: false,
+            };
+            lights.push(shader_light(&black_light, [0; 4]));
+        }
+        let light_count = lights.len() as u64;
+        let resources = frame_resources::reuse(
+            pool,
+            ViewResources::in_use,
+            |resources| resources.fits(light_count, environment),
+            || {
+                ViewResources::new(
+                    &self.allocators,
+                    &self.pipelines,
+                    light_count,
+                    environment,
+                    self.environment_sampler.clone(),
+                    self.brdf_lut.clone(),
+                    &self.shadow_maps,
+                )
+            },
+        );
+        *resources.camera.write().unwrap() = vertex_shader::UniformBufferObject {
+            view_proj: view.camera.view_projection(view.aspect),
+            camera_position: view.camera.transform.translation,
+        };
+        resources.lights.write().unwrap().copy_from_slice(&lights);
+        resources.shadow_layers.write().unwrap()[..shadow_layers.len()]
+            .copy_from_slice(shadow_layers);
+        (resources.set.clone(), resources.skybox_set.clone())
+    }
+
+    /// Draws `drawn_assets` into the shadow maps of `shadow_layers`.
+    /// The poses of the animated ones are written to buffers taken from `pool`, reused from frame to frame.
+    fn add_shadows_to_command_buffer(
+        &self,
+        drawn_assets: &[DrawnAsset],
+        shadow_layers: &[(usize, [[f32; 4]; 4])],
+        pool: &mut Vec<ShadowResources>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        if shadow_layers.is_empty() {
+            return;
+        }
+        // a cross-fading instance casts the shadow of the level it fades out of
+        let casters: Vec<_> = drawn_assets
+            .iter()
+            .filter(|drawn_asset| drawn_asset.lod_fade >= 0.0)
+            .map(|drawn_asset| {
+                let item_pos = self.create_instance_buffer(&drawn_asset.item_pos);
+                let pose = drawn_asset.pose.as_ref().map(|pose| {
+                    let resources = frame_resources::reuse(
+                        pool,
+                        ShadowResources::in_use,
+                        |resources| resources.fits(pose.len() as u64),
+                        || {
+                            ShadowResources::new(
+                                &self.allocators,
+                                &self.pipelines,
+                                pose.len() as u64,
+                            )
+                        },
+                    );
+                    let mut poses = resources.poses.write().unwrap();
+                    for (element, transform) in poses.iter_mut().zip(pose) {
+                        *element = transform.to_homogeneous();
+                    }
+                    let transform_length = (pose.len() / drawn_asset.item_pos.len()) as u32;
+                    (resources.set.clone(), transform_length)
+                });
+                (drawn_asset.asset, drawn_asset.lod, item_pos, pose)
+            })
+            .collect();
+        for &(layer, view_proj) in shadow_layers {
+            builder
+                .begin_render_pass(
+                    RenderPassBeginInfo {
+                        clear_values: vec![Some(1f32.into())],
+                        ..RenderPassBeginInfo::framebuffer(
+                            self.shadow_maps.framebuffers[layer].clone(),
+                        )
+                    },
+                    SubpassBeginInfo {
+                        contents: SubpassContents::Inline,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            for (asset, lod, item_pos, pose) in &casters {
+                self.add_asset_shadow_to_command_buffer(
+                    asset,
+                    *lod,
+                    view_proj,
+                    item_pos.clone(),
+                    pose.clone(),
+                    builder,
+                );
             }
+            builder.end_render_pass(Default::default()).unwrap();
+        }
+    }
+
+    /// `pose` is the descriptor set binding the poses of the instances, with the length of each pose.
+    fn add_asset_shadow_to_command_buffer(
+        &self,
+        asset: &Asset,
+        lod: usize,
+        view_proj: [[f32; 4]; 4],
+        item_pos: Subbuffer<[[[f32; 4]; 4]]>,
+        pose: Option<(Arc<PersistentDescriptorSet>, u32)>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        match (asset, pose) {
+            (Asset::Animated(animated_primitives, _), Some((pose_set, transform_length))) => self
+                .add_animated_shadow_to_command_buffer(
+                    animated_primitives,
+                    lod,
+                    shadow_animated_vertex_shader::PushConstants {
+                        view_proj,
+                        transform_length,
+                    },
+                    item_pos,
+                    pose_set,
+                    builder,
+                ),
+            (Asset::Animated(animated_primitives, _), None) => self
+                .add_still_shadow_to_command_buffer(
+                    animated_primitives
+                        .iter()
+                        .map(|animated_primitive| &animated_primitive.primitive)
+                        .filter(|primitive| primitive.lod.level == lod),
+                    view_proj,
+                    item_pos,
+                    builder,
+                ),
+            (Asset::Still(still_primitives), _) => self.add_still_shadow_to_command_buffer(
+                still_primitives
+                    .iter()
+                    .filter(|primitive| primitive.lod.level == lod),
+                view_proj,
+                item_pos,
+                builder,
+            ),
         }
-        let command_buffer = self.end_command_buffer(builder);
+    }
+
+    fn add_still_shadow_to_command_buffer<'a>(
+        &self,
+        primitives: impl Iterator<Item = &'a Primitive>,
+        view_proj: [[f32; 4]; 4],
+        item_pos: Subbuffer<[[[f32; 4]; 4]]>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let instance_count = item_pos.len() as u32;
+        let pipeline = self.pipelines.shadow(false);
+        builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .unwrap()
+            .push_constants(
+                pipeline.layout().clone(),
+                0,
+                shadow_vertex_shader::PushConstants { view_proj },
+            )
+            .unwrap();
+        for primitive in primitives.filter(|primitive| primitive.casts_shadows()) {
+            let index_count = primitive.vertex.indices.len() as u32;
+            builder
+                .bind_vertex_buffers(0, (primitive.vertex.positions.clone(), item_pos.clone()))
+                .unwrap()
+                .bind_index_buffer(primitive.vertex.indices.clone())
+                .unwrap()
+                .draw_indexed(index_count, instance_count, 0, 0, 0)
+                .unwrap();
+        }
+    }
+
+    fn add_animated_shadow_to_command_buffer(
+        &self,
+        animated_primitives: &[AnimatedPrimitive],
+        lod: usize,
+        push_constants: shadow_animated_vertex_shader::PushConstants,
+        item_pos: Subbuffer<[[[f32; 4]; 4]]>,
+        pose_set: Arc<PersistentDescriptorSet>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let instance_count = item_pos.len() as u32;
+        let pipeline = self.pipelines.shadow(true);
+        builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                pose_set,
+            )
+            .unwrap()
+            .push_constants(pipeline.layout().clone(), 0, push_constants)
+            .unwrap();
+        for animated_primitive in animated_primitives.iter().filter(|animated_primitive| {
+            animated_primitive.primitive.lod.level == lod
+                && animated_primitive.primitive.casts_shadows()
+        }) {
+            let vertex = &animated_primitive.primitive.vertex;
+            let index_count = vertex.indices.len() as u32;
+            builder
+                .bind_vertex_buffers(
+                    0,
+                    (
+                        vertex.positions.clone(),
+                        item_pos.clone(),
+                        animated_primitive.skin.weights.clone(),
+                        animated_primitive.skin.joints.clone(),
+                    ),
+                )
+                .unwrap()
+                .bind_index_buffer(vertex.indices.clone())
+                .unwrap()
+                .draw_indexed(index_count, instance_count, 0, 0, 0)
+                .unwrap();
+        }
+    }
+
+    /// `skybox_set` binds the skybox, see `ViewResources::skybox_set`.
+    fn add_skybox_to_command_buffer(
+        &self,
+        skybox_set: Arc<PersistentDescriptorSet>,
+        view: &ViewCamera,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let camera_rotation = Transform {
+            translation: [0.0; 3],
+            ..view.camera.transform
+        };
+        // an orthographic camera looks in the same direction from every pixel
+        let view_scale = match view.camera.projection {
+            Projection::Perspective { .. } => view.camera.view_scale(view.aspect),
+            Projection::Orthographic { .. } => [0.0; 2],
+        };
+        let pipeline = self.pipelines.skybox(view.camera.reverse_z);
+        builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                skybox_set,
+            )
+            .unwrap()
+            .push_constants(
+                pipeline.layout().clone(),
+                0,
+                skybox_vertex_shader::PushConstants {
+                    camera_rotation: camera_rotation.to_homogeneous(),
+                    view_scale,
+                    far_depth: view.camera.far_depth(),
+                },
+            )
+            .unwrap()
+            .draw(3, 1, 0, 0)
+            .unwrap();
+    }
+
+    fn add_primitive_to_command_buffer(
+        &self,
+        primitive: DrawnPrimitive,
+        view: &ViewCamera,
+        instances: Instances,
+        view_set: Arc<PersistentDescriptorSet>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        match primitive {
+            DrawnPrimitive::Still(primitive) => self.add_still_primitive_to_command_buffer(
+                primitive,
+                view,
+                instances,
+                view_set.clone(),
+                builder,
+            ),
+            DrawnPrimitive::Animated(primitive) => self.add_animated_primitive_to_command_buffer(
+                primitive,
+                view,
+                instances,
+                view_set.clone(),
+                builder,
+            ),
+        }
+    }
+
+    fn add_still_primitive_to_command_buffer(
+        &self,
+        primitive: &Primitive,
+        view: &ViewCamera,
+        instances: Instances,
+        view_set: Arc<PersistentDescriptorSet>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let index_count = primitive.vertex.indices.len() as u32;
+        let pipeline = self.pipelines.graphic(PipelineKey {
+            animated: false,
+            alpha_mode: primitive.pbr.alpha_mode,
+            double_sided: primitive.pbr.double_sided,
+            reverse_z: view.camera.reverse_z,
+        });
+        builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                vec![view_set, primitive.material.clone()],
+            )
+            .unwrap()
+            .push_constants(
+                pipeline.layout().clone(),
+                0,
+                scene_push_constants(&primitive.pbr, 0, instances.lod_fade),
+            )
+            .unwrap()
+            .bind_vertex_buffers(
+                0,
+                (
+                    primitive.vertex.positions.clone(),
+                    primitive.vertex.normals.clone(),
+                    primitive.vertex.tangents.clone(),
+                    instances.item_pos,
+                    primitive.color.coordinates.clone(),
+                    primitive.metalness.coordinates.clone(),
+                    primitive.normal.coordinates.clone(),
+                    primitive.occlusion.coordinates.clone(),
+                    primitive.emissive.coordinates.clone(),
+                ),
+            )
+            .unwrap()
+            .bind_index_buffer(primitive.vertex.indices.clone())
+            .unwrap();
+        add_draw_to_command_buffer(index_count, instances.drawn, instances.commands, builder);
+    }
+
+    fn add_animated_primitive_to_command_buffer(
+        &self,
+        primitive: &AnimatedPrimitive,
+        view: &ViewCamera,
+        instances: Instances,
+        view_set: Arc<PersistentDescriptorSet>,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) {
+        let Some(pose_set) = instances.pose_set.clone() else {
+            return self.add_still_primitive_to_command_buffer(
+                &primitive.primitive,
+                view,
+                instances,
+                view_set,
+                builder,
+            );
+        };
+        let index_count = primitive.primitive.vertex.indices.len() as u32;
+        let pipeline = self.pipelines.graphic(PipelineKey {
+            animated: true,
+            alpha_mode: primitive.primitive.pbr.alpha_mode,
+            double_sided: primitive.primitive.pbr.double_sided,
+            reverse_z: view.camera.reverse_z,
+        });
+        builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .unwrap()
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                vec![view_set, primitive.primitive.material.clone(), pose_set],
+            )
+            .unwrap()
+            .push_constants(
+                pipeline.layout().clone(),
+                0,
+                scene_push_constants(
+                    &primitive.primitive.pbr,
+                    instances.transform_length,
+                    instances.lod_fade,
+                ),
+            )
+            .unwrap()
+            .bind_vertex_buffers(
+                0,
+                (
+                    primitive.primitive.vertex.positions.clone(),
+                    primitive.primitive.vertex.normals.clone(),
+                    primitive.primitive.vertex.tangents.clone(),
+                    instances.item_pos,
+                    primitive.skin.weights.clone(),
+                    primitive.skin.joints.clone(),
+                    primitive.primitive.color.coordinates.clone(),
+                    primitive.primitive.metalness.coordinates.clone(),
+                    primitive.primitive.normal.coordinates.clone(),
+                    primitive.primitive.occlusion.coordinates.clone(),
+                    primitive.primitive.emissive.coordinates.clone(),
+                ),
+            )
+            .unwrap()
+            .bind_index_buffer(primitive.primitive.vertex.indices.clone())
+            .unwrap();
+        add_draw_to_command_buffer(index_count, instances.drawn, instances.commands, builder);
+    }
+}
+
+impl<'a> DrawnPrimitive<'a> {
+    fn pbr(self) -> &'a PBRFactors {
+        match self {
+            DrawnPrimitive::Still(primitive) => &primitive.pbr,
+            DrawnPrimitive::Animated(primitive) => &primitive.primitive.pbr,
+        }
+    }
+
+    fn lod(self) -> Lod {
+        match self {
+            DrawnPrimitive::Still(primitive) => primitive.lod,
+            DrawnPrimitive::Animated(primitive) => primitive.primitive.lod,
+        }
+    }
+}
+
+impl Drawer for Engine {
+    fn draw_views(&mut self, views: &[View], lights: &[Light], display_request: &[DisplayRequest]) {
+        let Some((image_i, acquire_future)) = self.acquire_image() else {
+            return;
+        };
+        self.prepare_view_targets(views);
+        let displayed_environment = display_request.iter().find_map(|request| match request {
+            DisplayRequest::Environment(environment) => Some(*environment),
+            _ => None,
+        });
+        let environment = displayed_environment.unwrap_or(&self.default_environment);
+        let drawn_assets = drawn_assets(display_request);
+        let mut builder = self.init_command_buffer();
+        // the parts of the screen no view covers stay black
+        builder
+            .clear_color_image(ClearColorImageInfo::image(self.images[image_i].clone()))
+            .unwrap();
+        let view_cameras: Vec<_> = self
+            .view_targets
+            .iter()
+            .zip(views)
+            .map(|(targets, view)| {
+                let [width, height] = targets.dimensions();
+                let aspect = width as f32 / height as f32;
+                ViewCamera {
+                    camera: view.camera,
+                    aspect,
+                    frustum: view.camera.frustum(aspect),
+                }
+            })
+            .collect();
+        let all_view_assets: Vec<_> = views
+            .iter()
+            .map(|view| select_lods(&drawn_assets, &view.camera, self.lod_fade))
+            .collect();
+        let shadows = prepare_shadows(lights, &view_cameras);
+        self.shadowless_lights = shadows.shadowless_lights;
+        let mut shadow_resources = mem::take(&mut self.shadow_resources);
+        for group in &shadows.groups {
+            let light_assets;
+            let casters = match &group.casters {
+                ShadowCasters::View(view_index) => &all_view_assets[*view_index],
+                ShadowCasters::Light(camera) => {
+                    light_assets = light_casters(&drawn_assets, camera, self.lod_fade);
+                    &light_assets
+                }
+            };
+            self.add_shadows_to_command_buffer(
+                casters,
+                &shadows.group_layers(group),
+                &mut shadow_resources,
+                &mut builder,
+            );
+        }
+        self.shadow_resources = shadow_resources;
+        let mut culling_resources = mem::take(&mut self.culling_resources);
+        let mut culling_count = 0;
+        let mut view_resources = mem::take(&mut self.view_resources);
+        view_resources.resize_with(views.len(), Vec::new);
+        for (view_index, (view, targets)) in views.iter().zip(&self.view_targets).enumerate() {
+            let [width, height] = targets.dimensions();
+            let view_camera = view_cameras[view_index];
+            let view_assets = &all_view_assets[view_index];
+            let (view_set, skybox_set) = self.view_descriptor_set(
+                &view_camera,
+                shadows.view_lights[view_index].clone(),
+                &shadows.layers,
+                environment,
+                &mut view_resources[view_index],
+            );
+            // dispatched before the render pass, which draws what they leave
+            let culled_instances: Vec<_> = view_assets
+                .iter()
+                .map(|drawn_asset| {
+                    if culling_resources.len() == culling_count {
+                        culling_resources.push(Vec::new());
+                    }
+                    culling_count += 1;
+                    self.add_culling_to_command_buffer(
+                        drawn_asset,
+                        &view_camera,
+                        &mut culling_resources[culling_count - 1],
+                        &mut builder,
+                    )
+                })
+                .collect();
+            self.begin_render_pass(&view_camera, targets, &mut builder);
+            let mut blended_draws = Vec::new();
+            for (drawn_asset, culled) in view_assets.iter().zip(&culled_instances) {
+                let Some(culled) = culled else {
+                    continue;
+                };
+                self.add_asset_to_command_buffer(
+                    drawn_asset,
+                    culled,
+                    &view_camera,
+                    view_set.clone(),
+                    &mut blended_draws,
+                    &mut builder,
+                );
+            }
+            // drawn on the far plane after the opaque geometry, and behind the blended one
+            if displayed_environment.is_some() {
+                self.add_skybox_to_command_buffer(skybox_set, &view_camera, &mut builder);
+            }
+            self.add_blended_draws_to_command_buffer(
+                blended_draws,
+                &view_camera,
+                view_set,
+                &mut builder,
+            );
+            let (framebuffer, viewport) = match &view.target {
+                ViewTarget::Screen { offset, .. } => {
+                    let [screen_width, screen_height, _] = self.images[0].extent();
+                    let viewport = Viewport {
+                        offset: [
+                            (screen_width as f32 * offset[0]).round(),
+                            (screen_height as f32 * offset[1]).round(),
+                        ],
+                        extent: [width as f32, height as f32],
+                        depth_range: 0.0..=1.0,
+                    };
+                    (self.framebuffers[image_i].clone(), viewport)
+                }
+                ViewTarget::Texture(texture) => {
+                    (texture.framebuffer.clone(), full_viewport([width, height]))
+                }
+            };
+            self.add_post_process_to_command_buffer(targets, framebuffer, viewport, &mut builder);
+        }
+        // pools of the dispatches this frame no longer makes are dropped
+        culling_resources.truncate(culling_count);
+        self.culling_resources = culling_resources;
+        self.view_resources = view_resources;
+        let command_buffer = builder.build().unwrap();
         self.previous_frame_end.cleanup_finished();
         let mut temp_future = sync::now(self.device.clone()).boxed();
         mem::swap(&mut temp_future, &mut self.previous_frame_end);
         let future = temp_future
             .join(acquire_future)
             .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
+            .unwrap();
+        let Some(swapchain) = self.swapchain.clone() else {
+            future
+                .then_signal_fence_and_flush()
+                .expect("Failed to flush future")
+                .wait(None)
+                .unwrap();
+            return;
+        };
+        let future = future
             .then_swapchain_present(
                 self.queue.clone(),
-                SwapchainPresentInfo::swapchain_image_index(self.swapchain.clone(), image_i as u32),
+                SwapchainPresentInfo::swapchain_image_index(swapchain, image_i as u32),
             )
             .then_signal_fence_and_flush();
 
@@ -570,6 +1723,378 @@ impl Drawer for Engine {
         }
         self.previous_frame_end = future.expect("Failed to flush future").boxed();
     }
+
+    fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure;
+    }
+
+    fn set_tonemapping(&mut self, tonemapping: Tonemapping) {
+        self.tonemapping = tonemapping;
+    }
+
+    fn set_msaa(&mut self, msaa: bool) {
+        if msaa == self.msaa() {
+            return;
+        }
+        self.render_pass = get_render_pass(self.device.clone(), msaa);
+        self.pipelines
+            .recreate(self.device.clone(), self.render_pass.clone());
+        // made again with the new render pass by the next frame
+        self.view_targets.clear();
+    }
+
+    fn set_bloom(&mut self, bloom: Option<Bloom>) {
+        self.bloom = bloom;
+    }
+
+    fn set_fxaa(&mut self, fxaa: bool) {
+        self.fxaa = fxaa;
+    }
+
+    fn set_color_grading_lut(&mut self, color_grading_lut: Option<Arc<ImageView>>) {
+        self.color_grading_lut = color_grading_lut;
+    }
+
+    fn set_vignette(&mut self, vignette: Option<Vignette>) {
+        self.vignette = vignette;
+    }
+
+    fn set_lod_fade(&mut self, lod_fade: Option<f32>) {
+        self.lod_fade = lod_fade;
+    }
+}
+
+fn drawn_assets<'a>(display_request: &[DisplayRequest<'a>]) -> Vec<DrawnAsset<'a>> {
+    let mut drawn_assets = Vec::new();
+    for displayed_item in display_request {
+        match *displayed_item {
+            DisplayRequest::In3D(asset, item_pos, pose) => drawn_assets.push(DrawnAsset {
+                asset,
+                item_pos: item_pos.to_vec(),
+                pose: pose.map(<[Transform]>::to_vec),
+                lod: 0,
+                lod_fade: 0.0,
+            }),
+            DisplayRequest::Scene(scene, item_pos) => {
+                for (asset, asset_transform) in scene.assets(Transform::new()) {
+                    // every instance of the scene shares the pose of the node's animator
+                    let pose = match asset {
+                        Asset::Animated(_, animator) => {
+                            let pose = animator.compute_transforms();
+                            Some(pose.repeat(item_pos.len()))
+                        }
+                        Asset::Still(_) => None,
+                    };
+                    drawn_assets.push(DrawnAsset {
+                        asset,
+                        item_pos: item_pos
+                            .iter()
+                            .map(|pos| pos.compose(&asset_transform))
+                            .collect(),
+                        pose,
+                        lod: 0,
+                        lod_fade: 0.0,
+                    });
+                }
+            }
+            DisplayRequest::Environment(_) => (),
+        }
+    }
+    drawn_assets
+}
+
+/// Splits the instances of each asset by the level of detail `camera` sees them at,
+/// the cross-fading ones are drawn one by one.
+fn select_lods<'a>(
+    drawn_assets: &[DrawnAsset<'a>],
+    camera: &Camera,
+    fade_band: Option<f32>,
+) -> Vec<DrawnAsset<'a>> {
+    let mut view_assets = Vec::new();
+    for drawn_asset in drawn_assets {
+        let asset = drawn_asset.asset;
+        let screen_coverages = asset.screen_coverages();
+        if screen_coverages
+            .iter()
+            .all(|&screen_coverage| screen_coverage <= 0.0)
+        {
+            view_assets.push(DrawnAsset {
+                asset,
+                item_pos: drawn_asset.item_pos.clone(),
+                pose: drawn_asset.pose.clone(),
+                lod: 0,
+                lod_fade: 0.0,
+            });
+            continue;
+        }
+        let bounds = asset.bounds();
+        let transform_length = drawn_asset
+            .pose
+            .as_ref()
+            .map_or(0, |pose| pose.len() / drawn_asset.item_pos.len());
+        let mut levels: Vec<_> = (0..screen_coverages.len())
+            .map(|lod| DrawnAsset {
+                asset,
+                item_pos: Vec::new(),
+                pose: drawn_asset.pose.as_ref().map(|_| Vec::new()),
+                lod,
+                lod_fade: 0.0,
+            })
+            .collect();
+        for (i, transform) in drawn_asset.item_pos.iter().enumerate() {
+            let coverage = camera.screen_coverage(&bounds, transform);
+            for draw in lod::select(&screen_coverages, coverage, fade_band) {
+                let target = if draw.fade == 0.0 {
+                    &mut levels[draw.level]
+                } else {
+                    view_assets.push(DrawnAsset {
+                        asset,
+                        item_pos: Vec::new(),
+                        pose: drawn_asset.pose.as_ref().map(|_| Vec::new()),
+                        lod: draw.level,
+                        lod_fade: draw.fade,
+                    });
+                    view_assets.last_mut().unwrap()
+                };
+                target.item_pos.push(*transform);
+                if let (Some(target_pose), Some(pose)) = (&mut target.pose, &drawn_asset.pose) {
+                    target_pose
+                        .extend_from_slice(&pose[i * transform_length..(i + 1) * transform_length]);
+                }
+            }
+        }
+        view_assets.extend(
+            levels
+                .into_iter()
+                .filter(|level| !level.item_pos.is_empty()),
+        );
+    }
+    view_assets
+}
+
+// `transform_length` is the number of joints of each instance, 0 for still primitives
+fn scene_push_constants(
+    pbr: &PBRFactors,
+    transform_length: u32,
+    lod_fade: f32,
+) -> fragment_shader::PushConstants {
+    fragment_shader::PushConstants {
+        color: pbr.color,
+        emissive: pbr.emissive,
+        metalness: pbr.metalness,
+        roughness: pbr.roughness,
+        occlusion_strength: pbr.occlusion_strength,
+        alpha_cutoff: pbr.alpha_cutoff,
+        transform_length,
+        lod_fade,
+    }
+}
+
+/// Draws the `drawn` instances, or as many as the culling pass counted in `command` when given.
+fn add_draw_to_command_buffer(
+    index_count: u32,
+    drawn: Range<u32>,
+    command: Option<Subbuffer<[DrawIndexedIndirectCommand]>>,
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+) {
+    match command {
+        Some(command) => builder.draw_indexed_indirect(command).unwrap(),
+        None => builder
+            .draw_indexed(index_count, drawn.end - drawn.start, 0, 0, drawn.start)
+            .unwrap(),
+    };
+}
+
+/// Converts the lights for the fragment shader and lists the view-projections of their shadow maps.
+/// Shadow maps drawn once per frame and shared by its views.
+struct FrameShadows {
+    /// View-projection of each layer of the shadow maps.
+    layers: Vec<[[f32; 4]; 4]>,
+    /// Layers drawn with the same shadow casters.
+    groups: Vec<ShadowGroup>,
+    /// Lights of each view, pointing at their layers.
+    view_lights: Vec<Vec<fragment_shader::Light>>,
+    /// Lights casting shadows that didn't fit in the layers.
+    shadowless_lights: usize,
+}
+
+struct ShadowGroup {
+    layers: Vec<usize>,
+    casters: ShadowCasters,
+}
+
+enum ShadowCasters {
+    /// The levels of detail drawn by a view, for the cascades fitted to its camera.
+    View(usize),
+    /// The levels of detail seen by the camera of the light, for the lights that don't depend on the views.
+    Light(Camera),
+}
+
+impl FrameShadows {
+    /// Adds a layer per light camera, returns where the light finds them or `None` when they don't all fit.
+    /// The cameras of a view's cascades cast the shadows of `view`, the others select their own casters.
+    fn add_layers(&mut self, light_cameras: Vec<Camera>, view: Option<usize>) -> Option<[i32; 4]> {
+        let first_layer = self.layers.len();
+        if first_layer + light_cameras.len() > SHADOW_LAYER_COUNT as usize {
+            return None;
+        }
+        let layer_count = light_cameras.len();
+        for light_camera in light_cameras {
+            let layer = self.layers.len();
+            self.layers.push(light_camera.view_projection(1.0));
+            let view_group = view.and_then(|view| {
+                self.groups.iter_mut().find(
+                    |group| matches!(group.casters, ShadowCasters::View(other) if other == view),
+                )
+            });
+            match view_group {
+                Some(group) => group.layers.push(layer),
+                None => self.groups.push(ShadowGroup {
+                    layers: vec![layer],
+                    casters: match view {
+                        Some(view) => ShadowCasters::View(view),
+                        None => ShadowCasters::Light(light_camera),
+                    },
+                }),
+            }
+        }
+        Some([first_layer as i32, layer_count as i32, 0, 0])
+    }
+
+    /// Layers of `group`, with their view-projections.
+    fn group_layers(&self, group: &ShadowGroup) -> Vec<(usize, [[f32; 4]; 4])> {
+        group
+            .layers
+            .iter()
+            .map(|&layer| (layer, self.layers[layer]))
+            .collect()
+    }
+}
+
+/// Only the cascades of directional lights depend on the camera, they are drawn once per distinct
+/// camera while the other shadows are drawn once for all the views.
+fn prepare_shadows(lights: &[Light], view_cameras: &[ViewCamera]) -> FrameShadows {
+    let view_projections: Vec<_> = view_cameras
+        .iter()
+        .map(|view| view.camera.view_projection(view.aspect))
+        .collect();
+    // first view seeing through the same camera as each view
+    let camera_views: Vec<_> = view_projections
+        .iter()
+        .map(|view_proj| {
+            view_projections
+                .iter()
+                .position(|other| other == view_proj)
+                .unwrap()
+        })
+        .collect();
+    let mut shadows = FrameShadows {
+        layers: Vec::new(),
+        groups: Vec::new(),
+        view_lights: vec![Vec::new(); view_cameras.len()],
+        shadowless_lights: 0,
+    };
+    for light in lights {
+        let mut view_shadows = vec![[0; 4]; view_cameras.len()];
+        let mut fits = true;
+        if let Light::Directional { .. } = light {
+            for (view_index, view) in view_cameras.iter().enumerate() {
+                let source = camera_views[view_index];
+                if source < view_index {
+                    view_shadows[view_index] = view_shadows[source];
+                    continue;
+                }
+                let light_cameras = shadow::light_cameras(light, &view.camera, view.aspect);
+                match shadows.add_layers(light_cameras, Some(view_index)) {
+                    Some(shadow) => view_shadows[view_index] = shadow,
+                    None => fits = false,
+                }
+            }
+        } else if let Some(view) = view_cameras.first() {
+            let light_cameras = shadow::light_cameras(light, &view.camera, view.aspect);
+            match shadows.add_layers(light_cameras, None) {
+                Some(shadow) => view_shadows.fill(shadow),
+                None => fits = false,
+            }
+        }
+        if !fits {
+            shadows.shadowless_lights += 1;
+        }
+        for (view_lights, shadow) in shadows.view_lights.iter_mut().zip(view_shadows) {
+            view_lights.push(shader_light(light, shadow));
+        }
+    }
+    shadows
+}
+
+/// The levels of detail seen by `camera`, the camera of a light, without the still instances out of its frustum.
+fn light_casters<'a>(
+    drawn_assets: &[DrawnAsset<'a>],
+    camera: &Camera,
+    fade_band: Option<f32>,
+) -> Vec<DrawnAsset<'a>> {
+    let frustum = camera.frustum(1.0);
+    let mut casters = select_lods(drawn_assets, camera, fade_band);
+    for caster in &mut casters {
+        // skinned instances leave their bounds, only the still ones are culled
+        if caster.pose.is_none() {
+            let bounds = caster.asset.bounds();
+            caster
+                .item_pos
+                .retain(|transform| frustum.intersects(&bounds, transform));
+        }
+    }
+    casters.retain(|caster| !caster.item_pos.is_empty());
+    casters
+}
+
+fn shader_light(light: &Light, shadow: [i32; 4]) -> fragment_shader::Light {
+    let (kind, position, direction, color, intensity, range, cone) = match *light {
+        Light::Point {
+            position,
+            color,
+            intensity,
+            range,
+        } => (0.0, position, [0.0; 3], color, intensity, range, [0.0; 4]),
+        Light::Spot {
+            position,
+            direction,
+            color,
+            intensity,
+            range,
+            inner_cone_angle,
+            outer_cone_angle,
+            ..
+        } => {
+            let scale = 1.0 / (inner_cone_angle.cos() - outer_cone_angle.cos()).max(0.001);
+            let offset = -outer_cone_angle.cos() * scale;
+            (
+                1.0,
+                position,
+                direction,
+                color,
+                intensity,
+                range,
+                [scale, offset, 0.0, 0.0],
+            )
+        }
+        Light::Directional {
+            direction,
+            color,
+            intensity,
+            ..
+        } => (2.0, [0.0; 3], direction, color, intensity, None, [0.0; 4]),
+    };
+    let [x, y, z] = position;
+    let [dx, dy, dz]: [f32; 3] = Vec3::from(direction).normalize_or_zero().into();
+    let [r, g, b] = color;
+    fragment_shader::Light {
+        position_range: [x, y, z, range.unwrap_or(0.0)],
+        direction_kind: [dx, dy, dz, kind],
+        color_intensity: [r, g, b, intensity],
+        cone,
+        shadow,
+    }
 }
 
 fn engine_init(
@@ -582,26 +2107,54 @@ fn engine_init(
     Arc<Queue>,
     Arc<RenderPass>,
 ) {
-    let library = VulkanLibrary::new().unwrap();
-    let instance = Instance::new(
-        library,
-        InstanceCreateInfo {
-            flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
-            enabled_extensions: required_extensions,
-            ..Default::default()
-        },
-    )
-    .expect("failed to create instance");
+    let instance = create_instance(required_extensions);
     let device_extensions = DeviceExtensions {
         khr_swapchain: true,
         ..DeviceExtensions::empty()
     };
     let surface = Surface::from_window(instance.clone(), window).unwrap();
     let (physical_device, queue_family_id) =
-        select_physical_device(&instance, surface.clone(), &device_extensions);
+        select_physical_device(&instance, Some(&surface), &device_extensions);
     let caps = physical_device
         .surface_capabilities(&surface, Default::default())
         .expect("failed to get surface capabilities");
+    let (device, queue) = create_device(physical_device, queue_family_id, device_extensions);
+    let render_pass = get_render_pass(device.clone(), true);
+    (surface, caps, device, queue, render_pass)
+}
+
+fn headless_init() -> (Arc<Device>, Arc<Queue>, Arc<RenderPass>) {
+    let instance = create_instance(InstanceExtensions::empty());
+    let device_extensions = DeviceExtensions::empty();
+    let (physical_device, queue_family_id) =
+        select_physical_device(&instance, None, &device_extensions);
+    let (device, queue) = create_device(physical_device, queue_family_id, device_extensions);
+    let render_pass = get_render_pass(device.clone(), true);
+    (device, queue, render_pass)
+}
+
+fn create_instance(required_extensions: InstanceExtensions) -> Arc<Instance> {
+    let library = VulkanLibrary::new().unwrap();
+    Instance::new(
+        library,
+        InstanceCreateInfo {
+            flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
+            enabled_extensions: required_extensions,
+            ..Default::default()
+        },
+    )
+    .expect("failed to create instance")
+}
+
+fn create_device(
+    physical_device: Arc<PhysicalDevice>,
+    queue_family_id: u32,
+    device_extensions: DeviceExtensions,
+) -> (Arc<Device>, Arc<Queue>) {
+    // textures seen at grazing angles are filtered anisotropically where supported
+    let sampler_anisotropy = physical_device.supported_features().sampler_anisotropy;
+    // block compressed textures are uploaded as they are where supported
+    let texture_compression_bc = physical_device.supported_features().texture_compression_bc;
     let (device, mut queues) = Device::new(
         physical_device,
         DeviceCreateInfo {
@@ -612,6 +2165,8 @@ fn engine_init(
             enabled_extensions: device_extensions,
             enabled_features: Features {
                 robust_buffer_access: true,
+                sampler_anisotropy,
+                texture_compression_bc,
                 ..Features::empty()
             },
             ..Default::default()
@@ -619,13 +2174,12 @@ fn engine_init(
     )
     .expect("failed to create device");
     let queue = queues.next().unwrap();
-    let render_pass = get_render_pass(device.clone());
-    (surface, caps, device, queue, render_pass)
+    (device, queue)
 }
 
 fn select_physical_device(
     instance: &Arc<Instance>,
-    surface: Arc<Surface>,
+    surface: Option<&Arc<Surface>>,
     device_extensions: &DeviceExtensions,
 ) -> (Arc<PhysicalDevice>, u32) {
     let (physical_device, queue_family) = instance
@@ -638,7 +2192,9 @@ fn select_physical_device(
                 .enumerate()
                 .position(|(i, q)| {
                     q.queue_flags.intersects(QueueFlags::GRAPHICS)
-                        && p.surface_support(i as u32, &surface).unwrap_or(false)
+                        && surface.is_none_or(|surface| {
+                            p.surface_support(i as u32, surface).unwrap_or(false)
+                        })
                 })
                 .map(|i| (p, i as u32))
         })
@@ -654,18 +2210,42 @@ fn select_physical_device(
     (physical_device, queue_family)
 }
 
-fn get_render_pass(device: Arc<Device>) -> Arc<RenderPass> {
+fn get_render_pass(device: Arc<Device>, msaa: bool) -> Arc<RenderPass> {
+    if !msaa {
+        return vulkano::single_pass_renderpass!(
+            device,
+            attachments: {
+                hdr: {
+                    format: HDR_FORMAT,
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: Store,
+                },
+                depth_stencil: {
+                    format: DEPTH_FORMAT,
+                    samples: 1,
+                    load_op: Clear,
+                    store_op: DontCare,
+                }
+            },
+            pass: {
+                color: [hdr],
+                depth_stencil: {depth_stencil}
+            }
+        )
+        .unwrap();
+    }
     vulkano::single_pass_renderpass!(
         device,
         attachments: {
             intermediary: {
-                format: IMAGE_FORMAT,
+                format: HDR_FORMAT,
                 samples: 4,
                 load_op: Clear,
                 store_op: DontCare,
             },
-            color: {
-                format: IMAGE_FORMAT,
+            hdr: {
+                format: HDR_FORMAT,
                 samples: 1,
                 load_op: DontCare,
                 store_op: Store,
@@ -679,50 +2259,34 @@ fn get_render_pass(device: Arc<Device>) -> Arc<RenderPass> {
         },
         pass: {
             color: [intermediary],
-            color_resolve: [color],
+            color_resolve: [hdr],
             depth_stencil: {depth_stencil}
         }
     )
     .unwrap()
 }
 
-fn get_framebuffers(
+fn get_offscreen_image(
     memory_allocator: Arc<dyn MemoryAllocator>,
-    images: &[Arc<Image>],
-    render_pass: Arc<RenderPass>,
-) -> Vec<Arc<Framebuffer>> {
-    let intermediary = ImageView::new_default(
-        Image::new(
-            memory_allocator.clone(),
-            ImageCreateInfo {
-                image_type: ImageType::Dim2d,
-                format: images[0].format(),
-                extent: images[0].extent(),
-                usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
-                samples: SampleCount::Sample4,
-                ..Default::default()
-            },
-            AllocationCreateInfo::default(),
-        )
-        .unwrap(),
-    )
-    .unwrap();
-    let depth_buffer = ImageView::new_default(
-        Image::new(
-            memory_allocator,
-            ImageCreateInfo {
-                image_type: ImageType::Dim2d,
-                format: DEPTH_FORMAT,
-                extent: images[0].extent(),
-                usage: ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
-                samples: SampleCount::Sample4,
-                ..Default::default()
-            },
-            AllocationCreateInfo::default(),
-        )
-        .unwrap(),
+    dimensions: [u32; 2],
+) -> Arc<Image> {
+    Image::new(
+        memory_allocator,
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format: IMAGE_FORMAT,
+            extent: [dimensions[0], dimensions[1], 1],
+            usage: ImageUsage::COLOR_ATTACHMENT
+                | ImageUsage::TRANSFER_SRC
+                | ImageUsage::TRANSFER_DST,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
     )
-    .unwrap();
+    .unwrap()
+}
+
+fn get_framebuffers(images: &[Arc<Image>], render_pass: Arc<RenderPass>) -> Vec<Arc<Framebuffer>> {
     images
         .iter()
         .map(|image| {
@@ -730,7 +2294,7 @@ fn get_framebuffers(
             Framebuffer::new(
                 render_pass.clone(),
                 FramebufferCreateInfo {
-                    attachments: vec![intermediary.clone(), view, depth_buffer.clone()],
+                    attachments: vec![view],
                     ..Default::default()
                 },
             )
@@ -738,3 +2302,11 @@ fn get_framebuffers(
         })
         .collect::<Vec<_>>()
 }
+
+fn full_viewport(dimensions: [u32; 2]) -> Viewport {
+    Viewport {
+        offset: [0.0, 0.0],
+        extent: [dimensions[0] as f32, dimensions[1] as f32],
+        depth_range: 0.0..=1.0,
+    }
+}
@@ -0,0 +1,384 @@
+use std::{f32::consts::PI, sync::Arc};
+
+use vulkano::{
+    command_buffer::{
+        AutoCommandBufferBuilder, ClearColorImageInfo, CommandBufferUsage, PrimaryAutoCommandBuffer,
+    },
+    descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet},
+    device::{Device, Queue},
+    format::{ClearColorValue, Format},
+    image::{
+        sampler::{
+            Filter, Sampler, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode,
+            LOD_CLAMP_NONE,
+        },
+        view::{ImageView, ImageViewCreateInfo, ImageViewType},
+        Image, ImageAspects, ImageCreateFlags, ImageCreateInfo, ImageSubresourceRange, ImageType,
+        ImageUsage,
+    },
+    memory::allocator::AllocationCreateInfo,
+    pipeline::{ComputePipeline, Pipeline, PipelineBindPoint},
+    sync::{self, GpuFuture},
+};
+
+use crate::graphics::{
+    allocators::AllocatorCollection,
+    load_error::LoadError,
+    pipeline::PipelineCollection,
+    shaders::prefilter_shader,
+    texture::{generate_mipmaps, mip_level_count},
+};
+
+pub const ENVIRONMENT_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+/// Radiance of the environment used when none is displayed, a dim uniform gray.
+pub const DEFAULT_RADIANCE: f32 = 0.01 / PI;
+const MAX_SKYBOX_SIZE: u32 = 1024;
+const IRRADIANCE_SIZE: u32 = 32;
+const PREFILTERED_SIZE: u32 = 128;
+const PREFILTERED_MIP_LEVELS: u32 = 5;
+const BRDF_LUT_SIZE: u32 = 256;
+const WORKGROUP_SIZE: u32 = 8;
+
+/// The compute pipelines precomputing an environment, cloned into the threads loading in the background.
+#[derive(Clone)]
+pub struct EnvironmentPipelines {
+    pub equirectangular_to_cube: Arc<ComputePipeline>,
+    pub irradiance: Arc<ComputePipeline>,
+    pub prefilter: Arc<ComputePipeline>,
+}
+
+/// Cubemaps lighting the scene, made by `Loader::load_environment`.
+#[derive(Clone)]
+pub struct Environment {
+    pub skybox: Arc<ImageView>,
+    /// Cosine weighted incoming light, for diffuse reflections.
+    pub irradiance: Arc<ImageView>,
+    /// Incoming light blurred more at each mip level, for increasingly rough specular reflections.
+    pub prefiltered: Arc<ImageView>,
+}
+
+pub fn environment_sampler(device: Arc<Device>) -> Arc<Sampler> {
+    Sampler::new(
+        device,
+        SamplerCreateInfo {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_mode: SamplerMipmapMode::Linear,
+            address_mode: [SamplerAddressMode::ClampToEdge; 3],
+            lod: 0.0..=LOD_CLAMP_NONE,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+/// Precomputes the lighting of an equirectangular image.
+pub fn compute_environment(
+    queue: &Arc<Queue>,
+    allocators: &AllocatorCollection,
+    pipelines: &EnvironmentPipelines,
+    equirectangular: Arc<ImageView>,
+) -> Result<Environment, LoadError> {
+    let [width, _, _] = equirectangular.image().extent();
+    let skybox_size = (width / 4).max(1).next_power_of_two().min(MAX_SKYBOX_SIZE);
+    let mut builder = AutoCommandBufferBuilder::primary(
+        &allocators.command_buffer,
+        queue.queue_family_index(),
+        CommandBufferUsage::OneTimeSubmit,
+    )
+    .map_err(LoadError::gpu_upload)?;
+    let environment = record_environment(
+        &mut builder,
+        queue.device().clone(),
+        allocators,
+        pipelines,
+        equirectangular,
+        skybox_size,
+    )?;
+    submit_and_wait(queue, builder)?;
+    Ok(environment)
+}
+
+/// Creates an environment sending the same radiance from every direction.
+pub fn uniform_environment(
+    queue: &Arc<Queue>,
+    allocators: &AllocatorCollection,
+    pipelines: &EnvironmentPipelines,
+    radiance: [f32; 3],
+) -> Result<Environment, LoadError> {
+    let image = Image::new(
+        allocators.memory.clone(),
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format: ENVIRONMENT_FORMAT,
+            extent: [1, 1, 1],
+            usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
+    )
+    .map_err(LoadError::gpu_upload)?;
+    let mut builder = AutoCommandBufferBuilder::primary(
+        &allocators.command_buffer,
+        queue.queue_family_index(),
+        CommandBufferUsage::OneTimeSubmit,
+    )
+    .map_err(LoadError::gpu_upload)?;
+    let [r, g, b] = radiance;
+    builder
+        .clear_color_image(ClearColorImageInfo {
+            clear_value: ClearColorValue::Float([r, g, b, 1.0]),
+            ..ClearColorImageInfo::image(image.clone())
+        })
+        .map_err(LoadError::gpu_upload)?;
+    let environment = record_environment(
+        &mut builder,
+        queue.device().clone(),
+        allocators,
+        pipelines,
+        ImageView::new_default(image).map_err(LoadError::gpu_upload)?,
+        1,
+    )?;
+    submit_and_wait(queue, builder)?;
+    Ok(environment)
+}
+
+/// Precomputes the scale and bias applied to the specular reflectance, per view angle and roughness.
+pub fn compute_brdf_lut(
+    queue: &Arc<Queue>,
+    allocators: &AllocatorCollection,
+    pipelines: &PipelineCollection,
+) -> Result<Arc<ImageView>, LoadError> {
+    let image = Image::new(
+        allocators.memory.clone(),
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format: ENVIRONMENT_FORMAT,
+            extent: [BRDF_LUT_SIZE, BRDF_LUT_SIZE, 1],
+            usage: ImageUsage::STORAGE | ImageUsage::SAMPLED,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
+    )
+    .map_err(LoadError::gpu_upload)?;
+    let view = ImageView::new_default(image).map_err(LoadError::gpu_upload)?;
+    let mut builder = AutoCommandBufferBuilder::primary(
+        &allocators.command_buffer,
+        queue.queue_family_index(),
+        CommandBufferUsage::OneTimeSubmit,
+    )
+    .map_err(LoadError::gpu_upload)?;
+    dispatch(
+        &mut builder,
+        allocators,
+        &pipelines.brdf_lut,
+        [WriteDescriptorSet::image_view(0, view.clone())],
+        [
+            workgroup_count(BRDF_LUT_SIZE),
+            workgroup_count(BRDF_LUT_SIZE),
+            1,
+        ],
+    )?;
+    submit_and_wait(queue, builder)?;
+    Ok(view)
+}
+
+fn record_environment(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    device: Arc<Device>,
+    allocators: &AllocatorCollection,
+    pipelines: &EnvironmentPipelines,
+    equirectangular: Arc<ImageView>,
+    skybox_size: u32,
+) -> Result<Environment, LoadError> {
+    let cube_sampler = environment_sampler(device.clone());
+    // the equirectangular image wraps around horizontally
+    let equirectangular_sampler = Sampler::new(
+        device,
+        SamplerCreateInfo {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            address_mode: [
+                SamplerAddressMode::Repeat,
+                SamplerAddressMode::ClampToEdge,
+                SamplerAddressMode::ClampToEdge,
+            ],
+            ..Default::default()
+        },
+    )
+    .map_err(LoadError::gpu_upload)?;
+
+    let skybox = create_cube_image(
+        allocators,
+        skybox_size,
+        mip_level_count(skybox_size),
+        ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST,
+    )?;
+    dispatch(
+        builder,
+        allocators,
+        &pipelines.equirectangular_to_cube,
+        [
+            WriteDescriptorSet::image_view_sampler(0, equirectangular, equirectangular_sampler),
+            WriteDescriptorSet::image_view(1, cube_level_view(&skybox, 0)?),
+        ],
+        cube_workgroup_count(skybox_size),
+    )?;
+    // the convolutions read from blurrier mip levels to take fewer samples
+    generate_mipmaps(builder, skybox.clone())?;
+    let skybox = cube_view(skybox)?;
+
+    let irradiance_size = IRRADIANCE_SIZE.min(skybox_size);
+    let irradiance = create_cube_image(allocators, irradiance_size, 1, ImageUsage::empty())?;
+    dispatch(
+        builder,
+        allocators,
+        &pipelines.irradiance,
+        [
+            WriteDescriptorSet::image_view_sampler(0, skybox.clone(), cube_sampler.clone()),
+            WriteDescriptorSet::image_view(1, cube_level_view(&irradiance, 0)?),
+        ],
+        cube_workgroup_count(irradiance_size),
+    )?;
+
+    let prefiltered_size = PREFILTERED_SIZE.min(skybox_size);
+    let prefiltered_mip_levels = mip_level_count(prefiltered_size).min(PREFILTERED_MIP_LEVELS);
+    let prefiltered = create_cube_image(
+        allocators,
+        prefiltered_size,
+        prefiltered_mip_levels,
+        ImageUsage::empty(),
+    )?;
+    for level in 0..prefiltered_mip_levels {
+        let roughness = if prefiltered_mip_levels > 1 {
+            level as f32 / (prefiltered_mip_levels - 1) as f32
+        } else {
+            0.0
+        };
+        builder
+            .push_constants(
+                pipelines.prefilter.layout().clone(),
+                0,
+                prefilter_shader::PushConstants { roughness },
+            )
+            .map_err(LoadError::gpu_upload)?;
+        dispatch(
+            builder,
+            allocators,
+            &pipelines.prefilter,
+            [
+                WriteDescriptorSet::image_view_sampler(0, skybox.clone(), cube_sampler.clone()),
+                WriteDescriptorSet::image_view(1, cube_level_view(&prefiltered, level)?),
+            ],
+            cube_workgroup_count((prefiltered_size >> level).max(1)),
+        )?;
+    }
+
+    Ok(Environment {
+        skybox,
+        irradiance: cube_view(irradiance)?,
+        prefiltered: cube_view(prefiltered)?,
+    })
+}
+
+fn dispatch(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    allocators: &AllocatorCollection,
+    pipeline: &Arc<ComputePipeline>,
+    descriptor_writes: impl IntoIterator<Item = WriteDescriptorSet>,
+    workgroup_counts: [u32; 3],
+) -> Result<(), LoadError> {
+    let layout = pipeline.layout().set_layouts().first().unwrap();
+    let descriptor_set = PersistentDescriptorSet::new(
+        &allocators.descriptor_set,
+        layout.clone(),
+        descriptor_writes,
+        [],
+    )
+    .map_err(LoadError::gpu_upload)?;
+    builder
+        .bind_pipeline_compute(pipeline.clone())
+        .map_err(LoadError::gpu_upload)?
+        .bind_descriptor_sets(
+            PipelineBindPoint::Compute,
+            pipeline.layout().clone(),
+            0,
+            descriptor_set,
+        )
+        .map_err(LoadError::gpu_upload)?
+        .dispatch(workgroup_counts)
+        .map_err(LoadError::gpu_upload)?;
+    Ok(())
+}
+
+fn create_cube_image(
+    allocators: &AllocatorCollection,
+    size: u32,
+    mip_levels: u32,
+    extra_usage: ImageUsage,
+) -> Result<Arc<Image>, LoadError> {
+    Image::new(
+        allocators.memory.clone(),
+        ImageCreateInfo {
+            flags: ImageCreateFlags::CUBE_COMPATIBLE,
+            image_type: ImageType::Dim2d,
+            format: ENVIRONMENT_FORMAT,
+            extent: [size, size, 1],
+            array_layers: 6,
+            mip_levels,
+            usage: ImageUsage::STORAGE | ImageUsage::SAMPLED | extra_usage,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
+    )
+    .map_err(LoadError::gpu_upload)
+}
+
+fn cube_view(image: Arc<Image>) -> Result<Arc<ImageView>, LoadError> {
+    ImageView::new(
+        image.clone(),
+        ImageViewCreateInfo {
+            view_type: ImageViewType::Cube,
+            ..ImageViewCreateInfo::from_image(&image)
+        },
+    )
+    .map_err(LoadError::gpu_upload)
+}
+
+// the six faces of a single mip level, as written by the compute shaders
+fn cube_level_view(image: &Arc<Image>, level: u32) -> Result<Arc<ImageView>, LoadError> {
+    ImageView::new(
+        image.clone(),
+        ImageViewCreateInfo {
+            view_type: ImageViewType::Dim2dArray,
+            subresource_range: ImageSubresourceRange {
+                aspects: ImageAspects::COLOR,
+                mip_levels: level..level + 1,
+                array_layers: 0..6,
+            },
+            ..ImageViewCreateInfo::from_image(image)
+        },
+    )
+    .map_err(LoadError::gpu_upload)
+}
+
+fn submit_and_wait(
+    queue: &Arc<Queue>,
+    builder: AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+) -> Result<(), LoadError> {
+    let command_buffer = builder.build().map_err(LoadError::gpu_upload)?;
+    let future = sync::now(queue.device().clone())
+        .then_execute(queue.clone(), command_buffer)
+        .map_err(LoadError::gpu_upload)?
+        .then_signal_fence_and_flush()
+        .map_err(LoadError::gpu_upload)?;
+    future.wait(None).map_err(LoadError::gpu_upload)
+}
+
+fn workgroup_count(size: u32) -> u32 {
+    size.div_ceil(WORKGROUP_SIZE)
+}
+
+fn cube_workgroup_count(size: u32) -> [u32; 3] {
+    [workgroup_count(size), workgroup_count(size), 6]
+}
@@ -1,3 +1,4 @@
 pub mod color_texture;
+pub mod ktx2_texture;
 pub mod metal_roughness;
 pub mod occlusion;
@@ -0,0 +1,362 @@
+use std::io::Read;
+
+use basis_universal::{DecodeFlags, TranscodeParameters, Transcoder, TranscoderTextureFormat};
+use ktx2::{
+    BasicDataFormatDescriptor, ColorModel, DataFormatDescriptorHeader, SupercompressionScheme,
+};
+use vulkano::format::Format;
+
+use crate::graphics::{load_error::LoadError, texture::TextureUsage};
+
+// channels of UASTC and ETC1S data format descriptors that hold an alpha
+const UASTC_RGBA: u32 = 3;
+const UASTC_RRRG: u32 = 5;
+const ETC1S_AAA: u32 = 15;
+const BASIS_BLOCK_SIZE: u32 = 4;
+// lengths in bytes of the BasisLZ global data header and of its descriptions of each image
+const BASIS_LZ_HEADER_LENGTH: usize = 20;
+const BASIS_LZ_IMAGE_LENGTH: usize = 20;
+// lengths in bytes of the .basis file header and of its descriptions of each slice
+const BASIS_HEADER_LENGTH: usize = 77;
+const BASIS_SLICE_LENGTH: usize = 23;
+const BASIS_SIGNATURE: u64 = 0x4273;
+const BASIS_VERSION: u64 = 0x13;
+const BASIS_FLAG_ETC1S: u64 = 1;
+const BASIS_FLAG_ALPHA_SLICES: u64 = 4;
+
+/// A KTX2 texture ready to be copied to an image of `format`.
+pub struct Ktx2Image {
+    pub format: Format,
+    pub extent: [u32; 3],
+    /// Mip levels from the full size one, as many as the file stores.
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// The ETC1S codebooks shared by all the slices of a texture.
+struct Etc1sCodebooks<'a> {
+    endpoint_count: u16,
+    selector_count: u16,
+    endpoints: &'a [u8],
+    selectors: &'a [u8],
+    tables: &'a [u8],
+}
+
+/// Compressed data of a mip level, or of its alpha for ETC1S.
+struct BasisSlice<'a> {
+    level: u32,
+    alpha: bool,
+    data: &'a [u8],
+}
+
+/// Transcodes Basis Universal textures, UASTC or ETC1S, to the best block compressed format for `usage`
+/// that `supports` accepts, or to RGBA8 when it accepts none of them.
+/// The other textures are kept in their format if `supports` accepts it, `None` when the texture can't be used on the device.
+pub fn convert_texture(
+    data: &[u8],
+    usage: TextureUsage,
+    supports: impl Fn(Format) -> bool,
+) -> Result<Option<Ktx2Image>, LoadError> {
+    let reader = ktx2::Reader::new(data).map_err(|e| invalid_ktx2(format!("{:?}", e)))?;
+    let header = reader.header();
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+        return Err(invalid_ktx2(
+            "only single 2D images are supported".to_string(),
+        ));
+    }
+    let levels: Vec<Vec<u8>> = match header.supercompression_scheme {
+        None => reader.levels().map(<[u8]>::to_vec).collect(),
+        Some(SupercompressionScheme::Zstandard) => reader
+            .levels()
+            .map(decompress_zstd)
+            .collect::<Result<_, _>>()?,
+        Some(SupercompressionScheme::BasisLZ) if header.format.is_none() => {
+            reader.levels().map(<[u8]>::to_vec).collect()
+        }
+        Some(_) => return Ok(None),
+    };
+    let extent = [header.pixel_width, header.pixel_height.max(1), 1];
+    if let Some(format) = header.format {
+        return Ok(vulkan_format(format)
+            .filter(|&format| supports(format))
+            .map(|format| Ktx2Image {
+                format,
+                extent,
+                levels,
+            }));
+    }
+    let descriptor = reader
+        .data_format_descriptors()
+        .find(|descriptor| descriptor.header == DataFormatDescriptorHeader::BASIC)
+        .ok_or_else(|| invalid_ktx2("missing basic data format descriptor".to_string()))?;
+    let descriptor = BasicDataFormatDescriptor::parse(descriptor.data)
+        .map_err(|e| invalid_ktx2(format!("{:?}", e)))?;
+    let basis = match descriptor.color_model {
+        Some(ColorModel::UASTC) => {
+            let has_alpha = descriptor
+                .sample_information()
+                .any(|sample| matches!(sample.channel_type, UASTC_RGBA | UASTC_RRRG));
+            let slices: Vec<_> = levels
+                .iter()
+                .enumerate()
+                .map(|(level, data)| BasisSlice {
+                    level: level as u32,
+                    alpha: false,
+                    data,
+                })
+                .collect();
+            basis_file(extent, None, has_alpha, &slices)
+        }
+        Some(ColorModel::ETC1S) => {
+            if header.supercompression_scheme != Some(SupercompressionScheme::BasisLZ) {
+                return Err(invalid_ktx2(
+                    "ETC1S textures must be BasisLZ supercompressed".to_string(),
+                ));
+            }
+            let has_alpha = descriptor
+                .sample_information()
+                .any(|sample| sample.channel_type == ETC1S_AAA);
+            etc1s_basis_file(data, &levels, extent, has_alpha)?
+        }
+        _ => return Ok(None),
+    };
+    let Some(&(transcode_format, format)) = transcode_formats(usage)
+        .iter()
+        .find(|(_, format)| supports(*format))
+    else {
+        return Ok(None);
+    };
+    let mut transcoder = Transcoder::new();
+    transcoder
+        .prepare_transcoding(&basis)
+        .map_err(|()| invalid_ktx2("failed to read the Basis Universal data".to_string()))?;
+    let levels = (0..levels.len() as u32)
+        .map(|level| {
+            transcoder
+                .transcode_image_level(
+                    &basis,
+                    transcode_format,
+                    TranscodeParameters {
+                        level_index: level,
+                        decode_flags: Some(DecodeFlags::HIGH_QUALITY),
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| {
+                    invalid_ktx2(format!("failed to transcode mip level {}: {:?}", level, e))
+                })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Some(Ktx2Image {
+        format,
+        extent,
+        levels,
+    }))
+}
+
+/// Splits the mip levels of a BasisLZ texture into their color and alpha slices,
+/// the offsets of which are in the supercompression global data along with the codebooks.
+fn etc1s_basis_file(
+    data: &[u8],
+    levels: &[Vec<u8>],
+    extent: [u32; 3],
+    has_alpha: bool,
+) -> Result<Vec<u8>, LoadError> {
+    let truncated = || invalid_ktx2("truncated BasisLZ global data".to_string());
+    // the offset and length of the global data end the KTX2 header
+    let global_data = usize::try_from(read_le(data, 64, 8)?)
+        .ok()
+        .zip(usize::try_from(read_le(data, 72, 8)?).ok())
+        .and_then(|(offset, length)| data.get(offset..offset.checked_add(length)?))
+        .ok_or_else(truncated)?;
+    // the codebooks follow the descriptions of the images, their lengths are in the header
+    let mut offset = BASIS_LZ_HEADER_LENGTH + levels.len() * BASIS_LZ_IMAGE_LENGTH;
+    let [endpoints, selectors, tables] = [4, 8, 12].map(|length_offset| {
+        let start = offset;
+        offset += read_le(global_data, length_offset, 4)? as usize;
+        global_data.get(start..offset).ok_or_else(truncated)
+    });
+    let codebooks = Etc1sCodebooks {
+        endpoint_count: read_le(global_data, 0, 2)? as u16,
+        selector_count: read_le(global_data, 2, 2)? as u16,
+        endpoints: endpoints?,
+        selectors: selectors?,
+        tables: tables?,
+    };
+    let mut slices = Vec::new();
+    for (level, level_data) in levels.iter().enumerate() {
+        let image = BASIS_LZ_HEADER_LENGTH + level * BASIS_LZ_IMAGE_LENGTH;
+        let channels: &[(usize, bool)] = if has_alpha {
+            &[(4, false), (12, true)]
+        } else {
+            &[(4, false)]
+        };
+        for &(offset, alpha) in channels {
+            let start = read_le(global_data, image + offset, 4)? as usize;
+            let length = read_le(global_data, image + offset + 4, 4)? as usize;
+            slices.push(BasisSlice {
+                level: level as u32,
+                alpha,
+                data: level_data
+                    .get(start..start.saturating_add(length))
+                    .ok_or_else(|| invalid_ktx2(format!("truncated mip level {}", level)))?,
+            });
+        }
+    }
+    Ok(basis_file(extent, Some(&codebooks), has_alpha, &slices))
+}
+
+/// Lays out the slices of a texture as a .basis file, the only container the transcoder reads.
+/// The checksums are left out, the transcoder doesn't check them.
+fn basis_file(
+    extent: [u32; 3],
+    codebooks: Option<&Etc1sCodebooks>,
+    has_alpha: bool,
+    slices: &[BasisSlice],
+) -> Vec<u8> {
+    let codebooks_offset = BASIS_HEADER_LENGTH + slices.len() * BASIS_SLICE_LENGTH;
+    let [endpoints, selectors, tables] = codebooks.map_or([&[][..]; 3], |codebooks| {
+        [codebooks.endpoints, codebooks.selectors, codebooks.tables]
+    });
+    let endpoints_offset = codebooks_offset;
+    let selectors_offset = endpoints_offset + endpoints.len();
+    let tables_offset = selectors_offset + selectors.len();
+    let slices_offset = tables_offset + tables.len();
+    let length = slices_offset + slices.iter().map(|slice| slice.data.len()).sum::<usize>();
+    let flags = match codebooks {
+        Some(_) => BASIS_FLAG_ETC1S,
+        None => 0,
+    } | if has_alpha {
+        BASIS_FLAG_ALPHA_SLICES
+    } else {
+        0
+    };
+    let mut bytes = Vec::with_capacity(length);
+    for (value, size) in [
+        (BASIS_SIGNATURE, 2),
+        (BASIS_VERSION, 2),
+        (BASIS_HEADER_LENGTH as u64, 2),
+        (0, 2),
+        ((length - BASIS_HEADER_LENGTH) as u64, 4),
+        (0, 2),
+        (slices.len() as u64, 3),
+        (1, 3),
+        (codebooks.is_none() as u64, 1),
+        (flags, 2),
+        (0, 1),
+        (0, 3),
+        (0, 12),
+        (
+            codebooks.map_or(0, |codebooks| codebooks.endpoint_count) as u64,
+            2,
+        ),
+        (endpoints_offset as u64, 4),
+        (endpoints.len() as u64, 3),
+        (
+            codebooks.map_or(0, |codebooks| codebooks.selector_count) as u64,
+            2,
+        ),
+        (selectors_offset as u64, 4),
+        (selectors.len() as u64, 3),
+        (tables_offset as u64, 4),
+        (tables.len() as u64, 4),
+        (BASIS_HEADER_LENGTH as u64, 4),
+        (0, 8),
+    ] {
+        write_le(&mut bytes, value, size);
+    }
+    let mut slice_offset = slices_offset;
+    for slice in slices {
+        let [width, height] = [extent[0], extent[1]].map(|size| (size >> slice.level).max(1));
+        for (value, size) in [
+            (0, 3),
+            (slice.level as u64, 1),
+            (slice.alpha as u64, 1),
+            (width as u64, 2),
+            (height as u64, 2),
+            (width.div_ceil(BASIS_BLOCK_SIZE) as u64, 2),
+            (height.div_ceil(BASIS_BLOCK_SIZE) as u64, 2),
+            (slice_offset as u64, 4),
+            (slice.data.len() as u64, 4),
+            (0, 2),
+        ] {
+            write_le(&mut bytes, value, size);
+        }
+        slice_offset += slice.data.len();
+    }
+    for data in [endpoints, selectors, tables] {
+        bytes.extend_from_slice(data);
+    }
+    for slice in slices {
+        bytes.extend_from_slice(slice.data);
+    }
+    bytes
+}
+
+fn read_le(data: &[u8], offset: usize, size: usize) -> Result<u64, LoadError> {
+    let bytes = data
+        .get(offset..offset + size)
+        .ok_or_else(|| invalid_ktx2("unexpected end of file".to_string()))?;
+    Ok(bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| value << 8 | byte as u64))
+}
+
+// values wider than `size` bytes are truncated
+fn write_le(bytes: &mut Vec<u8>, value: u64, size: usize) {
+    bytes.extend((0..size).map(|byte| value.checked_shr(byte as u32 * 8).unwrap_or(0) as u8));
+}
+
+// from the best to the worst quality
+fn transcode_formats(usage: TextureUsage) -> &'static [(TranscoderTextureFormat, Format)] {
+    match usage {
+        TextureUsage::Color => &[
+            (TranscoderTextureFormat::BC7_RGBA, Format::BC7_SRGB_BLOCK),
+            (TranscoderTextureFormat::BC3_RGBA, Format::BC3_SRGB_BLOCK),
+            (TranscoderTextureFormat::RGBA32, Format::R8G8B8A8_SRGB),
+        ],
+        TextureUsage::MetalRoughness | TextureUsage::Normal => &[
+            (TranscoderTextureFormat::BC7_RGBA, Format::BC7_UNORM_BLOCK),
+            (TranscoderTextureFormat::BC3_RGBA, Format::BC3_UNORM_BLOCK),
+            (TranscoderTextureFormat::RGBA32, Format::R8G8B8A8_UNORM),
+        ],
+        TextureUsage::Occlusion => &[
+            (TranscoderTextureFormat::BC4_R, Format::BC4_UNORM_BLOCK),
+            (TranscoderTextureFormat::BC7_RGBA, Format::BC7_UNORM_BLOCK),
+            (TranscoderTextureFormat::BC3_RGBA, Format::BC3_UNORM_BLOCK),
+            (TranscoderTextureFormat::RGBA32, Format::R8G8B8A8_UNORM),
+        ],
+    }
+}
+
+fn vulkan_format(format: ktx2::Format) -> Option<Format> {
+    Some(match format {
+        ktx2::Format::R8_UNORM => Format::R8_UNORM,
+        ktx2::Format::R8G8_UNORM => Format::R8G8_UNORM,
+        ktx2::Format::R8G8B8A8_UNORM => Format::R8G8B8A8_UNORM,
+        ktx2::Format::R8G8B8A8_SRGB => Format::R8G8B8A8_SRGB,
+        ktx2::Format::BC1_RGBA_UNORM_BLOCK => Format::BC1_RGBA_UNORM_BLOCK,
+        ktx2::Format::BC1_RGBA_SRGB_BLOCK => Format::BC1_RGBA_SRGB_BLOCK,
+        ktx2::Format::BC3_UNORM_BLOCK => Format::BC3_UNORM_BLOCK,
+        ktx2::Format::BC3_SRGB_BLOCK => Format::BC3_SRGB_BLOCK,
+        ktx2::Format::BC4_UNORM_BLOCK => Format::BC4_UNORM_BLOCK,
+        ktx2::Format::BC5_UNORM_BLOCK => Format::BC5_UNORM_BLOCK,
+        ktx2::Format::BC7_UNORM_BLOCK => Format::BC7_UNORM_BLOCK,
+        ktx2::Format::BC7_SRGB_BLOCK => Format::BC7_SRGB_BLOCK,
+        _ => return None,
+    })
+}
+
+fn decompress_zstd(mut level_data: &[u8]) -> Result<Vec<u8>, LoadError> {
+    let mut decoder = ruzstd::StreamingDecoder::new(&mut level_data)
+        .map_err(|e| invalid_ktx2(format!("{:?}", e)))?;
+    let mut decompressed = Vec::new();
+    decoder
+        .read_to_end(&mut decompressed)
+        .map_err(|e| invalid_ktx2(e.to_string()))?;
+    Ok(decompressed)
+}
+
+fn invalid_ktx2(details: String) -> LoadError {
+    LoadError::InvalidImage(format!("invalid KTX2 texture: {}", details))
+}
@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use vulkano::{
+    buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer},
+    command_buffer::DrawIndexedIndirectCommand,
+    descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet},
+    image::{sampler::Sampler, view::ImageView},
+    memory::allocator::{AllocationCreateInfo, MemoryTypeFilter},
+    pipeline::Pipeline,
+};
+
+use crate::graphics::{
+    allocators::AllocatorCollection,
+    environment::Environment,
+    pipeline::PipelineCollection,
+    shaders::{cull_instances_shader, fragment_shader, vertex_shader},
+    shadow::{ShadowMaps, SHADOW_LAYER_COUNT},
+};
+
+/// Buffers of the culling pass of a drawn asset, kept with their descriptor sets from frame to frame
+/// and written again each time they are used.
+pub struct CullingResources {
+    pub culling: Subbuffer<cull_instances_shader::Culling>,
+    pub models: Subbuffer<[[[f32; 4]; 4]]>,
+    pub poses: Subbuffer<[[[f32; 4]; 4]]>,
+    pub visible_models: Subbuffer<[[[f32; 4]; 4]]>,
+    pub visible_poses: Subbuffer<[[[f32; 4]; 4]]>,
+    /// One per primitive of the asset.
+    pub commands: Subbuffer<[DrawIndexedIndirectCommand]>,
+    /// Binds the buffers above for the culling pass.
+    pub culling_set: Arc<PersistentDescriptorSet>,
+    /// Binds `commands` for the pass copying the instance count of the first one to the others.
+    pub spread_set: Arc<PersistentDescriptorSet>,
+    /// Binds `poses` for the animated pipelines, drawing the instances one by one.
+    pub pose_set: Arc<PersistentDescriptorSet>,
+    /// Binds `visible_poses` for the animated pipelines.
+    pub visible_pose_set: Arc<PersistentDescriptorSet>,
+}
+
+impl CullingResources {
+    pub fn new(
+        allocators: &AllocatorCollection,
+        pipelines: &PipelineCollection,
+        instance_count: u64,
+        pose_length: u64,
+        command_count: u64,
+    ) -> Self {
+        // rounded up so that assets drawn a few more times don't need new ones every frame
+        let instance_count = instance_count.next_power_of_two();
+        // a storage buffer can't be empty, instances without a pose get a single unused transform
+        let pose_length = pose_length.max(1).next_power_of_two();
+        let culling = Buffer::new_sized(
+            allocators.memory.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::UNIFORM_BUFFER,
+                ..Default::default()
+            },
+            host_written(),
+        )
+        .unwrap();
+        // the instances drawn one by one read it as the instance vertex buffer
+        let models = new_slice(
+            allocators,
+            BufferUsage::VERTEX_BUFFER | BufferUsage::STORAGE_BUFFER,
+            instance_count,
+        );
+        let poses = new_slice(allocators, BufferUsage::STORAGE_BUFFER, pose_length);
+        // also read by the draws as the instance vertex buffer
+        let visible_models = new_slice(
+            allocators,
+            BufferUsage::VERTEX_BUFFER | BufferUsage::STORAGE_BUFFER,
+            instance_count,
+        );
+        let visible_poses = new_slice(allocators, BufferUsage::STORAGE_BUFFER, pose_length);
+        let commands = new_slice(
+            allocators,
+            BufferUsage::INDIRECT_BUFFER | BufferUsage::STORAGE_BUFFER,
+            command_count,
+        );
+        let culling_set = PersistentDescriptorSet::new(
+            &allocators.descriptor_set,
+            pipelines.cull_instances.layout().set_layouts()[0].clone(),
+            [
+                WriteDescriptorSet::buffer(0, culling.clone()),
+                WriteDescriptorSet::buffer(1, models.clone()),
+                WriteDescriptorSet::buffer(2, poses.clone()),
+                WriteDescriptorSet::buffer(3, visible_models.clone()),
+                WriteDescriptorSet::buffer(4, visible_poses.clone()),
+                WriteDescriptorSet::buffer(5, commands.clone()),
+            ],
+            [],
+        )
+        .unwrap();
+        let spread_set = PersistentDescriptorSet::new(
+            &allocators.descriptor_set,
+            pipelines.spread_instance_count.layout().set_layouts()[0].clone(),
+            [WriteDescriptorSet::buffer(0, commands.clone())],
+            [],
+        )
+        .unwrap();
+        let pose_set = PersistentDescriptorSet::new(
+            &allocators.descriptor_set,
+            pipelines.scene_set_layout(2).clone(),
+            [WriteDescriptorSet::buffer(0, poses.clone())],
+            [],
+        )
+        .unwrap();
+        let visible_pose_set = PersistentDescriptorSet::new(
+            &allocators.descriptor_set,
+            pipelines.scene_set_layout(2).clone(),
+            [WriteDescriptorSet::buffer(0, visible_poses.clone())],
+            [],
+        )
+        .unwrap();
+        CullingResources {
+            culling,
+            models,
+            poses,
+            visible_models,
+            visible_poses,
+            commands,
+            culling_set,
+            spread_set,
+            pose_set,
+            visible_pose_set,
+        }
+    }
+
+    pub fn fits(&self, instance_count: u64, pose_length: u64, command_count: u64) -> bool {
+        self.models.len() >= instance_count
+            && self.poses.len() >= pose_length
+            && self.commands.len() >= command_count
+    }
+
+    /// Whether a recorded command buffer still holds the resources, they can't be written until it is done.
+    pub fn in_use(&self) -> bool {
+        [
+            &self.culling_set,
+            &self.spread_set,
+            &self.pose_set,
+            &self.visible_pose_set,
+        ]
+        .into_iter()
+        .any(in_use)
+    }
+}
+
+/// Buffers of the descriptor set binding what the scene shaders share for a view,
+/// kept with it from frame to frame and written again each time it is used.
+pub struct ViewResources {
+    pub camera: Subbuffer<vertex_shader::UniformBufferObject>,
+    /// The shaders go through all of them, there are exactly as many as the view has lights.
+    pub lights: Subbuffer<[fragment_shader::Light]>,
+    /// View-projection of each layer of the shadow maps, the ones the frame doesn't use are left as they are.
+    pub shadow_layers: Subbuffer<[[[f32; 4]; 4]]>,
+    /// The one the sets bind.
+    pub environment: Environment,
+    pub set: Arc<PersistentDescriptorSet>,
+    /// Binds the skybox of `environment` for the skybox pipelines.
+    pub skybox_set: Arc<PersistentDescriptorSet>,
+}
+
+impl ViewResources {
+    pub fn new(
+        allocators: &AllocatorCollection,
+        pipelines: &PipelineCollection,
+        light_count: u64,
+        environment: &Environment,
+        environment_sampler: Arc<Sampler>,
+        brdf_lut: Arc<ImageView>,
+        shadow_maps: &ShadowMaps,
+    ) -> Self {
+        let camera = Buffer::new_sized(
+            allocators.memory.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::UNIFORM_BUFFER,
+                ..Default::default()
+            },
+            host_written(),
+        )
+        .unwrap();
+        let lights = new_slice(allocators, BufferUsage::STORAGE_BUFFER, light_count);
+        let shadow_layers = new_slice(
+            allocators,
+            BufferUsage::STORAGE_BUFFER,
+            SHADOW_LAYER_COUNT as u64,
+        );
+        let set = PersistentDescriptorSet::new(
+            &allocators.descriptor_set,
+            pipelines.scene_set_layout(0).clone(),
+            [
+                WriteDescriptorSet::buffer(0, camera.clone()),
+                WriteDescriptorSet::buffer(1, lights.clone()),
+                WriteDescriptorSet::image_view_sampler(
+                    2,
+                    shadow_maps.view.clone(),
+                    shadow_maps.sampler.clone(),
+                ),
+                WriteDescriptorSet::buffer(3, shadow_layers.clone()),
+                WriteDescriptorSet::image_view_sampler(
+                    4,
+                    environment.irradiance.clone(),
+                    environment_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view_sampler(
+                    5,
+                    environment.prefiltered.clone(),
+                    environment_sampler.clone(),
+                ),
+                WriteDescriptorSet::image_view_sampler(6, brdf_lut, environment_sampler.clone()),
+            ],
+            [],
+        )
+        .unwrap();
+        // both skybox pipelines have the same layout
+        let skybox_set = PersistentDescriptorSet::new(
+            &allocators.descriptor_set,
+            pipelines.skybox(false).layout().set_layouts()[0].clone(),
+            [WriteDescriptorSet::image_view_sampler(
+                0,
+                environment.skybox.clone(),
+                environment_sampler,
+            )],
+            [],
+        )
+        .unwrap();
+        ViewResources {
+            camera,
+            lights,
+            shadow_layers,
+            environment: environment.clone(),
+            set,
+            skybox_set,
+        }
+    }
+
+    pub fn fits(&self, light_count: u64, environment: &Environment) -> bool {
+        self.lights.len() == light_count
+            && Arc::ptr_eq(&self.environment.skybox, &environment.skybox)
+            && Arc::ptr_eq(&self.environment.irradiance, &environment.irradiance)
+            && Arc::ptr_eq(&self.environment.prefiltered, &environment.prefiltered)
+    }
+
+    /// See `CullingResources::in_use`.
+    pub fn in_use(&self) -> bool {
+        in_use(&self.set) || in_use(&self.skybox_set)
+    }
+}
+
+/// Poses of an animated asset casting shadows, kept with their descriptor set from frame to frame
+/// and written again each time they are used.
+pub struct ShadowResources {
+    pub poses: Subbuffer<[[[f32; 4]; 4]]>,
+    /// Binds `poses` for the animated shadow pipeline.
+    pub set: Arc<PersistentDescriptorSet>,
+}
+
+impl ShadowResources {
+    pub fn new(
+        allocators: &AllocatorCollection,
+        pipelines: &PipelineCollection,
+        pose_length: u64,
+    ) -> Self {
+        // rounded up like the buffers of `CullingResources`
+        let poses = new_slice(
+            allocators,
+            BufferUsage::STORAGE_BUFFER,
+            pose_length.next_power_of_two(),
+        );
+        let set = PersistentDescriptorSet::new(
+            &allocators.descriptor_set,
+            pipelines.shadow(true).layout().set_layouts()[0].clone(),
+            [WriteDescriptorSet::buffer(0, poses.clone())],
+            [],
+        )
+        .unwrap();
+        ShadowResources { poses, set }
+    }
+
+    pub fn fits(&self, pose_length: u64) -> bool {
+        self.poses.len() >= pose_length
+    }
+
+    /// See `CullingResources::in_use`.
+    pub fn in_use(&self) -> bool {
+        in_use(&self.set)
+    }
+}
+
+/// Command buffers hold the descriptor sets they bind until the GPU is done with them.
+pub fn in_use(descriptor_set: &Arc<PersistentDescriptorSet>) -> bool {
+    Arc::strong_count(descriptor_set) > 1
+}
+
+/// Picks the resources of `pool` that no command buffer uses anymore and that `fits`,
+/// else replaces unused ones or adds new ones made by `create`.
+pub fn reuse<T>(
+    pool: &mut Vec<T>,
+    in_use: impl Fn(&T) -> bool,
+    fits: impl Fn(&T) -> bool,
+    create: impl FnOnce() -> T,
+) -> &T {
+    let index = match pool
+        .iter()
+        .position(|resources| !in_use(resources) && fits(resources))
+    {
+        Some(index) => index,
+        None => match pool.iter().position(|resources| !in_use(resources)) {
+            Some(index) => {
+                pool[index] = create();
+                index
+            }
+            None => {
+                pool.push(create());
+                pool.len() - 1
+            }
+        },
+    };
+    &pool[index]
+}
+
+fn new_slice<T: BufferContents>(
+    allocators: &AllocatorCollection,
+    usage: BufferUsage,
+    len: u64,
+) -> Subbuffer<[T]> {
+    Buffer::new_slice(
+        allocators.memory.clone(),
+        BufferCreateInfo {
+            usage,
+            ..Default::default()
+        },
+        host_written(),
+        len,
+    )
+    .unwrap()
+}
+
+fn host_written() -> AllocationCreateInfo {
+    AllocationCreateInfo {
+        memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+            | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,78 @@
+use crate::geometry::Transform;
+
+#[derive(Clone, Copy)]
+pub enum Light {
+    /// `intensity` in candela, no cutoff if `range` is `None`.
+    Point {
+        position: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        range: Option<f32>,
+    },
+    /// `intensity` in candela, cone angles in radians from `direction`.
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        range: Option<f32>,
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+        cast_shadows: bool,
+    },
+    /// `intensity` in lux, shadows are cascaded up to `SHADOW_DISTANCE` from the camera.
+    Directional {
+        direction: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        cast_shadows: bool,
+    },
+}
+
+impl Light {
+    pub fn transform(&self, transform: &Transform) -> Self {
+        match *self {
+            Light::Point {
+                position,
+                color,
+                intensity,
+                range,
+            } => Light::Point {
+                position: transform.apply_point(position),
+                color,
+                intensity,
+                range,
+            },
+            Light::Spot {
+                position,
+                direction,
+                color,
+                intensity,
+                range,
+                inner_cone_angle,
+                outer_cone_angle,
+                cast_shadows,
+            } => Light::Spot {
+                position: transform.apply_point(position),
+                direction: transform.apply_vector(direction),
+                color,
+                intensity,
+                range,
+                inner_cone_angle,
+                outer_cone_angle,
+                cast_shadows,
+            },
+            Light::Directional {
+                direction,
+                color,
+                intensity,
+                cast_shadows,
+            } => Light::Directional {
+                direction: transform.apply_vector(direction),
+                color,
+                intensity,
+                cast_shadows,
+            },
+        }
+    }
+}
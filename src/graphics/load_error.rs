@@ -0,0 +1,70 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum LoadError {
+    FileNotFound(String),
+    Parse(gltf::Error),
+    Image(image::ImageError),
+    InvalidImage(String),
+    NodeNotFound(String),
+    NoMesh(String),
+    InvalidLod(String),
+    UnsupportedAccessor(String),
+    GpuUpload(String),
+}
+
+impl LoadError {
+    pub fn from_import(filename: &str, error: gltf::Error) -> Self {
+        match error {
+            gltf::Error::Io(io_error) if io_error.kind() == io::ErrorKind::NotFound => {
+                LoadError::FileNotFound(filename.to_string())
+            }
+            error => LoadError::Parse(error),
+        }
+    }
+
+    pub fn from_image(filename: &str, error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(io_error) if io_error.kind() == io::ErrorKind::NotFound => {
+                LoadError::FileNotFound(filename.to_string())
+            }
+            error => LoadError::Image(error),
+        }
+    }
+
+    pub fn gpu_upload(error: impl fmt::Debug) -> Self {
+        LoadError::GpuUpload(format!("{:?}", error))
+    }
+
+    pub fn missing_accessor(name: &str) -> Self {
+        LoadError::UnsupportedAccessor(format!("missing or unreadable {} accessor", name))
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::FileNotFound(filename) => write!(f, "file not found: {}", filename),
+            LoadError::Parse(error) => write!(f, "failed to parse glTF file: {}", error),
+            LoadError::Image(error) => write!(f, "failed to decode image: {}", error),
+            LoadError::InvalidImage(details) => write!(f, "invalid image: {}", details),
+            LoadError::NodeNotFound(node) => write!(f, "node not found: {}", node),
+            LoadError::NoMesh(node) => write!(f, "node {} has no mesh", node),
+            LoadError::InvalidLod(details) => write!(f, "invalid level of detail: {}", details),
+            LoadError::UnsupportedAccessor(details) => {
+                write!(f, "unsupported accessor: {}", details)
+            }
+            LoadError::GpuUpload(details) => write!(f, "failed to upload to the GPU: {}", details),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Parse(error) => Some(error),
+            LoadError::Image(error) => Some(error),
+            _ => None,
+        }
+    }
+}
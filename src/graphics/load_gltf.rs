@@ -1,23 +1,29 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+    sync::Arc,
+};
 
 use gltf::{
     animation::{util::ReadOutputs, Interpolation},
-    image::Data,
-    mesh::Reader,
-    texture::Info,
+    image::{Data, Source},
+    khr_lights_punctual::Kind,
     Node,
 };
 use vulkano::{
     buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer},
     command_buffer::{
-        AutoCommandBufferBuilder, CommandBufferUsage, CopyBufferInfo, CopyBufferToImageInfo,
+        AutoCommandBufferBuilder, BufferImageCopy, CopyBufferInfo, CopyBufferToImageInfo,
+        PrimaryAutoCommandBuffer,
+    },
+    format::{Format, FormatFeatures},
+    image::{
+        sampler::{self, ComponentMapping, ComponentSwizzle},
+        view::{ImageView, ImageViewCreateInfo},
+        Image, ImageAspects, ImageCreateInfo, ImageSubresourceLayers, ImageType, ImageUsage,
     },
-    descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet},
-    format::Format,
-    image::{view::ImageView, Image, ImageCreateInfo, ImageType, ImageUsage},
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter},
-    pipeline::{Pipeline, PipelineBindPoint},
-    sync::{self, GpuFuture},
 };
 
 use crate::{
@@ -25,743 +31,792 @@ use crate::{
         animation::{AnimatedProperty, Animation, AnimationChannel, Sampler},
         animator::Animator,
     },
-    geometry::{Interpolable, Transform},
+    geometry::{BoundingBox, Interpolable, Transform},
     graphics::{
+        attribute_generator::{normal, tangent},
         engine::{
-            AnimatedPrimitive, Asset, BaseVertex, Engine, Joint, Normal, PBRFactors, Position,
-            Primitive, Skin, Tangent, Texture, TextureCoord, Weight,
+            AlphaMode, AnimatedPrimitive, Asset, BaseVertex, Engine, Joint, Normal, PBRFactors,
+            Position, Primitive, Skin, Tangent, Texture, TextureCoord, Weight,
         },
-        format_converter::{color_texture, metal_roughness},
+        environment::{self, Environment},
+        format_converter::{
+            color_texture,
+            ktx2_texture::{self, Ktx2Image},
+            metal_roughness, occlusion,
+        },
+        light::Light,
+        load_error::LoadError,
+        load_handle::LoadHandle,
+        load_options::{LoadOptions, NormalGeneration},
+        lod::Lod,
+        mesh_data::MeshData,
+        scene::{Scene, SceneNode},
+        texture::{self, TextureSampler, TextureUsage},
+        uploader::{DefaultImages, Uploader},
+        view::RenderTexture,
     },
     Loader,
 };
 
 impl Loader for Engine {
-    fn load(&mut self, filename: &str, node_name: &str) -> Asset {
-        let (gltf_document, gltf_buffers, gltf_images) = gltf::import(filename).unwrap();
-        let all_nodes: Vec<_> = gltf_document.nodes().collect();
-        let node = all_nodes
-            .iter()
+    fn load_with_options(
+        &mut self,
+        filename: &str,
+        node_name: &str,
+        options: &LoadOptions,
+    ) -> Result<Asset, LoadError> {
+        self.uploader.load_asset(filename, node_name, options)
+    }
+
+    fn load_scene_with_options(
+        &mut self,
+        filename: &str,
+        options: &LoadOptions,
+    ) -> Result<Scene, LoadError> {
+        self.uploader.load_scene(filename, options)
+    }
+
+    fn load_in_background(
+        &mut self,
+        filename: &str,
+        node_name: &str,
+        options: &LoadOptions,
+    ) -> LoadHandle<Asset> {
+        let uploader = self.uploader.clone();
+        let (filename, node_name, options) =
+            (filename.to_string(), node_name.to_string(), *options);
+        LoadHandle::spawn(move || uploader.load_asset(&filename, &node_name, &options))
+    }
+
+    fn load_scene_in_background(
+        &mut self,
+        filename: &str,
+        options: &LoadOptions,
+    ) -> LoadHandle<Scene> {
+        let uploader = self.uploader.clone();
+        let (filename, options) = (filename.to_string(), *options);
+        LoadHandle::spawn(move || uploader.load_scene(&filename, &options))
+    }
+
+    fn load_environment(&mut self, filename: &str) -> Result<Environment, LoadError> {
+        self.uploader.load_environment(filename)
+    }
+
+    fn load_color_grading_lut(&mut self, filename: &str) -> Result<Arc<ImageView>, LoadError> {
+        self.uploader.load_color_grading_lut(filename)
+    }
+
+    fn load_environment_in_background(&mut self, filename: &str) -> LoadHandle<Environment> {
+        let uploader = self.uploader.clone();
+        let filename = filename.to_string();
+        LoadHandle::spawn(move || uploader.load_environment(&filename))
+    }
+
+    fn load_color_grading_lut_in_background(
+        &mut self,
+        filename: &str,
+    ) -> LoadHandle<Arc<ImageView>> {
+        let uploader = self.uploader.clone();
+        let filename = filename.to_string();
+        LoadHandle::spawn(move || uploader.load_color_grading_lut(&filename))
+    }
+
+    fn update_materials(&mut self, asset: &mut Asset) {
+        let primitives: Vec<&mut Primitive> = match asset {
+            Asset::Still(primitives) => primitives.iter_mut().collect(),
+            Asset::Animated(primitives, _) => primitives
+                .iter_mut()
+                .map(|primitive| &mut primitive.primitive)
+                .collect(),
+        };
+        for primitive in primitives {
+            primitive.material = self.uploader.create_material(primitive.textures());
+        }
+    }
+
+    fn create_render_texture(&mut self, dimensions: [u32; 2]) -> RenderTexture {
+        RenderTexture::new(
+            self.allocators.memory.clone(),
+            dimensions,
+            self.post_process_render_pass.clone(),
+        )
+    }
+}
+
+impl Uploader {
+    fn load_asset(
+        &self,
+        filename: &str,
+        node_name: &str,
+        options: &LoadOptions,
+    ) -> Result<Asset, LoadError> {
+        let file = import(filename)?;
+        let node = file
+            .document
+            .nodes()
             .find(|n| match n.name() {
                 Some(name) => name == node_name,
                 None => false,
             })
-            .unwrap();
-        let mesh = node.mesh().unwrap();
+            .ok_or_else(|| LoadError::NodeNotFound(node_name.to_string()))?;
+        if node.mesh().is_none() {
+            return Err(LoadError::NoMesh(node_name.to_string()));
+        }
+        self.batch(|builder| self.load_mesh(builder, &node, &file, options))
+    }
+
+    fn load_scene(&self, filename: &str, options: &LoadOptions) -> Result<Scene, LoadError> {
+        let file = import(filename)?;
+        let Some(gltf_scene) = file
+            .document
+            .default_scene()
+            .or_else(|| file.document.scenes().next())
+        else {
+            return Ok(Scene { nodes: Vec::new() });
+        };
+        // levels of detail are loaded with the node they belong to
+        let lod_indices: HashSet<_> = file
+            .document
+            .nodes()
+            .flat_map(|node| {
+                lod_nodes(&node, &file)
+                    .into_iter()
+                    .skip(1)
+                    .map(|lod_node| lod_node.index())
+            })
+            .collect();
+        let nodes = self.batch(|builder| {
+            let mut meshes = HashMap::new();
+            self.load_scene_nodes(
+                builder,
+                gltf_scene.nodes(),
+                &lod_indices,
+                &mut meshes,
+                &file,
+                options,
+            )
+        })?;
+        Ok(Scene { nodes })
+    }
+
+    fn load_environment(&self, filename: &str) -> Result<Environment, LoadError> {
+        let image = image::open(filename)
+            .map_err(|e| LoadError::from_image(filename, e))?
+            .into_rgba32f();
+        let equirectangular = self.batch(|builder| {
+            self.upload_image(
+                builder,
+                image.pixels().map(|pixel| pixel.0),
+                Format::R32G32B32A32_SFLOAT,
+                [image.width(), image.height(), 1],
+                false,
+            )
+        })?;
+        environment::compute_environment(
+            &self.queue,
+            &self.allocators,
+            &self.environment_pipelines,
+            equirectangular,
+        )
+    }
+
+    fn load_color_grading_lut(&self, filename: &str) -> Result<Arc<ImageView>, LoadError> {
+        let image = image::open(filename)
+            .map_err(|e| LoadError::from_image(filename, e))?
+            .into_rgba8();
+        let (width, height) = image.dimensions();
+        if width != height * height {
+            return Err(LoadError::InvalidImage(format!(
+                "a {}x{} image is not a strip of square LUT slices",
+                width, height
+            )));
+        }
+        // the shader expects the display encoded values as they are stored
+        self.batch(|builder| {
+            self.upload_image(
+                builder,
+                image.pixels().map(|pixel| pixel.0),
+                Format::R8G8B8A8_UNORM,
+                [width, height, 1],
+                false,
+            )
+        })
+    }
+
+    /// Loads `nodes` but the levels of detail, whose children are moved up in their place.
+    /// The nodes drawing the same meshes share the assets of `meshes`.
+    fn load_scene_nodes<'a>(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        nodes: impl Iterator<Item = Node<'a>>,
+        lod_indices: &HashSet<usize>,
+        meshes: &mut HashMap<MeshKey, Asset>,
+        file: &GltfFile,
+        options: &LoadOptions,
+    ) -> Result<Vec<SceneNode>, LoadError> {
+        let mut scene_nodes = Vec::new();
+        for node in nodes {
+            if lod_indices.contains(&node.index()) {
+                let lod_transform = node_transform(&node);
+                let children = self.load_scene_nodes(
+                    builder,
+                    node.children(),
+                    lod_indices,
+                    meshes,
+                    file,
+                    options,
+                )?;
+                scene_nodes.extend(children.into_iter().map(|mut child| {
+                    child.transform = lod_transform.compose(&child.transform);
+                    child
+                }));
+            } else {
+                let scene_node =
+                    self.load_scene_node(builder, node, lod_indices, meshes, file, options)?;
+                scene_nodes.push(scene_node);
+            }
+        }
+        Ok(scene_nodes)
+    }
+
+    fn load_scene_node(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        node: Node,
+        lod_indices: &HashSet<usize>,
+        meshes: &mut HashMap<MeshKey, Asset>,
+        file: &GltfFile,
+        options: &LoadOptions,
+    ) -> Result<SceneNode, LoadError> {
+        let asset = match node.mesh() {
+            Some(_) => {
+                let key = MeshKey::new(&node, file, options);
+                match meshes.get(&key) {
+                    Some(asset) => Some(asset.clone()),
+                    None => {
+                        let asset = self.load_mesh(builder, &node, file, options)?;
+                        meshes.insert(key, asset.clone());
+                        Some(asset)
+                    }
+                }
+            }
+            None => None,
+        };
+        let children =
+            self.load_scene_nodes(builder, node.children(), lod_indices, meshes, file, options)?;
+        Ok(SceneNode {
+            index: node.index(),
+            name: node.name().map(str::to_string),
+            transform: node_transform(&node),
+            asset,
+            light: node.light().map(load_light),
+            children,
+        })
+    }
+
+    /// Loads the mesh of `node` with the ones of its levels of detail.
+    fn load_mesh(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        node: &Node,
+        file: &GltfFile,
+        options: &LoadOptions,
+    ) -> Result<Asset, LoadError> {
+        let lod_nodes = lod_nodes(node, file);
+        let screen_coverages = lod_screen_coverages(node, lod_nodes.len(), options);
+        let lods = lod_nodes
+            .iter()
+            .zip(screen_coverages)
+            .enumerate()
+            .map(|(level, (lod_node, screen_coverage))| {
+                let mesh = lod_node
+                    .mesh()
+                    .ok_or_else(|| LoadError::NoMesh(node_label(lod_node)))?;
+                Ok((
+                    mesh,
+                    Lod {
+                        level,
+                        screen_coverage,
+                    },
+                ))
+            })
+            .collect::<Result<Vec<_>, LoadError>>()?;
         match node.skin() {
-            None => Asset::Still(
-                mesh.primitives()
-                    .map(|primitive| {
-                        self.load_still_primitive(&primitive, &gltf_buffers, &gltf_images)
-                    })
-                    .collect(),
-            ),
+            None => {
+                let mut primitives = Vec::new();
+                for (mesh, lod) in &lods {
+                    for primitive in mesh.primitives() {
+                        let mut primitive = self.load_still_primitive(
+                            builder,
+                            &primitive,
+                            &file.buffers,
+                            &file.images,
+                            options,
+                        )?;
+                        primitive.lod = *lod;
+                        primitives.push(primitive);
+                    }
+                }
+                Ok(Asset::Still(primitives))
+            }
             Some(skin) => {
-                let (animator, joint_mapping) =
-                    load_animator(skin, &all_nodes, &gltf_document, &gltf_buffers);
-                Asset::Animated(
-                    mesh.primitives()
-                        .map(|primitive| {
-                            self.load_animated_primitive(
-                                &primitive,
-                                &gltf_buffers,
-                                &gltf_images,
-                                &joint_mapping,
-                            )
-                        })
-                        .collect(),
-                    animator,
-                )
+                // the levels are posed by the same animator
+                if let Some(lod_node) = lod_nodes.iter().find(|lod_node| {
+                    lod_node.skin().map(|lod_skin| lod_skin.index()) != Some(skin.index())
+                }) {
+                    return Err(LoadError::InvalidLod(format!(
+                        "node {} does not share the skin of node {}",
+                        node_label(lod_node),
+                        node_label(node)
+                    )));
+                }
+                let (animator, joint_mapping) = load_animator(skin, &file.document, &file.buffers)?;
+                let mut primitives = Vec::new();
+                for (mesh, lod) in &lods {
+                    for primitive in mesh.primitives() {
+                        let mut primitive = self.load_animated_primitive(
+                            builder,
+                            &primitive,
+                            &file.buffers,
+                            &file.images,
+                            &joint_mapping,
+                            options,
+                        )?;
+                        primitive.primitive.lod = *lod;
+                        primitives.push(primitive);
+                    }
+                }
+                Ok(Asset::Animated(primitives, animator))
             }
         }
     }
-}
 
-impl Engine {
     fn load_animated_primitive(
         &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
         primitive: &gltf::Primitive,
         gltf_buffers: &[gltf::buffer::Data],
-        gltf_images: &[gltf::image::Data],
+        gltf_images: &[GltfImage],
         mapping: &[usize],
-    ) -> AnimatedPrimitive {
-        let reader = primitive.reader(|buffer| Some(&gltf_buffers[buffer.index()]));
-        let index_buffer_option = self.load_index_buffer(&reader);
-        let vertex_len = self.get_vertex_count(&reader);
-        let skin = self.load_joints(&reader, &index_buffer_option, vertex_len, mapping);
-        let primitive = self.load_still_primitive(primitive, gltf_buffers, gltf_images);
-        AnimatedPrimitive { skin, primitive }
+        options: &LoadOptions,
+    ) -> Result<AnimatedPrimitive, LoadError> {
+        let mesh_data = read_mesh_data(primitive, gltf_buffers, options)?;
+        let skin = self.load_skin(builder, &mesh_data, mapping)?;
+        let primitive =
+            self.load_primitive(builder, primitive, &mesh_data, gltf_images, options)?;
+        Ok(AnimatedPrimitive { skin, primitive })
     }
 
     fn load_still_primitive(
         &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
         primitive: &gltf::Primitive,
         gltf_buffers: &[gltf::buffer::Data],
-        gltf_images: &[gltf::image::Data],
-    ) -> Primitive {
-        let reader = primitive.reader(|buffer| Some(&gltf_buffers[buffer.index()]));
-        let index_buffer_option = self.load_index_buffer(&reader);
-        let vertex_len = self.get_vertex_count(&reader);
+        gltf_images: &[GltfImage],
+        options: &LoadOptions,
+    ) -> Result<Primitive, LoadError> {
+        let mesh_data = read_mesh_data(primitive, gltf_buffers, options)?;
+        self.load_primitive(builder, primitive, &mesh_data, gltf_images, options)
+    }
+
+    fn load_primitive(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        primitive: &gltf::Primitive,
+        mesh_data: &MeshData,
+        gltf_images: &[GltfImage],
+        options: &LoadOptions,
+    ) -> Result<Primitive, LoadError> {
+        let vertex_len = mesh_data.vertex_count();
+        let default_images = self.default_images();
+        // the missing textures share their images and texture coordinates
+        let mut default_coordinates = None;
+        let mut default_texture =
+            |builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+             image: &Arc<ImageView>|
+             -> Result<Texture, LoadError> {
+                let coordinates = match &default_coordinates {
+                    Some(coordinates) => coordinates.clone(),
+                    None => default_coordinates
+                        .insert(self.load_default_texture_coord(builder, vertex_len)?)
+                        .clone(),
+                };
+                Ok(Texture {
+                    coordinates,
+                    image: image.clone(),
+                    sampler: self.sampler.clone(),
+                })
+            };
         let pbr_gltf = primitive.material().pbr_metallic_roughness();
         let pbr = load_pbr_factors(primitive);
         let color = match pbr_gltf.base_color_texture() {
             Some(texture_info) => Texture {
                 coordinates: self.load_texture_coords(
-                    &reader,
-                    &texture_info,
-                    vertex_len,
-                    &index_buffer_option,
-                ),
-                image: self.load_color_image(gltf_images, &texture_info),
+                    builder,
+                    mesh_data,
+                    texture_info.tex_coord(),
+                )?,
+                image: self.load_texture_image(
+                    builder,
+                    gltf_images,
+                    &texture_info.texture(),
+                    TextureUsage::Color,
+                )?,
+                sampler: self.load_sampler(&texture_info.texture(), options)?,
             },
-            None => self.load_default_color_texture(vertex_len),
+            None => default_texture(builder, &default_images.color)?,
         };
         let metalness = match pbr_gltf.metallic_roughness_texture() {
             Some(texture_info) => Texture {
                 coordinates: self.load_texture_coords(
-                    &reader,
-                    &texture_info,
-                    vertex_len,
-                    &index_buffer_option,
-                ),
-                image: self.load_metal_image(gltf_images, &texture_info),
+                    builder,
+                    mesh_data,
+                    texture_info.tex_coord(),
+                )?,
+                image: self.load_texture_image(
+                    builder,
+                    gltf_images,
+                    &texture_info.texture(),
+                    TextureUsage::MetalRoughness,
+                )?,
+                sampler: self.load_sampler(&texture_info.texture(), options)?,
             },
-            None => self.load_default_metal_texture(vertex_len),
+            None => default_texture(builder, &default_images.metal)?,
         };
         let normal = match primitive.material().normal_texture() {
-            Some(normal_texture_info) => {
-                let normal_tex_coords = self.load_normal_texture_coords(
-                    &reader,
-                    &normal_texture_info,
-                    vertex_len,
-                    &index_buffer_option,
-                );
-                Texture {
-                    coordinates: normal_tex_coords,
-                    image: self.load_normal_image(gltf_images, &normal_texture_info),
-                }
-            }
-            None => self.load_default_normal_texture(vertex_len),
+            Some(normal_texture_info) => Texture {
+                coordinates: self.load_texture_coords(
+                    builder,
+                    mesh_data,
+                    normal_texture_info.tex_coord(),
+                )?,
+                image: self.load_texture_image(
+                    builder,
+                    gltf_images,
+                    &normal_texture_info.texture(),
+                    TextureUsage::Normal,
+                )?,
+                sampler: self.load_sampler(&normal_texture_info.texture(), options)?,
+            },
+            None => default_texture(builder, &default_images.normal)?,
+        };
+        let occlusion = match primitive.material().occlusion_texture() {
+            Some(occlusion_texture_info) => Texture {
+                coordinates: self.load_texture_coords(
+                    builder,
+                    mesh_data,
+                    occlusion_texture_info.tex_coord(),
+                )?,
+                image: self.load_texture_image(
+                    builder,
+                    gltf_images,
+                    &occlusion_texture_info.texture(),
+                    TextureUsage::Occlusion,
+                )?,
+                sampler: self.load_sampler(&occlusion_texture_info.texture(), options)?,
+            },
+            None => default_texture(builder, &default_images.occlusion)?,
         };
-        let vertex = self.load_base_vertex(&reader, &index_buffer_option, &normal.coordinates);
-        Primitive {
+        let emissive = match primitive.material().emissive_texture() {
+            Some(texture_info) => Texture {
+                coordinates: self.load_texture_coords(
+                    builder,
+                    mesh_data,
+                    texture_info.tex_coord(),
+                )?,
+                image: self.load_texture_image(
+                    builder,
+                    gltf_images,
+                    &texture_info.texture(),
+                    TextureUsage::Color,
+                )?,
+                sampler: self.load_sampler(&texture_info.texture(), options)?,
+            },
+            None => default_texture(builder, &default_images.color)?,
+        };
+        let vertex = self.load_base_vertex(builder, mesh_data)?;
+        let material = self.create_material([&color, &metalness, &normal, &occlusion, &emissive]);
+        let bounds = primitive.bounding_box();
+        Ok(Primitive {
             vertex,
             color,
             metalness,
             normal,
+            occlusion,
+            emissive,
             pbr,
-        }
-    }
-}
-
-impl<'a, 's> Engine {
-    fn get_vertex_count(
-        &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-    ) -> u64 {
-        let Some(index_buffer) = reader.read_indices() else {
-            return reader.read_positions().unwrap().len() as u64;
-        };
-        index_buffer.into_u32().len() as u64
+            cast_shadows: true,
+            material,
+            bounds: BoundingBox {
+                min: bounds.min,
+                max: bounds.max,
+            },
+            lod: Lod::single(),
+        })
     }
 
     fn load_base_vertex(
         &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-        index_buffer_option: &Option<Subbuffer<[u32]>>,
-        normal_tex_coords: &Subbuffer<[TextureCoord]>,
-    ) -> BaseVertex {
-        let positions = self.load_vertex(reader, index_buffer_option);
-        let normals = self.load_normal(reader, &positions, index_buffer_option);
-        let tangents = self.load_tangent(
-            reader,
-            &positions,
-            &normals,
-            normal_tex_coords,
-            index_buffer_option,
-        );
-        BaseVertex {
-            positions,
-            normals,
-            tangents,
-        }
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        mesh_data: &MeshData,
+    ) -> Result<BaseVertex, LoadError> {
+        let normals = mesh_data
+            .normals
+            .as_ref()
+            .ok_or_else(|| LoadError::missing_accessor("NORMAL"))?;
+        let tangents = mesh_data
+            .tangents
+            .as_ref()
+            .ok_or_else(|| LoadError::missing_accessor("TANGENT"))?;
+        Ok(BaseVertex {
+            positions: self.upload_buffer(
+                builder,
+                BufferUsage::VERTEX_BUFFER,
+                mesh_data
+                    .positions
+                    .iter()
+                    .map(|&p| Position { position: p }),
+            )?,
+            normals: self.upload_buffer(
+                builder,
+                BufferUsage::VERTEX_BUFFER,
+                normals.iter().map(|&n| Normal { normal: n }),
+            )?,
+            tangents: self.upload_buffer(
+                builder,
+                BufferUsage::VERTEX_BUFFER,
+                tangents.iter().map(|&t| Tangent { tangent: t }),
+            )?,
+            indices: self.upload_buffer(
+                builder,
+                BufferUsage::INDEX_BUFFER,
+                mesh_data.indices.iter().copied(),
+            )?,
+        })
     }
 
-    fn load_joints(
+    fn load_skin(
         &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-        index_buffer_option: &Option<Subbuffer<[u32]>>,
-        vertex_len: u64,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        mesh_data: &MeshData,
         mapping: &[usize],
-    ) -> Skin {
+    ) -> Result<Skin, LoadError> {
         let mapping: Vec<_> = mapping
             .iter()
             .map(|&i| if i != usize::MAX { i as u32 } else { 0 })
             .collect();
-        let joints_buffer_temp = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            reader.read_joints(0).unwrap().into_u16().map(|j| Joint {
-                joints: [
-                    mapping[j[0] as usize],
-                    mapping[j[1] as usize],
-                    mapping[j[2] as usize],
-                    mapping[j[3] as usize],
-                ],
-            }),
-        )
-        .unwrap();
-        let joints_buffer = Buffer::new_slice::<Joint>(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
-                ..Default::default()
-            },
-            vertex_len,
-        )
-        .unwrap();
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        if let Some(index_buffer) = &index_buffer_option {
-            let layout = self
-                .pipelines
-                .unindex_vec3
-                .layout()
-                .set_layouts()
-                .first()
-                .unwrap();
-            let set = PersistentDescriptorSet::new(
-                &self.allocators.descriptor_set,
-                layout.clone(),
-                [
-                    WriteDescriptorSet::buffer(0, joints_buffer_temp),
-                    WriteDescriptorSet::buffer(1, index_buffer.clone()),
-                    WriteDescriptorSet::buffer(2, joints_buffer.clone()),
-                ],
-                [],
-            )
-            .unwrap();
-            builder
-                .bind_pipeline_compute(self.pipelines.unindex_uvec4.clone())
-                .unwrap()
-                .bind_descriptor_sets(
-                    PipelineBindPoint::Compute,
-                    self.pipelines.unindex_uvec4.layout().clone(),
-                    0,
-                    set,
-                )
-                .unwrap()
-                .dispatch([index_buffer.len() as u32 / 64 + 1, 1, 1])
-                .unwrap();
-        } else {
-            builder
-                .copy_buffer(CopyBufferInfo::buffers(
-                    joints_buffer_temp,
-                    joints_buffer.clone(),
-                ))
-                .unwrap();
-        }
-        let command_buffer = builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        let weight_buffer_temp = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            reader
-                .read_weights(0)
-                .unwrap()
-                .into_f32()
-                .map(|w| Weight { weights: w }),
-        )
-        .unwrap();
-        let weight_buffer = Buffer::new_slice::<Weight>(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
-                ..Default::default()
-            },
-            vertex_len,
-        )
-        .unwrap();
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        if let Some(index_buffer) = &index_buffer_option {
-            let layout = self
-                .pipelines
-                .unindex_vec3
-                .layout()
-                .set_layouts()
-                .first()
-                .unwrap();
-            let set = PersistentDescriptorSet::new(
-                &self.allocators.descriptor_set,
-                layout.clone(),
-                [
-                    WriteDescriptorSet::buffer(0, weight_buffer_temp),
-                    WriteDescriptorSet::buffer(1, index_buffer.clone()),
-                    WriteDescriptorSet::buffer(2, weight_buffer.clone()),
-                ],
-                [],
-            )
-            .unwrap();
-            builder
-                .bind_pipeline_compute(self.pipelines.unindex_vec4.clone())
-                .unwrap()
-                .bind_descriptor_sets(
-                    PipelineBindPoint::Compute,
-                    self.pipelines.unindex_vec4.layout().clone(),
-                    0,
-                    set,
-                )
-                .unwrap()
-                .dispatch([index_buffer.len() as u32 / 64 + 1, 1, 1])
-                .unwrap();
-        } else {
-            builder
-                .copy_buffer(CopyBufferInfo::buffers(
-                    weight_buffer_temp,
-                    weight_buffer.clone(),
-                ))
-                .unwrap();
-        }
-        let command_buffer = builder.build().unwrap();
-
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        Skin {
-            joints: joints_buffer,
-            weights: weight_buffer,
-        }
+        let joints = mesh_data
+            .joints
+            .as_ref()
+            .ok_or_else(|| LoadError::missing_accessor("JOINTS_0"))?;
+        let weights = mesh_data
+            .weights
+            .as_ref()
+            .ok_or_else(|| LoadError::missing_accessor("WEIGHTS_0"))?;
+        Ok(Skin {
+            joints: self.upload_buffer(
+                builder,
+                BufferUsage::VERTEX_BUFFER,
+                joints.iter().map(|j| Joint {
+                    joints: j.map(|joint| mapping[joint as usize]),
+                }),
+            )?,
+            weights: self.upload_buffer(
+                builder,
+                BufferUsage::VERTEX_BUFFER,
+                weights.iter().map(|&w| Weight { weights: w }),
+            )?,
+        })
     }
 
-    fn load_normal(
+    fn load_texture_coords(
         &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-        vertex_buffer: &Subbuffer<[Position]>,
-        index_buffer_option: &Option<Subbuffer<[u32]>>,
-    ) -> Subbuffer<[Normal]> {
-        let vertex_len = vertex_buffer.len();
-
-        let normal_buffer_option = reader.read_normals().map(|buffer| {
-            Buffer::from_iter(
-                self.allocators.memory.clone(),
-                BufferCreateInfo {
-                    usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
-                    ..Default::default()
-                },
-                AllocationCreateInfo {
-                    memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                        | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                    ..Default::default()
-                },
-                buffer.map(|n| Normal { normal: n }),
-            )
-            .unwrap()
-        });
-        let normal_buffer = Buffer::new_slice::<Normal>(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
-                ..Default::default()
-            },
-            vertex_len,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        mesh_data: &MeshData,
+        tex_coord: u32,
+    ) -> Result<Subbuffer<[TextureCoord]>, LoadError> {
+        self.upload_buffer(
+            builder,
+            BufferUsage::VERTEX_BUFFER,
+            mesh_data
+                .tex_coords(tex_coord)?
+                .iter()
+                .map(|&c| TextureCoord { tex_coords_in: c }),
         )
-        .unwrap();
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
+    }
+
+    fn load_default_texture_coord(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        vertex_len: usize,
+    ) -> Result<Subbuffer<[TextureCoord]>, LoadError> {
+        self.upload_buffer(
+            builder,
+            BufferUsage::VERTEX_BUFFER,
+            (0..vertex_len).map(|_| TextureCoord {
+                tex_coords_in: [0.0; 2],
+            }),
         )
-        .unwrap();
-        if let Some(normal_buffer_temp) = &normal_buffer_option {
-            if let Some(index_buffer) = &index_buffer_option {
-                let layout = self
-                    .pipelines
-                    .unindex_vec3
-                    .layout()
-                    .set_layouts()
-                    .first()
-                    .unwrap();
-                let set = PersistentDescriptorSet::new(
-                    &self.allocators.descriptor_set,
-                    layout.clone(),
-                    [
-                        WriteDescriptorSet::buffer(0, normal_buffer_temp.clone()),
-                        WriteDescriptorSet::buffer(1, index_buffer.clone()),
-                        WriteDescriptorSet::buffer(2, normal_buffer.clone()),
-                    ],
-                    [],
-                )
-                .unwrap();
-                builder
-                    .bind_pipeline_compute(self.pipelines.unindex_vec3.clone())
-                    .unwrap()
-                    .bind_descriptor_sets(
-                        PipelineBindPoint::Compute,
-                        self.pipelines.unindex_vec3.layout().clone(),
-                        0,
-                        set,
-                    )
-                    .unwrap()
-                    .dispatch([index_buffer.len() as u32 / 64 + 1, 1, 1])
-                    .unwrap();
-            } else {
-                builder
-                    .copy_buffer(CopyBufferInfo::buffers(
-                        normal_buffer_temp.clone(),
-                        normal_buffer.clone(),
-                    ))
-                    .unwrap();
-            }
-        } else {
-            let layout = self
-                .pipelines
-                .normal
-                .layout()
-                .set_layouts()
-                .first()
-                .unwrap();
-            let set = PersistentDescriptorSet::new(
-                &self.allocators.descriptor_set,
-                layout.clone(),
-                [
-                    WriteDescriptorSet::buffer(0, vertex_buffer.clone()),
-                    WriteDescriptorSet::buffer(1, normal_buffer.clone()),
-                ],
-                [],
-            )
-            .unwrap();
-            builder
-                .bind_pipeline_compute(self.pipelines.normal.clone())
-                .unwrap()
-                .bind_descriptor_sets(
-                    PipelineBindPoint::Compute,
-                    self.pipelines.normal.layout().clone(),
-                    0,
-                    set,
-                )
-                .unwrap()
-                .dispatch([vertex_buffer.len() as u32 / 3 / 64 + 1, 1, 1])
-                .unwrap();
-        }
-        let command_buffer = builder.build().unwrap();
+    }
 
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        normal_buffer
+    pub fn upload_default_images(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    ) -> Result<DefaultImages, LoadError> {
+        Ok(DefaultImages {
+            color: self.upload_image(
+                builder,
+                [[u8::MAX; 4]].into_iter(),
+                Format::R8G8B8A8_SRGB,
+                [1; 3],
+                false,
+            )?,
+            metal: self.upload_image(
+                builder,
+                [[u8::MAX, u8::MAX]].into_iter(),
+                Format::R8G8_UNORM,
+                [1; 3],
+                false,
+            )?,
+            normal: self.upload_image(
+                builder,
+                [[u8::MAX / 2, u8::MAX / 2, u8::MAX, 0]].into_iter(),
+                Format::R8G8B8A8_UNORM,
+                [1; 3],
+                false,
+            )?,
+            occlusion: self.upload_image(
+                builder,
+                [u8::MAX].into_iter(),
+                Format::R8_UNORM,
+                [1; 3],
+                false,
+            )?,
+        })
     }
 
-    fn load_tangent(
+    /// Uploads the KTX2 image of the KHR_texture_basisu extension when the device can sample it,
+    /// else the image of the texture.
+    fn load_texture_image(
         &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-        vertex_buffer: &Subbuffer<[Position]>,
-        normal_buffer: &Subbuffer<[Normal]>,
-        texture_coord: &Subbuffer<[TextureCoord]>,
-        index_buffer_option: &Option<Subbuffer<[u32]>>,
-    ) -> Subbuffer<[Tangent]> {
-        let vertex_len = vertex_buffer.len();
-        let tangent_buffer_option = reader.read_tangents().map(|buffer| {
-            Buffer::from_iter(
-                self.allocators.memory.clone(),
-                BufferCreateInfo {
-                    usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
-                    ..Default::default()
-                },
-                AllocationCreateInfo {
-                    memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                        | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                    ..Default::default()
-                },
-                buffer.map(|t| Tangent {
-                    tangent: [t[0], t[1], t[2]],
-                }),
-            )
-            .unwrap()
-        });
-        let tangent_buffer = Buffer::new_slice::<Tangent>(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
-                ..Default::default()
-            },
-            vertex_len,
-        )
-        .unwrap();
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        if let Some(tangent_buffer_temp) = &tangent_buffer_option {
-            if let Some(index_buffer) = &index_buffer_option {
-                let layout = self
-                    .pipelines
-                    .unindex_vec3
-                    .layout()
-                    .set_layouts()
-                    .first()
-                    .unwrap();
-                let set = PersistentDescriptorSet::new(
-                    &self.allocators.descriptor_set,
-                    layout.clone(),
-                    [
-                        WriteDescriptorSet::buffer(0, tangent_buffer_temp.clone()),
-                        WriteDescriptorSet::buffer(1, index_buffer.clone()),
-                        WriteDescriptorSet::buffer(2, tangent_buffer.clone()),
-                    ],
-                    [],
-                )
-                .unwrap();
-                builder
-                    .bind_pipeline_compute(self.pipelines.unindex_vec3.clone())
-                    .unwrap()
-                    .bind_descriptor_sets(
-                        PipelineBindPoint::Compute,
-                        self.pipelines.unindex_vec3.layout().clone(),
-                        0,
-                        set,
-                    )
-                    .unwrap()
-                    .dispatch([index_buffer.len() as u32 / 64 + 1, 1, 1])
-                    .unwrap();
-            } else {
-                builder
-                    .copy_buffer(CopyBufferInfo::buffers(
-                        tangent_buffer_temp.clone(),
-                        tangent_buffer.clone(),
-                    ))
-                    .unwrap();
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        images: &[GltfImage],
+        texture: &gltf::Texture,
+        usage: TextureUsage,
+    ) -> Result<Arc<ImageView>, LoadError> {
+        let basisu_source = texture
+            .extension_value("KHR_texture_basisu")
+            .and_then(|extension| extension.get("source"))
+            .and_then(|source| source.as_u64())
+            .map(|source| source as usize);
+        let sources = [basisu_source, texture.source().map(|image| image.index())];
+        // an unreadable KTX2 image falls back on the next source, its error is kept for when none is left
+        let mut ktx2_error = None;
+        for index in sources.into_iter().flatten() {
+            match images.get(index) {
+                Some(GltfImage::Ktx2(data)) => {
+                    let supports = |format| self.supports_sampling(format);
+                    match ktx2_texture::convert_texture(data, usage, supports) {
+                        Ok(Some(image)) => return self.upload_image_levels(builder, image, usage),
+                        Ok(None) => {}
+                        Err(error) => ktx2_error = Some(error),
+                    }
+                }
+                Some(GltfImage::Decoded(image_data)) => {
+                    return self.upload_decoded_image(builder, image_data, usage);
+                }
+                None => {}
             }
-        } else {
-            let layout = self
-                .pipelines
-                .tangent_simple
-                .layout()
-                .set_layouts()
-                .first()
-                .unwrap();
-            let set = PersistentDescriptorSet::new(
-                &self.allocators.descriptor_set,
-                layout.clone(),
-                [
-                    WriteDescriptorSet::buffer(0, vertex_buffer.clone()),
-                    WriteDescriptorSet::buffer(1, texture_coord.clone()),
-                    WriteDescriptorSet::buffer(2, normal_buffer.clone()),
-                    WriteDescriptorSet::buffer(3, tangent_buffer.clone()),
-                ],
-                [],
-            )
-            .unwrap();
-            builder
-                .bind_pipeline_compute(self.pipelines.tangent_simple.clone())
-                .unwrap()
-                .bind_descriptor_sets(
-                    PipelineBindPoint::Compute,
-                    self.pipelines.tangent_simple.layout().clone(),
-                    0,
-                    set,
-                )
-                .unwrap()
-                .dispatch([vertex_len as u32 / 3 / 64 + 1, 1, 1])
-                .unwrap();
         }
-        let command_buffer = builder.build().unwrap();
-
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        tangent_buffer
+        Err(ktx2_error.unwrap_or_else(|| {
+            LoadError::InvalidImage(format!(
+                "texture {} has no image the device can sample",
+                texture.index()
+            ))
+        }))
     }
 
-    fn load_vertex(
+    fn upload_decoded_image(
         &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-        index_buffer_option: &Option<Subbuffer<[u32]>>,
-    ) -> Subbuffer<[Position]> {
-        let vertex_buffer_temp = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            reader
-                .read_positions()
-                .unwrap()
-                .map(|p| Position { position: p }),
-        )
-        .unwrap();
-        let vertex_len = match &index_buffer_option {
-            Some(index_buffer) => index_buffer.len(),
-            None => vertex_buffer_temp.len(),
-        };
-        let vertex_buffer = Buffer::new_slice::<Position>(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
-                ..Default::default()
-            },
-            vertex_len,
-        )
-        .unwrap();
-
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        if let Some(index_buffer) = &index_buffer_option {
-            let layout = self
-                .pipelines
-                .unindex_vec3
-                .layout()
-                .set_layouts()
-                .first()
-                .unwrap();
-            let set = PersistentDescriptorSet::new(
-                &self.allocators.descriptor_set,
-                layout.clone(),
-                [
-                    WriteDescriptorSet::buffer(0, vertex_buffer_temp),
-                    WriteDescriptorSet::buffer(1, index_buffer.clone()),
-                    WriteDescriptorSet::buffer(2, vertex_buffer.clone()),
-                ],
-                [],
-            )
-            .unwrap();
-            builder
-                .bind_pipeline_compute(self.pipelines.unindex_vec3.clone())
-                .unwrap()
-                .bind_descriptor_sets(
-                    PipelineBindPoint::Compute,
-                    self.pipelines.unindex_vec3.layout().clone(),
-                    0,
-                    set,
-                )
-                .unwrap()
-                .dispatch([index_buffer.len() as u32 / 64 + 1, 1, 1])
-                .unwrap();
-        } else {
-            builder
-                .copy_buffer(CopyBufferInfo::buffers(
-                    vertex_buffer_temp,
-                    vertex_buffer.clone(),
-                ))
-                .unwrap();
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        image_data: &Data,
+        usage: TextureUsage,
+    ) -> Result<Arc<ImageView>, LoadError> {
+        let extent = [image_data.width, image_data.height, 1];
+        match usage {
+            TextureUsage::Color => self.upload_image(
+                builder,
+                color_texture::convert_texture(image_data),
+                Format::R8G8B8A8_SRGB,
+                extent,
+                true,
+            ),
+            TextureUsage::MetalRoughness => self.upload_image(
+                builder,
+                metal_roughness::convert_texture(image_data),
+                Format::R8G8_UNORM,
+                extent,
+                true,
+            ),
+            TextureUsage::Normal => self.upload_image(
+                builder,
+                color_texture::convert_texture(image_data),
+                Format::R8G8B8A8_UNORM,
+                extent,
+                true,
+            ),
+            TextureUsage::Occlusion => self.upload_image(
+                builder,
+                occlusion::convert_texture(image_data),
+                Format::R8_UNORM,
+                extent,
+                true,
+            ),
         }
-        let command_buffer = builder.build().unwrap();
+    }
 
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        vertex_buffer
+    /// Whether images of `format` can be sampled with linear filtering.
+    fn supports_sampling(&self, format: Format) -> bool {
+        self.device
+            .physical_device()
+            .format_properties(format)
+            .is_ok_and(|properties| {
+                properties.optimal_tiling_features.contains(
+                    FormatFeatures::SAMPLED_IMAGE | FormatFeatures::SAMPLED_IMAGE_FILTER_LINEAR,
+                )
+            })
     }
 
-    fn load_index_buffer(
+    /// Shares the sampler with the textures already loaded with the same settings.
+    fn load_sampler(
         &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-    ) -> Option<Subbuffer<[u32]>> {
-        reader.read_indices().map(|buffer| {
-            Buffer::from_iter(
-                self.allocators.memory.clone(),
-                BufferCreateInfo {
-                    usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::INDEX_BUFFER),
-                    ..Default::default()
-                },
-                AllocationCreateInfo {
-                    memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                        | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                    ..Default::default()
-                },
-                buffer.into_u32(),
-            )
-            .unwrap()
-        })
+        texture: &gltf::Texture,
+        options: &LoadOptions,
+    ) -> Result<Arc<sampler::Sampler>, LoadError> {
+        let texture_sampler = TextureSampler::from_gltf(texture, options.anisotropy);
+        let mut samplers = self.samplers.lock().unwrap();
+        if let Some(sampler) = samplers.get(&texture_sampler) {
+            return Ok(sampler.clone());
+        }
+        let sampler = sampler::Sampler::new(
+            self.device.clone(),
+            texture_sampler.create_info(&self.device),
+        )
+        .map_err(LoadError::gpu_upload)?;
+        samplers.insert(texture_sampler, sampler.clone());
+        Ok(sampler)
     }
 
-    fn load_default_texture_coord(&self, vertex_len: u64) -> Subbuffer<[TextureCoord]> {
-        let tex_coord_temp = Buffer::from_iter(
+    fn upload_buffer<T: BufferContents>(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        usage: BufferUsage,
+        data: impl ExactSizeIterator<Item = T>,
+    ) -> Result<Subbuffer<[T]>, LoadError> {
+        let temporary_accessible_buffer = Buffer::from_iter(
             self.allocators.memory.clone(),
             BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
+                usage: BufferUsage::TRANSFER_SRC,
                 ..Default::default()
             },
             AllocationCreateInfo {
@@ -769,48 +824,42 @@ impl<'a, 's> Engine {
                     | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
                 ..Default::default()
             },
-            vec![[0.0; 2]; vertex_len as usize],
+            data,
         )
-        .unwrap();
-        let tex_coord = Buffer::new_slice::<TextureCoord>(
+        .map_err(LoadError::gpu_upload)?;
+        let buffer = Buffer::new_slice::<T>(
             self.allocators.memory.clone(),
             BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
+                usage: usage | BufferUsage::TRANSFER_DST,
                 ..Default::default()
             },
             AllocationCreateInfo {
                 memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
                 ..Default::default()
             },
-            vertex_len,
+            temporary_accessible_buffer.len(),
         )
-        .unwrap();
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
+        .map_err(LoadError::gpu_upload)?;
         builder
-            .copy_buffer(CopyBufferInfo::buffers(tex_coord_temp, tex_coord.clone()))
-            .unwrap();
-        let command_buffer = builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        tex_coord
+            .copy_buffer(CopyBufferInfo::buffers(
+                temporary_accessible_buffer,
+                buffer.clone(),
+            ))
+            .map_err(LoadError::gpu_upload)?;
+        Ok(buffer)
     }
 
-    fn load_default_texture_image<T>(&self, default_value: T, format: Format) -> Arc<ImageView>
-    where
-        T: BufferContents,
-    {
-        let temporary_accessible_buffer = Buffer::from_data(
+    /// With `mipmapped`, the other mip levels are blitted from the uploaded one
+    /// when the device can filter `format` linearly.
+    fn upload_image<T: BufferContents>(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        data: impl ExactSizeIterator<Item = T>,
+        format: Format,
+        extent: [u32; 3],
+        mipmapped: bool,
+    ) -> Result<Arc<ImageView>, LoadError> {
+        let temporary_accessible_buffer = Buffer::from_iter(
             self.allocators.memory.clone(),
             BufferCreateInfo {
                 usage: BufferUsage::TRANSFER_SRC,
@@ -821,252 +870,73 @@ impl<'a, 's> Engine {
                     | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
                 ..Default::default()
             },
-            default_value,
+            data,
         )
-        .unwrap();
-
+        .map_err(LoadError::gpu_upload)?;
+        let blit_features = FormatFeatures::BLIT_SRC
+            | FormatFeatures::BLIT_DST
+            | FormatFeatures::SAMPLED_IMAGE_FILTER_LINEAR;
+        let mip_levels = if mipmapped
+            && self
+                .device
+                .physical_device()
+                .format_properties(format)
+                .map_err(LoadError::gpu_upload)?
+                .optimal_tiling_features
+                .contains(blit_features)
+        {
+            texture::mip_level_count(extent[0].max(extent[1]))
+        } else {
+            1
+        };
         let image = Image::new(
             self.allocators.memory.clone(),
             ImageCreateInfo {
                 image_type: ImageType::Dim2d,
                 format,
-                extent: [1, 1, 1],
-                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+                extent,
+                mip_levels,
+                usage: ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
                 ..Default::default()
             },
             AllocationCreateInfo::default(),
         )
-        .unwrap();
-        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        command_buffer_builder
+        .map_err(LoadError::gpu_upload)?;
+        builder
             .copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
                 temporary_accessible_buffer,
                 image.clone(),
             ))
-            .unwrap();
-        let command_buffer = command_buffer_builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        ImageView::new_default(image).unwrap()
-    }
-
-    fn load_default_color_texture(&self, vertex_len: u64) -> Texture {
-        let image = self.load_default_texture_image([u8::MAX; 4], Format::R8G8B8A8_SRGB);
-        let coordinates = self.load_default_texture_coord(vertex_len);
-        Texture { coordinates, image }
-    }
-
-    fn load_default_metal_texture(&self, vertex_len: u64) -> Texture {
-        let image = self.load_default_texture_image([u8::MAX, u8::MAX], Format::R8G8_UNORM);
-        let coordinates = self.load_default_texture_coord(vertex_len);
-        Texture { coordinates, image }
-    }
-
-    fn load_default_normal_texture(&self, vertex_len: u64) -> Texture {
-        let image = self.load_default_texture_image(
-            [u8::MAX / 2, u8::MAX / 2, u8::MAX, 0],
-            Format::R8G8B8A8_UNORM,
-        );
-        let coordinates = self.load_default_texture_coord(vertex_len);
-        Texture { coordinates, image }
-    }
-
-    fn load_texture_coords(
-        &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-        texture_info: &Info,
-        vertex_len: u64,
-        index_buffer_option: &Option<Subbuffer<[u32]>>,
-    ) -> Subbuffer<[TextureCoord]> {
-        let tex_coord_temp = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            reader
-                .read_tex_coords(texture_info.tex_coord())
-                .unwrap()
-                .into_f32()
-                .map(|c| TextureCoord { tex_coords_in: c }),
-        )
-        .unwrap();
-        let tex_coord = Buffer::new_slice::<TextureCoord>(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
-                ..Default::default()
-            },
-            vertex_len,
-        )
-        .unwrap();
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        if let Some(index_buffer) = index_buffer_option {
-            let layout = self
-                .pipelines
-                .unindex_vec2
-                .layout()
-                .set_layouts()
-                .first()
-                .unwrap();
-            let set = PersistentDescriptorSet::new(
-                &self.allocators.descriptor_set,
-                layout.clone(),
-                [
-                    WriteDescriptorSet::buffer(0, tex_coord_temp),
-                    WriteDescriptorSet::buffer(1, index_buffer.clone()),
-                    WriteDescriptorSet::buffer(2, tex_coord.clone()),
-                ],
-                [],
-            )
-            .unwrap();
-            builder
-                .bind_pipeline_compute(self.pipelines.unindex_vec2.clone())
-                .unwrap()
-                .bind_descriptor_sets(
-                    PipelineBindPoint::Compute,
-                    self.pipelines.unindex_vec2.layout().clone(),
-                    0,
-                    set,
-                )
-                .unwrap()
-                .dispatch([index_buffer.len() as u32 / 64 + 1, 1, 1])
-                .unwrap();
-        } else {
-            builder
-                .copy_buffer(CopyBufferInfo::buffers(tex_coord_temp, tex_coord.clone()))
-                .unwrap();
-        }
-        let command_buffer = builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        tex_coord
+            .map_err(LoadError::gpu_upload)?;
+        texture::generate_mipmaps(builder, image.clone())?;
+        ImageView::new_default(image).map_err(LoadError::gpu_upload)
     }
 
-    fn load_normal_texture_coords(
+    /// Uploads the mip levels stored in a KTX2 file, which may be block compressed.
+    fn upload_image_levels(
         &self,
-        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
-        texture_info: &gltf::material::NormalTexture,
-        vertex_len: u64,
-        index_buffer_option: &Option<Subbuffer<[u32]>>,
-    ) -> Subbuffer<[TextureCoord]> {
-        let tex_coord_temp = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER.union(BufferUsage::TRANSFER_SRC),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            reader
-                .read_tex_coords(texture_info.tex_coord())
-                .unwrap()
-                .into_f32()
-                .map(|c| TextureCoord { tex_coords_in: c }),
-        )
-        .unwrap();
-        let tex_coord = Buffer::new_slice::<TextureCoord>(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::STORAGE_BUFFER
-                    .union(BufferUsage::TRANSFER_DST)
-                    .union(BufferUsage::VERTEX_BUFFER),
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        ktx2_image: Ktx2Image,
+        usage: TextureUsage,
+    ) -> Result<Arc<ImageView>, LoadError> {
+        let [width, height, _] = ktx2_image.extent;
+        let mut data = Vec::new();
+        let mut regions = Vec::new();
+        for (level, level_data) in ktx2_image.levels.iter().enumerate() {
+            regions.push(BufferImageCopy {
+                buffer_offset: data.len() as u64,
+                image_subresource: ImageSubresourceLayers {
+                    aspects: ImageAspects::COLOR,
+                    mip_level: level as u32,
+                    array_layers: 0..1,
+                },
+                image_extent: [(width >> level).max(1), (height >> level).max(1), 1],
                 ..Default::default()
-            },
-            vertex_len,
-        )
-        .unwrap();
-        let mut builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        if let Some(index_buffer) = index_buffer_option {
-            let layout = self
-                .pipelines
-                .unindex_vec2
-                .layout()
-                .set_layouts()
-                .first()
-                .unwrap();
-            let set = PersistentDescriptorSet::new(
-                &self.allocators.descriptor_set,
-                layout.clone(),
-                [
-                    WriteDescriptorSet::buffer(0, tex_coord_temp),
-                    WriteDescriptorSet::buffer(1, index_buffer.clone()),
-                    WriteDescriptorSet::buffer(2, tex_coord.clone()),
-                ],
-                [],
-            )
-            .unwrap();
-            builder
-                .bind_pipeline_compute(self.pipelines.unindex_vec2.clone())
-                .unwrap()
-                .bind_descriptor_sets(
-                    PipelineBindPoint::Compute,
-                    self.pipelines.unindex_vec2.layout().clone(),
-                    0,
-                    set,
-                )
-                .unwrap()
-                .dispatch([index_buffer.len() as u32 / 64 + 1, 1, 1])
-                .unwrap();
-        } else {
-            builder
-                .copy_buffer(CopyBufferInfo::buffers(tex_coord_temp, tex_coord.clone()))
-                .unwrap();
+            });
+            data.extend_from_slice(level_data);
+            // the next level starts on a whole block
+            data.resize(data.len().next_multiple_of(16), 0);
         }
-        let command_buffer = builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        tex_coord
-    }
-
-    fn load_color_image(&self, images: &[Data], texture_info: &Info) -> Arc<ImageView> {
-        let image_data = &images[texture_info.texture().source().index()];
-        let extent = [image_data.width, image_data.height, 1];
         let temporary_accessible_buffer = Buffer::from_iter(
             self.allocators.memory.clone(),
             BufferCreateInfo {
@@ -1078,171 +948,286 @@ impl<'a, 's> Engine {
                     | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
                 ..Default::default()
             },
-            color_texture::convert_texture(image_data),
+            data,
         )
-        .unwrap();
-
+        .map_err(LoadError::gpu_upload)?;
         let image = Image::new(
             self.allocators.memory.clone(),
             ImageCreateInfo {
                 image_type: ImageType::Dim2d,
-                format: Format::R8G8B8A8_SRGB,
-                extent,
+                format: ktx2_image.format,
+                extent: ktx2_image.extent,
+                mip_levels: ktx2_image.levels.len() as u32,
                 usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
                 ..Default::default()
             },
             AllocationCreateInfo::default(),
         )
-        .unwrap();
-        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        command_buffer_builder
-            .copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
-                temporary_accessible_buffer,
-                image.clone(),
-            ))
-            .unwrap();
-        let command_buffer = command_buffer_builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        ImageView::new_default(image).unwrap()
-    }
-
-    fn load_metal_image(&self, images: &[Data], texture_info: &Info) -> Arc<ImageView> {
-        let image_data = &images[texture_info.texture().source().index()];
-        let extent = [image_data.width, image_data.height, 1];
-        let temporary_accessible_buffer = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::TRANSFER_SRC,
-                ..Default::default()
+        .map_err(LoadError::gpu_upload)?;
+        builder
+            .copy_buffer_to_image(CopyBufferToImageInfo {
+                regions: regions.into(),
+                ..CopyBufferToImageInfo::buffer_image(temporary_accessible_buffer, image.clone())
+            })
+            .map_err(LoadError::gpu_upload)?;
+        // the shaders expect the metalness first, as the decoded textures store it
+        let component_mapping = match usage {
+            TextureUsage::MetalRoughness => ComponentMapping {
+                r: ComponentSwizzle::Blue,
+                g: ComponentSwizzle::Green,
+                ..ComponentMapping::identity()
             },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
+            _ => ComponentMapping::identity(),
+        };
+        ImageView::new(
+            image.clone(),
+            ImageViewCreateInfo {
+                component_mapping,
+                ..ImageViewCreateInfo::from_image(&image)
             },
-            metal_roughness::convert_texture(image_data),
         )
-        .unwrap();
+        .map_err(LoadError::gpu_upload)
+    }
+}
 
-        let image = Image::new(
-            self.allocators.memory.clone(),
-            ImageCreateInfo {
-                image_type: ImageType::Dim2d,
-                format: Format::R8G8_UNORM,
-                extent,
-                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
-                ..Default::default()
-            },
-            AllocationCreateInfo::default(),
-        )
-        .unwrap();
-        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
-        command_buffer_builder
-            .copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
-                temporary_accessible_buffer,
-                image.clone(),
-            ))
-            .unwrap();
-        let command_buffer = command_buffer_builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        ImageView::new_default(image).unwrap()
+/// A glTF file with its buffers and images read.
+struct GltfFile {
+    document: gltf::Document,
+    buffers: Vec<gltf::buffer::Data>,
+    images: Vec<GltfImage>,
+    /// Index of the parent of each node, `None` for the roots.
+    parents: Vec<Option<usize>>,
+}
+
+/// An image of a glTF file, the KTX2 ones are kept as they are stored to be transcoded for the device.
+enum GltfImage {
+    Decoded(Data),
+    Ktx2(Vec<u8>),
+}
+
+/// Like `gltf::import`, without decoding the KTX2 images.
+fn import(filename: &str) -> Result<GltfFile, LoadError> {
+    let path = Path::new(filename);
+    let base = path.parent().unwrap_or_else(|| Path::new("./"));
+    let gltf::Gltf { document, blob } =
+        gltf::Gltf::open(path).map_err(|e| LoadError::from_import(filename, e))?;
+    let buffers = gltf::import_buffers(&document, Some(base), blob)
+        .map_err(|e| LoadError::from_import(filename, e))?;
+    let images = document
+        .images()
+        .map(|image| match image.source() {
+            Source::View {
+                view,
+                mime_type: "image/ktx2",
+            } => {
+                let start = view.offset();
+                let data = &buffers[view.buffer().index()][start..start + view.length()];
+                Ok(GltfImage::Ktx2(data.to_vec()))
+            }
+            Source::Uri { uri, mime_type }
+                if mime_type == Some("image/ktx2") || uri.ends_with(".ktx2") =>
+            {
+                if uri.starts_with("data:") {
+                    return Err(LoadError::InvalidImage(
+                        "embedded KTX2 images are not supported".to_string(),
+                    ));
+                }
+                let ktx2_path = base.join(uri);
+                fs::read(&ktx2_path).map(GltfImage::Ktx2).map_err(|e| {
+                    let ktx2_filename = ktx2_path.display().to_string();
+                    match e.kind() {
+                        io::ErrorKind::NotFound => LoadError::FileNotFound(ktx2_filename),
+                        _ => LoadError::InvalidImage(format!(
+                            "failed to read {}: {}",
+                            ktx2_filename, e
+                        )),
+                    }
+                })
+            }
+            source => Data::from_source(source, Some(base), &buffers)
+                .map(GltfImage::Decoded)
+                .map_err(|e| LoadError::from_import(filename, e)),
+        })
+        .collect::<Result<_, _>>()?;
+    let mut parents = vec![None; document.nodes().len()];
+    for node in document.nodes() {
+        for child in node.children() {
+            parents[child.index()] = Some(node.index());
+        }
     }
+    Ok(GltfFile {
+        document,
+        buffers,
+        images,
+        parents,
+    })
+}
 
-    fn load_normal_image(
-        &self,
-        images: &[Data],
-        texture_info: &gltf::material::NormalTexture,
-    ) -> Arc<ImageView> {
-        let image_data = &images[texture_info.texture().source().index()];
-        let extent = [image_data.width, image_data.height, 1];
-        let temporary_accessible_buffer = Buffer::from_iter(
-            self.allocators.memory.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::TRANSFER_SRC,
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_HOST
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            color_texture::convert_texture(image_data),
-        )
-        .unwrap();
+/// Identifies the asset `Uploader::load_mesh` makes for a node.
+#[derive(PartialEq, Eq, Hash)]
+struct MeshKey {
+    /// Mesh of each level of detail.
+    meshes: Vec<Option<usize>>,
+    skin: Option<usize>,
+    /// Bits of the screen coverage of each level of detail.
+    screen_coverages: Vec<u32>,
+}
 
-        let image = Image::new(
-            self.allocators.memory.clone(),
-            ImageCreateInfo {
-                image_type: ImageType::Dim2d,
-                format: Format::R8G8B8A8_UNORM,
-                extent,
-                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
-                ..Default::default()
+impl MeshKey {
+    fn new(node: &Node, file: &GltfFile, options: &LoadOptions) -> Self {
+        let lod_nodes = lod_nodes(node, file);
+        MeshKey {
+            meshes: lod_nodes
+                .iter()
+                .map(|lod_node| lod_node.mesh().map(|mesh| mesh.index()))
+                .collect(),
+            skin: node.skin().map(|skin| skin.index()),
+            screen_coverages: lod_screen_coverages(node, lod_nodes.len(), options)
+                .into_iter()
+                .map(f32::to_bits)
+                .collect(),
+        }
+    }
+}
+
+/// Lists `node` followed by its levels of detail, given by the MSFT_lod extension
+/// or else by its siblings named like it with a `_LOD1`, `_LOD2`... suffix.
+fn lod_nodes<'a>(node: &Node<'a>, file: &'a GltfFile) -> Vec<Node<'a>> {
+    let mut nodes = vec![node.clone()];
+    let lod_ids = node
+        .extension_value("MSFT_lod")
+        .and_then(|extension| extension.get("ids"))
+        .and_then(|ids| ids.as_array());
+    if let Some(lod_ids) = lod_ids {
+        nodes.extend(lod_ids.iter().filter_map(|id| {
+            let index = id.as_u64()? as usize;
+            file.document.nodes().nth(index)
+        }));
+        return nodes;
+    }
+    let Some(name) = node.name() else {
+        return nodes;
+    };
+    let parent = file.parents[node.index()];
+    let siblings: Vec<_> = match parent.and_then(|parent| file.document.nodes().nth(parent)) {
+        Some(parent) => parent.children().collect(),
+        None => file
+            .document
+            .nodes()
+            .filter(|root| file.parents[root.index()].is_none())
+            .collect(),
+    };
+    let base_name = name.strip_suffix("_LOD0").unwrap_or(name);
+    for level in 1.. {
+        let lod_name = format!("{}_LOD{}", base_name, level);
+        match siblings
+            .iter()
+            .find(|lod_node| lod_node.name() == Some(lod_name.as_str()))
+            .cloned()
+        {
+            Some(lod_node) => nodes.push(lod_node),
+            None => break,
+        }
+    }
+    nodes
+}
+
+/// Screen coverage of each of the `level_count` levels of detail of `node`,
+/// from its MSFT_screencoverage extra when it has one.
+fn lod_screen_coverages(node: &Node, level_count: usize, options: &LoadOptions) -> Vec<f32> {
+    let given: Option<Vec<f32>> = node.extras().as_ref().and_then(|extras| {
+        let extras: gltf::json::Value = gltf::json::deserialize::from_str(extras.get()).ok()?;
+        extras
+            .get("MSFT_screencoverage")?
+            .as_array()?
+            .iter()
+            .map(|coverage| coverage.as_f64().map(|coverage| coverage as f32))
+            .collect()
+    });
+    (0..level_count)
+        .map(
+            |level| match given.as_ref().and_then(|given| given.get(level)) {
+                Some(&screen_coverage) => screen_coverage,
+                None if level + 1 == level_count => 0.0,
+                None => options.lod_screen_coverage * 0.5f32.powi(level as i32),
             },
-            AllocationCreateInfo::default(),
-        )
-        .unwrap();
-        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
-            &self.allocators.command_buffer,
-            self.queue.queue_family_index(),
-            CommandBufferUsage::OneTimeSubmit,
         )
-        .unwrap();
-        command_buffer_builder
-            .copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
-                temporary_accessible_buffer,
-                image.clone(),
-            ))
-            .unwrap();
-        let command_buffer = command_buffer_builder.build().unwrap();
-        let future = sync::now(self.device.clone())
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap();
-        future.wait(None).unwrap();
-        ImageView::new_default(image).unwrap()
+        .collect()
+}
+
+fn node_transform(node: &Node) -> Transform {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    Transform::from_trs(translation.into(), rotation.into(), scale.into())
+}
+
+fn node_label(node: &Node) -> String {
+    match node.name() {
+        Some(name) => name.to_string(),
+        None => format!("#{}", node.index()),
     }
 }
 
+fn read_mesh_data(
+    primitive: &gltf::Primitive,
+    gltf_buffers: &[gltf::buffer::Data],
+    options: &LoadOptions,
+) -> Result<MeshData, LoadError> {
+    let reader = primitive.reader(|buffer| Some(&gltf_buffers[buffer.index()]));
+    let mut mesh_data = MeshData::read(&reader)?;
+    if mesh_data.normals.is_none() {
+        mesh_data.unweld();
+        mesh_data.normals = Some(match options.normals {
+            NormalGeneration::Flat => normal::flat(&mesh_data.positions, &mesh_data.indices),
+            NormalGeneration::Smooth { crease_angle } => {
+                normal::smooth(&mesh_data.positions, &mesh_data.indices, crease_angle)
+            }
+        });
+        mesh_data.weld();
+    }
+    if mesh_data.tangents.is_none() {
+        let tex_coord = primitive
+            .material()
+            .normal_texture()
+            .map_or(0, |texture_info| texture_info.tex_coord());
+        if mesh_data.tex_coords(tex_coord).is_ok() {
+            mesh_data.unweld();
+            mesh_data.tangents = Some(tangent::mikktspace(
+                &mesh_data.positions,
+                mesh_data.normals.as_deref().unwrap(),
+                mesh_data.tex_coords(tex_coord)?,
+                &mesh_data.indices,
+            ));
+            mesh_data.weld();
+        } else {
+            mesh_data.tangents = Some(tangent::fallback(mesh_data.normals.as_deref().unwrap()));
+        }
+    }
+    Ok(mesh_data)
+}
+
 fn load_channel(
     channel: &gltf::animation::Channel,
     joints: &[usize],
     mapping: &[usize],
     buffer_data: &[gltf::buffer::Data],
-) -> Option<AnimationChannel> {
+) -> Result<Option<AnimationChannel>, LoadError> {
     let target = channel.target();
     let node_id_gltf = target.node().index();
     if !joints.contains(&node_id_gltf) {
-        return None;
+        return Ok(None);
     }
     let node_id = mapping[node_id_gltf];
     let reader = channel.reader(|buffer| Some(&buffer_data[buffer.index()]));
     let sampler = channel.sampler();
-    let timestamps: Vec<_> = reader.read_inputs().unwrap().collect();
+    let timestamps: Vec<_> = reader
+        .read_inputs()
+        .ok_or_else(|| LoadError::missing_accessor("animation input"))?
+        .collect();
     let t_min = timestamps[0];
     let t_max = timestamps[timestamps.len() - 1];
-    let output = reader.read_outputs().unwrap();
+    let output = reader
+        .read_outputs()
+        .ok_or_else(|| LoadError::missing_accessor("animation output"))?;
     let interpolation = sampler.interpolation();
     let frame_count = timestamps.len();
     let animated_property = match output {
@@ -1257,18 +1242,16 @@ fn load_channel(
         ReadOutputs::Scales(scales) => {
             AnimatedProperty::Scale(convert_sampler(scales, interpolation, frame_count))
         }
-        _ => {
-            println!("morph target animation not handled yet");
-            return None;
-        }
+        // morph targets aren't supported, their weights are left as they are
+        ReadOutputs::MorphTargetWeights(_) => return Ok(None),
     };
-    Some(AnimationChannel {
+    Ok(Some(AnimationChannel {
         t_max,
         t_min,
         node_id,
         timestamps,
         animated_property,
-    })
+    }))
 }
 
 fn convert_sampler<T1: Into<T2>, T2: Interpolable + Copy>(
@@ -1300,32 +1283,81 @@ fn convert_sampler<T1: Into<T2>, T2: Interpolable + Copy>(
 
 fn load_animator(
     skin: gltf::Skin,
-    all_nodes: &[Node],
     gltf_document: &gltf::Document,
     gltf_buffers: &[gltf::buffer::Data],
-) -> (Animator, Vec<usize>) {
+) -> Result<(Animator, Vec<usize>), LoadError> {
+    let all_nodes: Vec<_> = gltf_document.nodes().collect();
     let joints: Vec<_> = skin.joints().map(|n| n.index()).collect();
     let inverse_matrices: Option<Vec<_>> = skin
         .reader(|buffer| Some(&gltf_buffers[buffer.index()]))
         .read_inverse_bind_matrices()
         .map(|i| i.map(Transform::from_homogeneous).collect());
     let (mut animator, global_mapping, joint_mapping) =
-        Animator::new(all_nodes, &joints, inverse_matrices);
+        Animator::new(&all_nodes, &joints, inverse_matrices);
     for animation in gltf_document.animations() {
         let channels = animation
             .channels()
-            .filter_map(|c| load_channel(&c, &joints, &global_mapping, gltf_buffers))
-            .collect();
+            .filter_map(|c| load_channel(&c, &joints, &global_mapping, gltf_buffers).transpose())
+            .collect::<Result<_, LoadError>>()?;
         animator.animations.push(Animation { channels });
     }
-    (animator, joint_mapping)
+    Ok((animator, joint_mapping))
+}
+
+fn load_light(light: gltf::khr_lights_punctual::Light) -> Light {
+    // glTF lights are at the origin of their node, pointing toward -Z
+    // and have no shadow setting, spot and directional lights cast shadows by default
+    let position = [0.0; 3];
+    let direction = [0.0, 0.0, -1.0];
+    match light.kind() {
+        Kind::Point => Light::Point {
+            position,
+            color: light.color(),
+            intensity: light.intensity(),
+            range: light.range(),
+        },
+        Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => Light::Spot {
+            position,
+            direction,
+            color: light.color(),
+            intensity: light.intensity(),
+            range: light.range(),
+            inner_cone_angle,
+            outer_cone_angle,
+            cast_shadows: true,
+        },
+        Kind::Directional => Light::Directional {
+            direction,
+            color: light.color(),
+            intensity: light.intensity(),
+            cast_shadows: true,
+        },
+    }
 }
 
 fn load_pbr_factors(primitive: &gltf::Primitive) -> PBRFactors {
-    let material = primitive.material().pbr_metallic_roughness();
+    let material = primitive.material();
+    let pbr = material.pbr_metallic_roughness();
     PBRFactors {
-        color: material.base_color_factor(),
-        metalness: material.metallic_factor(),
-        roughness: material.roughness_factor(),
+        color: pbr.base_color_factor(),
+        metalness: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        occlusion_strength: material
+            .occlusion_texture()
+            .map_or(1.0, |texture_info| texture_info.strength()),
+        emissive: material.emissive_factor(),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        alpha_cutoff: match material.alpha_mode() {
+            gltf::material::AlphaMode::Mask => material.alpha_cutoff().unwrap_or(0.5),
+            _ => 0.0,
+        },
+        double_sided: material.double_sided(),
     }
 }
@@ -0,0 +1,58 @@
+use std::{
+    panic,
+    thread::{self, JoinHandle},
+};
+
+use crate::graphics::load_error::LoadError;
+
+pub enum LoadStatus<T> {
+    Pending,
+    Ready(T),
+    Failed(LoadError),
+}
+
+/// Loads on another thread, made by the `_in_background` methods of `Loader`.
+pub struct LoadHandle<T> {
+    thread: Option<JoinHandle<Result<T, LoadError>>>,
+    status: LoadStatus<T>,
+}
+
+impl<T: Send + 'static> LoadHandle<T> {
+    pub fn spawn(load: impl FnOnce() -> Result<T, LoadError> + Send + 'static) -> Self {
+        LoadHandle {
+            thread: Some(thread::spawn(load)),
+            status: LoadStatus::Pending,
+        }
+    }
+}
+
+impl<T> LoadHandle<T> {
+    /// Checks whether the loading ended without blocking, what it made can then be used in place or taken.
+    /// A panic of the loading thread is resumed here.
+    pub fn poll(&mut self) -> &mut LoadStatus<T> {
+        if self.thread.as_ref().is_some_and(JoinHandle::is_finished) {
+            self.finish();
+        }
+        &mut self.status
+    }
+
+    /// Blocks until the loading ends.
+    pub fn wait(mut self) -> Result<T, LoadError> {
+        self.finish();
+        match self.status {
+            LoadStatus::Pending => panic!("the loaded value was taken from the status"),
+            LoadStatus::Ready(loaded) => Ok(loaded),
+            LoadStatus::Failed(error) => Err(error),
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.status = match thread.join() {
+                Ok(Ok(loaded)) => LoadStatus::Ready(loaded),
+                Ok(Err(error)) => LoadStatus::Failed(error),
+                Err(payload) => panic::resume_unwind(payload),
+            };
+        }
+    }
+}
@@ -0,0 +1,30 @@
+#[derive(Clone, Copy)]
+pub enum NormalGeneration {
+    Flat,
+    /// Averages the normals of the faces sharing a position, unless they differ by more than `crease_angle` radians.
+    Smooth {
+        crease_angle: f32,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub struct LoadOptions {
+    /// Used for the primitives without a NORMAL attribute.
+    pub normals: NormalGeneration,
+    /// Screen coverage the most detailed level of an asset switches at when the file gives none,
+    /// halved at each following level. The last level is always drawn.
+    pub lod_screen_coverage: f32,
+    /// Texels blended along the slope of trilinearly filtered textures seen at grazing angles,
+    /// clamped to what the device supports. The `anisotropy` extra of a texture overrides it.
+    pub anisotropy: Option<u32>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            normals: NormalGeneration::Flat,
+            lod_screen_coverage: 0.25,
+            anisotropy: Some(16),
+        }
+    }
+}
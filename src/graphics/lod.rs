@@ -0,0 +1,55 @@
+/// Level of detail a primitive belongs to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lod {
+    /// 0 for the most detailed level.
+    pub level: usize,
+    /// Share of the view height the asset covers down to which the level is drawn,
+    /// the next level is drawn below it, or nothing after the last one.
+    pub screen_coverage: f32,
+}
+
+/// A level of detail an instance is drawn at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodDraw {
+    pub level: usize,
+    /// 0 to draw every fragment. When two levels cross-fade, the share of the fragments kept by the first one,
+    /// the second one gets it negated and keeps the others.
+    pub fade: f32,
+}
+
+impl Lod {
+    /// The only level of assets without levels of detail, always drawn.
+    pub fn single() -> Self {
+        Lod {
+            level: 0,
+            screen_coverage: 0.0,
+        }
+    }
+}
+
+/// Picks the levels of detail of an instance covering `coverage` of the view height,
+/// given the screen coverage of each level from the most detailed.
+/// Above each switch, over `fade_band` times its screen coverage, the level is dithered out for the next one.
+pub fn select(screen_coverages: &[f32], coverage: f32, fade_band: Option<f32>) -> Vec<LodDraw> {
+    let Some(level) = screen_coverages
+        .iter()
+        .position(|&screen_coverage| coverage >= screen_coverage)
+    else {
+        return Vec::new();
+    };
+    let switch = screen_coverages[level];
+    let fade_start = switch * (1.0 + fade_band.unwrap_or(0.0));
+    if coverage >= fade_start {
+        return vec![LodDraw { level, fade: 0.0 }];
+    }
+    // never 0, which would draw the whole level
+    let kept = ((coverage - switch) / (fade_start - switch)).max(f32::MIN_POSITIVE);
+    let mut draws = vec![LodDraw { level, fade: kept }];
+    if level + 1 < screen_coverages.len() {
+        draws.push(LodDraw {
+            level: level + 1,
+            fade: -kept,
+        });
+    }
+    draws
+}
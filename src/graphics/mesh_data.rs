@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use gltf::mesh::Reader;
+
+use crate::graphics::load_error::LoadError;
+
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tangents: Option<Vec<[f32; 4]>>,
+    pub tex_coords: Vec<Vec<[f32; 2]>>,
+    pub joints: Option<Vec<[u16; 4]>>,
+    pub weights: Option<Vec<[f32; 4]>>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn read<'a, 's>(
+        reader: &Reader<'a, 's, impl Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>,
+    ) -> Result<Self, LoadError> {
+        let positions: Vec<_> = reader
+            .read_positions()
+            .ok_or_else(|| LoadError::missing_accessor("POSITION"))?
+            .collect();
+        let indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let tex_coords = (0..)
+            .map_while(|set| {
+                reader
+                    .read_tex_coords(set)
+                    .map(|coords| coords.into_f32().collect())
+            })
+            .collect();
+        Ok(MeshData {
+            normals: reader.read_normals().map(|normals| normals.collect()),
+            tangents: reader.read_tangents().map(|tangents| tangents.collect()),
+            joints: reader
+                .read_joints(0)
+                .map(|joints| joints.into_u16().collect()),
+            weights: reader
+                .read_weights(0)
+                .map(|weights| weights.into_f32().collect()),
+            positions,
+            tex_coords,
+            indices,
+        })
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn tex_coords(&self, set: u32) -> Result<&[[f32; 2]], LoadError> {
+        self.tex_coords
+            .get(set as usize)
+            .map(Vec::as_slice)
+            .ok_or_else(|| LoadError::missing_accessor(&format!("TEXCOORD_{}", set)))
+    }
+
+    /// Gives every triangle corner its own vertex, so that per-face attributes can be generated.
+    pub fn unweld(&mut self) {
+        let indices = std::mem::take(&mut self.indices);
+        self.select_vertices(&indices);
+        self.indices = (0..indices.len() as u32).collect();
+    }
+
+    /// Merges the vertices whose attributes are all identical.
+    pub fn weld(&mut self) {
+        let mut vertex_ids = HashMap::new();
+        let mut kept_vertices = Vec::new();
+        let indices = self
+            .indices
+            .iter()
+            .map(|&i| {
+                *vertex_ids.entry(self.vertex_key(i)).or_insert_with(|| {
+                    kept_vertices.push(i);
+                    kept_vertices.len() as u32 - 1
+                })
+            })
+            .collect();
+        self.select_vertices(&kept_vertices);
+        self.indices = indices;
+    }
+
+    fn select_vertices(&mut self, vertices: &[u32]) {
+        self.positions = select(&self.positions, vertices);
+        self.normals = self
+            .normals
+            .as_ref()
+            .map(|normals| select(normals, vertices));
+        self.tangents = self
+            .tangents
+            .as_ref()
+            .map(|tangents| select(tangents, vertices));
+        for tex_coords in &mut self.tex_coords {
+            *tex_coords = select(tex_coords, vertices);
+        }
+        self.joints = self.joints.as_ref().map(|joints| select(joints, vertices));
+        self.weights = self
+            .weights
+            .as_ref()
+            .map(|weights| select(weights, vertices));
+    }
+
+    fn vertex_key(&self, vertex: u32) -> Vec<u32> {
+        let i = vertex as usize;
+        let mut key: Vec<_> = self.positions[i].map(f32::to_bits).into();
+        if let Some(normals) = &self.normals {
+            key.extend(normals[i].map(f32::to_bits));
+        }
+        if let Some(tangents) = &self.tangents {
+            key.extend(tangents[i].map(f32::to_bits));
+        }
+        for tex_coords in &self.tex_coords {
+            key.extend(tex_coords[i].map(f32::to_bits));
+        }
+        if let Some(joints) = &self.joints {
+            key.extend(joints[i].map(u32::from));
+        }
+        if let Some(weights) = &self.weights {
+            key.extend(weights[i].map(f32::to_bits));
+        }
+        key
+    }
+}
+
+fn select<T: Copy>(attribute: &[T], vertices: &[u32]) -> Vec<T> {
+    vertices.iter().map(|&i| attribute[i as usize]).collect()
+}
@@ -1,21 +1,23 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use vulkano::{
+    descriptor_set::layout::DescriptorSetLayout,
     device::Device,
     pipeline::{
         compute::ComputePipelineCreateInfo,
         graphics::{
-            color_blend::{ColorBlendAttachmentState, ColorBlendState},
-            depth_stencil::{DepthState, DepthStencilState},
+            color_blend::{AttachmentBlend, ColorBlendAttachmentState, ColorBlendState},
+            depth_stencil::{CompareOp, DepthState, DepthStencilState},
             input_assembly::InputAssemblyState,
             multisample::MultisampleState,
-            rasterization::{CullMode, RasterizationState},
-            vertex_input::{Vertex, VertexBufferDescription, VertexDefinition},
+            rasterization::{CullMode, DepthBiasState, RasterizationState},
+            vertex_input::{Vertex, VertexBufferDescription, VertexDefinition, VertexInputState},
             viewport::{Viewport, ViewportState},
             GraphicsPipelineCreateInfo,
         },
         layout::PipelineDescriptorSetLayoutCreateInfo,
-        ComputePipeline, GraphicsPipeline, PipelineLayout, PipelineShaderStageCreateInfo,
+        ComputePipeline, DynamicState, GraphicsPipeline, Pipeline, PipelineLayout,
+        PipelineShaderStageCreateInfo,
     },
     render_pass::{RenderPass, Subpass},
     shader::{EntryPoint, ShaderModule},
@@ -23,183 +25,432 @@ use vulkano::{
 
 use crate::graphics::{
     engine::{
-        Joint, Model, Normal, Position, Tangent, TextureCoord, TextureMetalCoord,
-        TextureNormalCoord, Weight,
+        AlphaMode, Joint, Model, Normal, Position, Tangent, TextureCoord, TextureEmissiveCoord,
+        TextureMetalCoord, TextureNormalCoord, TextureOcclusionCoord, Weight,
     },
+    environment::EnvironmentPipelines,
     shaders::{
-        animated_vertex_shader, fragment_shader, map_joints_shader, normal_shader,
-        tangent_simple_shader, unindex_uvec4_shader, unindex_vec2_shader, unindex_vec3_shader,
-        unindex_vec4_shader, vertex_shader,
+        animated_vertex_shader, bloom_downsample_shader, bloom_upsample_shader, brdf_lut_shader,
+        cull_instances_shader, equirectangular_to_cube_shader, fragment_shader,
+        fullscreen_vertex_shader, fxaa_fragment_shader, irradiance_shader, map_joints_shader,
+        prefilter_shader, shadow_animated_vertex_shader, shadow_vertex_shader,
+        skybox_fragment_shader, skybox_vertex_shader, spread_instance_count_shader,
+        tonemap_fragment_shader, vertex_shader,
     },
+    shadow::SHADOW_MAP_SIZE,
 };
 
 struct ShaderCollection {
     vertex: Arc<ShaderModule>,
     animated_vertex: Arc<ShaderModule>,
     fragment: Arc<ShaderModule>,
+    skybox_vertex: Arc<ShaderModule>,
+    skybox_fragment: Arc<ShaderModule>,
+    fullscreen_vertex: Arc<ShaderModule>,
+    tonemap_fragment: Arc<ShaderModule>,
+    fxaa_fragment: Arc<ShaderModule>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub animated: bool,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+    pub reverse_z: bool,
 }
 
 pub struct PipelineCollection {
-    pub graphic: Arc<GraphicsPipeline>,
-    pub graphic_animated: Arc<GraphicsPipeline>,
-    pub unindex_uvec4: Arc<ComputePipeline>,
-    pub unindex_vec4: Arc<ComputePipeline>,
-    pub unindex_vec3: Arc<ComputePipeline>,
-    pub unindex_vec2: Arc<ComputePipeline>,
-    pub normal: Arc<ComputePipeline>,
-    pub tangent_simple: Arc<ComputePipeline>,
+    graphics: HashMap<PipelineKey, Arc<GraphicsPipeline>>,
+    shadow: Arc<GraphicsPipeline>,
+    animated_shadow: Arc<GraphicsPipeline>,
+    skybox: Arc<GraphicsPipeline>,
+    reverse_z_skybox: Arc<GraphicsPipeline>,
+    pub tonemap: Arc<GraphicsPipeline>,
+    pub fxaa: Arc<GraphicsPipeline>,
     pub map_joints: Arc<ComputePipeline>,
+    /// Keeps the instances a view may see and counts them in indirect draw commands.
+    pub cull_instances: Arc<ComputePipeline>,
+    /// Copies the instance count of the first indirect draw command to the others.
+    pub spread_instance_count: Arc<ComputePipeline>,
+    pub environment: EnvironmentPipelines,
+    pub brdf_lut: Arc<ComputePipeline>,
+    pub bloom_downsample: Arc<ComputePipeline>,
+    pub bloom_upsample: Arc<ComputePipeline>,
     shaders: ShaderCollection,
 }
 
+impl PipelineKey {
+    fn all() -> impl Iterator<Item = Self> {
+        [false, true].into_iter().flat_map(|animated| {
+            [AlphaMode::Opaque, AlphaMode::Mask, AlphaMode::Blend]
+                .into_iter()
+                .flat_map(move |alpha_mode| {
+                    [false, true].into_iter().flat_map(move |double_sided| {
+                        [false, true].into_iter().map(move |reverse_z| PipelineKey {
+                            animated,
+                            alpha_mode,
+                            double_sided,
+                            reverse_z,
+                        })
+                    })
+                })
+        })
+    }
+}
+
 impl PipelineCollection {
-    pub fn init(device: Arc<Device>, render_pass: Arc<RenderPass>, dimensions: &[u32]) -> Self {
+    pub fn init(
+        device: Arc<Device>,
+        render_pass: Arc<RenderPass>,
+        shadow_render_pass: Arc<RenderPass>,
+        post_process_render_pass: Arc<RenderPass>,
+    ) -> Self {
         let vertex = vertex_shader::load(device.clone()).expect("failed to create shader module");
         let animated_vertex =
             animated_vertex_shader::load(device.clone()).expect("failed to create shader module");
         let fragment =
             fragment_shader::load(device.clone()).expect("failed to create shader module");
-        let graphic = build_graphics_pipeline(
+        let skybox_vertex =
+            skybox_vertex_shader::load(device.clone()).expect("failed to create shader module");
+        let skybox_fragment =
+            skybox_fragment_shader::load(device.clone()).expect("failed to create shader module");
+        let fullscreen_vertex =
+            fullscreen_vertex_shader::load(device.clone()).expect("failed to create shader module");
+        let tonemap_fragment =
+            tonemap_fragment_shader::load(device.clone()).expect("failed to create shader module");
+        let fxaa_fragment =
+            fxaa_fragment_shader::load(device.clone()).expect("failed to create shader module");
+        let map_joints = build_compute_pipeline(
             device.clone(),
-            vertex.entry_point("main").unwrap(),
-            &[
-                Position::per_vertex(),
-                Normal::per_vertex(),
-                Tangent::per_vertex(),
-                Model::per_instance(),
-                TextureCoord::per_vertex(),
-                TextureMetalCoord::per_vertex(),
-                TextureNormalCoord::per_vertex(),
-            ],
-            fragment.entry_point("main").unwrap(),
-            render_pass.clone(),
-            dimensions,
+            map_joints_shader::load(device.clone())
+                .expect("failed to create shader module")
+                .entry_point("main")
+                .unwrap(),
         );
-        let graphic_animated = build_graphics_pipeline(
+        let cull_instances = build_compute_pipeline(
             device.clone(),
-            animated_vertex.entry_point("main").unwrap(),
-            &[
-                Position::per_vertex(),
-                Normal::per_vertex(),
-                Tangent::per_vertex(),
-                Model::per_instance(),
-                Weight::per_vertex(),
-                Joint::per_vertex(),
-                TextureCoord::per_vertex(),
-                TextureMetalCoord::per_vertex(),
-                TextureNormalCoord::per_vertex(),
-            ],
-            fragment.entry_point("main").unwrap(),
-            render_pass,
-            dimensions,
+            cull_instances_shader::load(device.clone())
+                .expect("failed to create shader module")
+                .entry_point("main")
+                .unwrap(),
         );
-        let unindex_uvec4 = build_compute_pipeline(
+        let spread_instance_count = build_compute_pipeline(
             device.clone(),
-            unindex_uvec4_shader::load(device.clone())
+            spread_instance_count_shader::load(device.clone())
                 .expect("failed to create shader module")
                 .entry_point("main")
                 .unwrap(),
         );
-        let unindex_vec4 = build_compute_pipeline(
+        let equirectangular_to_cube = build_compute_pipeline(
             device.clone(),
-            unindex_vec4_shader::load(device.clone())
+            equirectangular_to_cube_shader::load(device.clone())
                 .expect("failed to create shader module")
                 .entry_point("main")
                 .unwrap(),
         );
-        let unindex_vec3 = build_compute_pipeline(
+        let irradiance = build_compute_pipeline(
             device.clone(),
-            unindex_vec3_shader::load(device.clone())
+            irradiance_shader::load(device.clone())
                 .expect("failed to create shader module")
                 .entry_point("main")
                 .unwrap(),
         );
-        let unindex_vec2 = build_compute_pipeline(
+        let prefilter = build_compute_pipeline(
             device.clone(),
-            unindex_vec2_shader::load(device.clone())
+            prefilter_shader::load(device.clone())
                 .expect("failed to create shader module")
                 .entry_point("main")
                 .unwrap(),
         );
-        let normal = build_compute_pipeline(
+        let brdf_lut = build_compute_pipeline(
             device.clone(),
-            normal_shader::load(device.clone())
+            brdf_lut_shader::load(device.clone())
                 .expect("failed to create shader module")
                 .entry_point("main")
                 .unwrap(),
         );
-        let tangent_simple = build_compute_pipeline(
+        let bloom_downsample = build_compute_pipeline(
             device.clone(),
-            tangent_simple_shader::load(device.clone())
+            bloom_downsample_shader::load(device.clone())
                 .expect("failed to create shader module")
                 .entry_point("main")
                 .unwrap(),
         );
-        let map_joints = build_compute_pipeline(
+        let bloom_upsample = build_compute_pipeline(
             device.clone(),
-            map_joints_shader::load(device)
+            bloom_upsample_shader::load(device.clone())
                 .expect("failed to create shader module")
                 .entry_point("main")
                 .unwrap(),
         );
-        PipelineCollection {
-            unindex_uvec4,
-            unindex_vec4,
-            unindex_vec3,
-            unindex_vec2,
-            normal,
-            tangent_simple,
-            map_joints,
-            graphic,
-            graphic_animated,
-            shaders: ShaderCollection {
-                vertex,
-                animated_vertex,
-                fragment,
-            },
-        }
-    }
-
-    pub fn recreate(
-        &mut self,
-        device: Arc<Device>,
-        render_pass: Arc<RenderPass>,
-        dimensions: &[u32; 2],
-    ) {
-        self.graphic = build_graphics_pipeline(
+        let shadow = build_shadow_pipeline(
             device.clone(),
-            self.shaders.vertex.entry_point("main").unwrap(),
-            &[
-                Position::per_vertex(),
-                Normal::per_vertex(),
-                Tangent::per_vertex(),
-                Model::per_instance(),
-                TextureCoord::per_vertex(),
-                TextureMetalCoord::per_vertex(),
-                TextureNormalCoord::per_vertex(),
-            ],
-            self.shaders.fragment.entry_point("main").unwrap(),
-            render_pass.clone(),
-            dimensions,
+            shadow_vertex_shader::load(device.clone())
+                .expect("failed to create shader module")
+                .entry_point("main")
+                .unwrap(),
+            &[Position::per_vertex(), Model::per_instance()],
+            shadow_render_pass.clone(),
         );
-        self.graphic_animated = build_graphics_pipeline(
+        let animated_shadow = build_shadow_pipeline(
             device.clone(),
-            self.shaders.animated_vertex.entry_point("main").unwrap(),
+            shadow_animated_vertex_shader::load(device.clone())
+                .expect("failed to create shader module")
+                .entry_point("main")
+                .unwrap(),
             &[
                 Position::per_vertex(),
-                Normal::per_vertex(),
-                Tangent::per_vertex(),
                 Model::per_instance(),
                 Weight::per_vertex(),
                 Joint::per_vertex(),
-                TextureCoord::per_vertex(),
-                TextureMetalCoord::per_vertex(),
-                TextureNormalCoord::per_vertex(),
             ],
-            self.shaders.fragment.entry_point("main").unwrap(),
-            render_pass,
-            dimensions,
+            shadow_render_pass,
         );
+        let shaders = ShaderCollection {
+            vertex,
+            animated_vertex,
+            fragment,
+            skybox_vertex,
+            skybox_fragment,
+            fullscreen_vertex,
+            tonemap_fragment,
+            fxaa_fragment,
+        };
+        PipelineCollection {
+            graphics: shaders.build_graphics_pipelines(device.clone(), render_pass.clone()),
+            shadow,
+            animated_shadow,
+            skybox: shaders.build_skybox_pipeline(device.clone(), render_pass.clone(), false),
+            reverse_z_skybox: shaders.build_skybox_pipeline(device.clone(), render_pass, true),
+            tonemap: shaders.build_fullscreen_pipeline(
+                device.clone(),
+                &shaders.tonemap_fragment,
+                post_process_render_pass.clone(),
+            ),
+            fxaa: shaders.build_fullscreen_pipeline(
+                device,
+                &shaders.fxaa_fragment,
+                post_process_render_pass,
+            ),
+            map_joints,
+            cull_instances,
+            spread_instance_count,
+            environment: EnvironmentPipelines {
+                equirectangular_to_cube,
+                irradiance,
+                prefilter,
+            },
+            brdf_lut,
+            bloom_downsample,
+            bloom_upsample,
+            shaders,
+        }
+    }
+
+    /// Rebuilds the pipelines drawing the scene, after its render pass changed.
+    pub fn recreate(&mut self, device: Arc<Device>, render_pass: Arc<RenderPass>) {
+        self.graphics = self
+            .shaders
+            .build_graphics_pipelines(device.clone(), render_pass.clone());
+        self.skybox =
+            self.shaders
+                .build_skybox_pipeline(device.clone(), render_pass.clone(), false);
+        self.reverse_z_skybox = self
+            .shaders
+            .build_skybox_pipeline(device, render_pass, true);
+    }
+
+    pub fn graphic(&self, key: PipelineKey) -> &Arc<GraphicsPipeline> {
+        &self.graphics[&key]
+    }
+
+    /// Layout of the descriptor set `set` of the scene pipelines, 0 for the view, 1 for the material
+    /// and 2 for the poses, only bound by the animated pipelines.
+    pub fn scene_set_layout(&self, set: usize) -> &Arc<DescriptorSetLayout> {
+        let key = PipelineKey {
+            animated: set == 2,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+            reverse_z: false,
+        };
+        &self.graphics[&key].layout().set_layouts()[set]
+    }
+
+    pub fn skybox(&self, reverse_z: bool) -> &Arc<GraphicsPipeline> {
+        if reverse_z {
+            &self.reverse_z_skybox
+        } else {
+            &self.skybox
+        }
+    }
+
+    pub fn shadow(&self, animated: bool) -> &Arc<GraphicsPipeline> {
+        if animated {
+            &self.animated_shadow
+        } else {
+            &self.shadow
+        }
+    }
+}
+
+impl ShaderCollection {
+    fn build_graphics_pipelines(
+        &self,
+        device: Arc<Device>,
+        render_pass: Arc<RenderPass>,
+    ) -> HashMap<PipelineKey, Arc<GraphicsPipeline>> {
+        PipelineKey::all()
+            .map(|key| {
+                let pipeline =
+                    self.build_graphics_pipeline(key, device.clone(), render_pass.clone());
+                (key, pipeline)
+            })
+            .collect()
+    }
+
+    fn build_graphics_pipeline(
+        &self,
+        key: PipelineKey,
+        device: Arc<Device>,
+        render_pass: Arc<RenderPass>,
+    ) -> Arc<GraphicsPipeline> {
+        let (vertex, vertex_definitions) = if key.animated {
+            (
+                &self.animated_vertex,
+                vec![
+                    Position::per_vertex(),
+                    Normal::per_vertex(),
+                    Tangent::per_vertex(),
+                    Model::per_instance(),
+                    Weight::per_vertex(),
+                    Joint::per_vertex(),
+                    TextureCoord::per_vertex(),
+                    TextureMetalCoord::per_vertex(),
+                    TextureNormalCoord::per_vertex(),
+                    TextureOcclusionCoord::per_vertex(),
+                    TextureEmissiveCoord::per_vertex(),
+                ],
+            )
+        } else {
+            (
+                &self.vertex,
+                vec![
+                    Position::per_vertex(),
+                    Normal::per_vertex(),
+                    Tangent::per_vertex(),
+                    Model::per_instance(),
+                    TextureCoord::per_vertex(),
+                    TextureMetalCoord::per_vertex(),
+                    TextureNormalCoord::per_vertex(),
+                    TextureOcclusionCoord::per_vertex(),
+                    TextureEmissiveCoord::per_vertex(),
+                ],
+            )
+        };
+        build_graphics_pipeline(
+            device,
+            vertex.entry_point("main").unwrap(),
+            &vertex_definitions,
+            self.fragment.entry_point("main").unwrap(),
+            render_pass,
+            key,
+        )
+    }
+
+    fn build_skybox_pipeline(
+        &self,
+        device: Arc<Device>,
+        render_pass: Arc<RenderPass>,
+        reverse_z: bool,
+    ) -> Arc<GraphicsPipeline> {
+        let stages = [
+            PipelineShaderStageCreateInfo::new(self.skybox_vertex.entry_point("main").unwrap()),
+            PipelineShaderStageCreateInfo::new(self.skybox_fragment.entry_point("main").unwrap()),
+        ];
+        let layout = PipelineLayout::new(
+            device.clone(),
+            PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+                .into_pipeline_layout_create_info(device.clone())
+                .unwrap(),
+        )
+        .unwrap();
+        let subpass = Subpass::from(render_pass, 0).unwrap();
+        GraphicsPipeline::new(
+            device,
+            None,
+            GraphicsPipelineCreateInfo {
+                stages: stages.into_iter().collect(),
+                vertex_input_state: Some(VertexInputState::new()),
+                input_assembly_state: Some(InputAssemblyState::default()),
+                viewport_state: Some(ViewportState::default()),
+                rasterization_state: Some(RasterizationState::default()),
+                // drawn on the far plane, only where nothing else was
+                depth_stencil_state: Some(DepthStencilState {
+                    depth: Some(DepthState {
+                        write_enable: false,
+                        compare_op: if reverse_z {
+                            CompareOp::GreaterOrEqual
+                        } else {
+                            CompareOp::LessOrEqual
+                        },
+                    }),
+                    ..Default::default()
+                }),
+                multisample_state: Some(MultisampleState {
+                    rasterization_samples: subpass.num_samples().unwrap(),
+                    ..Default::default()
+                }),
+                color_blend_state: Some(ColorBlendState::with_attachment_states(
+                    subpass.num_color_attachments(),
+                    ColorBlendAttachmentState::default(),
+                )),
+                subpass: Some(subpass.into()),
+                dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+                ..GraphicsPipelineCreateInfo::layout(layout)
+            },
+        )
+        .unwrap()
+    }
+
+    /// Builds a pipeline running `fragment` once per pixel of the target, without depth.
+    fn build_fullscreen_pipeline(
+        &self,
+        device: Arc<Device>,
+        fragment: &Arc<ShaderModule>,
+        render_pass: Arc<RenderPass>,
+    ) -> Arc<GraphicsPipeline> {
+        let stages = [
+            PipelineShaderStageCreateInfo::new(self.fullscreen_vertex.entry_point("main").unwrap()),
+            PipelineShaderStageCreateInfo::new(fragment.entry_point("main").unwrap()),
+        ];
+        let layout = PipelineLayout::new(
+            device.clone(),
+            PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+                .into_pipeline_layout_create_info(device.clone())
+                .unwrap(),
+        )
+        .unwrap();
+        let subpass = Subpass::from(render_pass, 0).unwrap();
+        GraphicsPipeline::new(
+            device,
+            None,
+            GraphicsPipelineCreateInfo {
+                stages: stages.into_iter().collect(),
+                vertex_input_state: Some(VertexInputState::new()),
+                input_assembly_state: Some(InputAssemblyState::default()),
+                viewport_state: Some(ViewportState::default()),
+                rasterization_state: Some(RasterizationState::default()),
+                multisample_state: Some(MultisampleState::default()),
+                color_blend_state: Some(ColorBlendState::with_attachment_states(
+                    subpass.num_color_attachments(),
+                    ColorBlendAttachmentState::default(),
+                )),
+                subpass: Some(subpass.into()),
+                dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+                ..GraphicsPipelineCreateInfo::layout(layout)
+            },
+        )
+        .unwrap()
     }
 }
 
@@ -209,7 +460,7 @@ fn build_graphics_pipeline(
     vertex_definitions: &[VertexBufferDescription],
     fragment_entrypoint: EntryPoint,
     render_pass: Arc<RenderPass>,
-    dimensions: &[u32],
+    key: PipelineKey,
 ) -> Arc<GraphicsPipeline> {
     let vertex_input_state = vertex_definitions
         .definition(&vertex_entrypoint.info().input_interface)
@@ -226,6 +477,72 @@ fn build_graphics_pipeline(
     )
     .unwrap();
     let subpass = Subpass::from(render_pass, 0).unwrap();
+    GraphicsPipeline::new(
+        device,
+        None,
+        GraphicsPipelineCreateInfo {
+            stages: stages.into_iter().collect(),
+            vertex_input_state: Some(vertex_input_state),
+            input_assembly_state: Some(InputAssemblyState::default()),
+            // the viewport is set for each view
+            viewport_state: Some(ViewportState::default()),
+            rasterization_state: Some(RasterizationState {
+                cull_mode: if key.double_sided {
+                    CullMode::None
+                } else {
+                    CullMode::Back
+                },
+                ..Default::default()
+            }),
+            depth_stencil_state: Some(DepthStencilState {
+                depth: Some(DepthState {
+                    // blended primitives are drawn back to front after the others
+                    write_enable: key.alpha_mode != AlphaMode::Blend,
+                    compare_op: if key.reverse_z {
+                        CompareOp::Greater
+                    } else {
+                        CompareOp::Less
+                    },
+                }),
+                ..Default::default()
+            }),
+            multisample_state: Some(MultisampleState {
+                rasterization_samples: subpass.num_samples().unwrap(),
+                ..Default::default()
+            }),
+            color_blend_state: Some(ColorBlendState::with_attachment_states(
+                subpass.num_color_attachments(),
+                ColorBlendAttachmentState {
+                    blend: (key.alpha_mode == AlphaMode::Blend).then(AttachmentBlend::alpha),
+                    ..Default::default()
+                },
+            )),
+            subpass: Some(subpass.into()),
+            dynamic_state: [DynamicState::Viewport].into_iter().collect(),
+            ..GraphicsPipelineCreateInfo::layout(layout)
+        },
+    )
+    .unwrap()
+}
+
+fn build_shadow_pipeline(
+    device: Arc<Device>,
+    vertex_entrypoint: EntryPoint,
+    vertex_definitions: &[VertexBufferDescription],
+    render_pass: Arc<RenderPass>,
+) -> Arc<GraphicsPipeline> {
+    let vertex_input_state = vertex_definitions
+        .definition(&vertex_entrypoint.info().input_interface)
+        .unwrap();
+    let stages = [PipelineShaderStageCreateInfo::new(vertex_entrypoint)];
+    let layout = PipelineLayout::new(
+        device.clone(),
+        PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+            .into_pipeline_layout_create_info(device.clone())
+            .unwrap(),
+    )
+    .unwrap();
+    let subpass = Subpass::from(render_pass, 0).unwrap();
     GraphicsPipeline::new(
         device,
         None,
@@ -236,29 +553,31 @@ fn build_graphics_pipeline(
             viewport_state: Some(ViewportState {
                 viewports: [Viewport {
                     offset: [0.0, 0.0],
-                    extent: [dimensions[0] as f32, dimensions[1] as f32],
+                    extent: [SHADOW_MAP_SIZE as f32, SHADOW_MAP_SIZE as f32],
                     depth_range: 0.0..=1.0,
                 }]
                 .into_iter()
                 .collect(),
                 ..Default::default()
             }),
+            // both faces cast shadows, the bias keeps lit surfaces from shadowing themselves
             rasterization_state: Some(RasterizationState {
-                cull_mode: CullMode::Back,
+                cull_mode: CullMode::None,
+                depth_bias: Some(DepthBiasState {
+                    constant_factor: 1.25,
+                    clamp: 0.0,
+                    slope_factor: 1.75,
+                }),
                 ..Default::default()
             }),
             depth_stencil_state: Some(DepthStencilState {
-                depth: Some(DepthState::simple()),
-                ..Default::default()
-            }),
-            multisample_state: Some(MultisampleState {
-                rasterization_samples: subpass.num_samples().unwrap(),
+                depth: Some(DepthState {
+                    write_enable: true,
+                    compare_op: CompareOp::Less,
+                }),
                 ..Default::default()
             }),
-            color_blend_state: Some(ColorBlendState::with_attachment_states(
-                subpass.num_color_attachments(),
-                ColorBlendAttachmentState::default(),
-            )),
+            multisample_state: Some(MultisampleState::default()),
             subpass: Some(subpass.into()),
             ..GraphicsPipelineCreateInfo::layout(layout)
         },
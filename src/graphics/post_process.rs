@@ -0,0 +1,297 @@
+use std::sync::Arc;
+
+use vulkano::{
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    device::Device,
+    format::Format,
+    image::{
+        sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo},
+        view::ImageView,
+        Image, ImageCreateInfo, ImageType, ImageUsage,
+    },
+    memory::allocator::AllocationCreateInfo,
+    pipeline::Pipeline,
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass},
+};
+
+use crate::graphics::{
+    allocators::AllocatorCollection, engine::IMAGE_FORMAT, pipeline::PipelineCollection,
+};
+
+/// Format the scene is rendered to before tonemapping.
+pub const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+pub const BLOOM_LEVEL_COUNT: u32 = 6;
+
+/// Maps the HDR scene colors to the displayable range.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tonemapping {
+    Aces,
+    Reinhard,
+    AgX,
+}
+
+#[derive(Clone, Copy)]
+pub struct Bloom {
+    /// Only the colors brighter than it bloom, 0 to bloom everything.
+    pub threshold: f32,
+    pub intensity: f32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Vignette {
+    /// Darkening in the corners, from 0 to 1.
+    pub intensity: f32,
+    /// Share of the distance to the corners over which the darkening fades in, above 0 and up to 1.
+    pub smoothness: f32,
+}
+
+/// Intermediate images of the post-processing stages, sized like the view,
+/// with the descriptor sets binding them, made again only when the view is resized.
+pub struct PostProcessTargets {
+    /// Halved in size at each level, starting at half the view size.
+    pub bloom_levels: Vec<Arc<ImageView>>,
+    /// Receives the tonemapped view when FXAA is on.
+    pub ldr_framebuffer: Arc<Framebuffer>,
+    pub ldr_image: Arc<ImageView>,
+    pub sampler: Arc<Sampler>,
+    /// One per bloom level, each binds the larger image it is downsampled from.
+    pub downsample_sets: Vec<Arc<PersistentDescriptorSet>>,
+    /// One per bloom level but the smallest, each binds the smaller level it is added to.
+    pub upsample_sets: Vec<Arc<PersistentDescriptorSet>>,
+    /// Binds the HDR image, the first bloom level and `color_grading_lut`.
+    pub tonemap_set: Arc<PersistentDescriptorSet>,
+    /// The one `tonemap_set` binds, see `Engine::color_grading_lut`.
+    pub color_grading_lut: Option<Arc<ImageView>>,
+    pub fxaa_set: Arc<PersistentDescriptorSet>,
+}
+
+impl Tonemapping {
+    /// Value of the `tonemapping` push constant of the tonemapping shader.
+    pub fn shader_id(self) -> u32 {
+        match self {
+            Tonemapping::Aces => 0,
+            Tonemapping::Reinhard => 1,
+            Tonemapping::AgX => 2,
+        }
+    }
+}
+
+impl PostProcessTargets {
+    /// `hdr_image` is the image the view is rendered to.
+    pub fn new(
+        device: Arc<Device>,
+        allocators: &AllocatorCollection,
+        pipelines: &PipelineCollection,
+        hdr_image: &Arc<ImageView>,
+        extent: [u32; 3],
+        render_pass: Arc<RenderPass>,
+        color_grading_lut: Option<Arc<ImageView>>,
+    ) -> Self {
+        let [width, height, _] = extent;
+        let bloom_levels: Vec<_> = (1..=BLOOM_LEVEL_COUNT)
+            .map(|level| {
+                let image = Image::new(
+                    allocators.memory.clone(),
+                    ImageCreateInfo {
+                        image_type: ImageType::Dim2d,
+                        format: HDR_FORMAT,
+                        extent: [(width >> level).max(1), (height >> level).max(1), 1],
+                        usage: ImageUsage::STORAGE | ImageUsage::SAMPLED,
+                        ..Default::default()
+                    },
+                    AllocationCreateInfo::default(),
+                )
+                .unwrap();
+                ImageView::new_default(image).unwrap()
+            })
+            .collect();
+        let ldr_image = ImageView::new_default(
+            Image::new(
+                allocators.memory.clone(),
+                ImageCreateInfo {
+                    image_type: ImageType::Dim2d,
+                    format: IMAGE_FORMAT,
+                    extent,
+                    usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let ldr_framebuffer = Framebuffer::new(
+            render_pass,
+            FramebufferCreateInfo {
+                attachments: vec![ldr_image.clone()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let sampler = Sampler::new(
+            device,
+            SamplerCreateInfo {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let descriptor_set_allocator = &allocators.descriptor_set;
+        let sources = [hdr_image].into_iter().chain(&bloom_levels);
+        let downsample_sets = sources
+            .zip(&bloom_levels)
+            .map(|(source, level)| {
+                bloom_set(
+                    descriptor_set_allocator,
+                    pipelines,
+                    false,
+                    source,
+                    level,
+                    &sampler,
+                )
+            })
+            .collect();
+        let upsample_sets = bloom_levels
+            .windows(2)
+            .map(|pair| {
+                bloom_set(
+                    descriptor_set_allocator,
+                    pipelines,
+                    true,
+                    &pair[1],
+                    &pair[0],
+                    &sampler,
+                )
+            })
+            .collect();
+        let tonemap_set = tonemap_set(
+            descriptor_set_allocator,
+            pipelines,
+            hdr_image,
+            &bloom_levels[0],
+            color_grading_lut.as_ref(),
+            &sampler,
+        );
+        let fxaa_set = PersistentDescriptorSet::new(
+            descriptor_set_allocator,
+            pipelines.fxaa.layout().set_layouts()[0].clone(),
+            [WriteDescriptorSet::image_view_sampler(
+                0,
+                ldr_image.clone(),
+                sampler.clone(),
+            )],
+            [],
+        )
+        .unwrap();
+        PostProcessTargets {
+            bloom_levels,
+            ldr_framebuffer,
+            ldr_image,
+            sampler,
+            downsample_sets,
+            upsample_sets,
+            tonemap_set,
+            color_grading_lut,
+            fxaa_set,
+        }
+    }
+
+    /// Binds another color grading LUT for the tonemapping of `hdr_image`, unless it is already the one bound.
+    pub fn set_color_grading_lut(
+        &mut self,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        pipelines: &PipelineCollection,
+        hdr_image: &Arc<ImageView>,
+        color_grading_lut: Option<&Arc<ImageView>>,
+    ) {
+        let bound = match (&self.color_grading_lut, color_grading_lut) {
+            (Some(bound), Some(lut)) => Arc::ptr_eq(bound, lut),
+            (bound, lut) => bound.is_none() && lut.is_none(),
+        };
+        if bound {
+            return;
+        }
+        self.tonemap_set = tonemap_set(
+            descriptor_set_allocator,
+            pipelines,
+            hdr_image,
+            &self.bloom_levels[0],
+            color_grading_lut,
+            &self.sampler,
+        );
+        self.color_grading_lut = color_grading_lut.cloned();
+    }
+}
+
+/// Binds `source`, sampled, and `destination`, written, for the bloom downsampling or upsampling pipeline.
+fn bloom_set(
+    descriptor_set_allocator: &StandardDescriptorSetAllocator,
+    pipelines: &PipelineCollection,
+    upsample: bool,
+    source: &Arc<ImageView>,
+    destination: &Arc<ImageView>,
+    sampler: &Arc<Sampler>,
+) -> Arc<PersistentDescriptorSet> {
+    let pipeline = if upsample {
+        &pipelines.bloom_upsample
+    } else {
+        &pipelines.bloom_downsample
+    };
+    PersistentDescriptorSet::new(
+        descriptor_set_allocator,
+        pipeline.layout().set_layouts()[0].clone(),
+        [
+            WriteDescriptorSet::image_view_sampler(0, source.clone(), sampler.clone()),
+            WriteDescriptorSet::image_view(1, destination.clone()),
+        ],
+        [],
+    )
+    .unwrap()
+}
+
+fn tonemap_set(
+    descriptor_set_allocator: &StandardDescriptorSetAllocator,
+    pipelines: &PipelineCollection,
+    hdr_image: &Arc<ImageView>,
+    bloom: &Arc<ImageView>,
+    color_grading_lut: Option<&Arc<ImageView>>,
+    sampler: &Arc<Sampler>,
+) -> Arc<PersistentDescriptorSet> {
+    // the HDR image stands in for the LUT when there is none, it is never sampled then
+    let color_grading_lut = color_grading_lut.unwrap_or(hdr_image).clone();
+    PersistentDescriptorSet::new(
+        descriptor_set_allocator,
+        pipelines.tonemap.layout().set_layouts()[0].clone(),
+        [
+            WriteDescriptorSet::image_view_sampler(0, hdr_image.clone(), sampler.clone()),
+            WriteDescriptorSet::image_view_sampler(1, bloom.clone(), sampler.clone()),
+            WriteDescriptorSet::image_view_sampler(2, color_grading_lut, sampler.clone()),
+        ],
+        [],
+    )
+    .unwrap()
+}
+
+pub fn get_post_process_render_pass(device: Arc<Device>) -> Arc<RenderPass> {
+    vulkano::single_pass_renderpass!(
+        device,
+        attachments: {
+            color: {
+                format: IMAGE_FORMAT,
+                samples: 1,
+                // views drawn to the screen each cover a part of it
+                load_op: Load,
+                store_op: Store,
+            }
+        },
+        pass: {
+            color: [color],
+            depth_stencil: {}
+        }
+    )
+    .unwrap()
+}
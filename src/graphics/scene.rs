@@ -0,0 +1,120 @@
+use crate::{
+    geometry::Transform,
+    graphics::{engine::Asset, light::Light},
+};
+
+pub struct Scene {
+    pub nodes: Vec<SceneNode>,
+}
+
+pub struct SceneNode {
+    pub index: usize,
+    pub name: Option<String>,
+    pub transform: Transform,
+    /// Nodes drawing the same meshes share its buffers, animated ones are drawn in the pose of their own animator.
+    pub asset: Option<Asset>,
+    pub light: Option<Light>,
+    pub children: Vec<SceneNode>,
+}
+
+impl Scene {
+    pub fn find_by_name(&self, name: &str) -> Option<&SceneNode> {
+        self.nodes.iter().find_map(|node| node.find_by_name(name))
+    }
+
+    pub fn find_by_name_mut(&mut self, name: &str) -> Option<&mut SceneNode> {
+        self.nodes
+            .iter_mut()
+            .find_map(|node| node.find_by_name_mut(name))
+    }
+
+    pub fn find_by_index(&self, index: usize) -> Option<&SceneNode> {
+        self.nodes.iter().find_map(|node| node.find_by_index(index))
+    }
+
+    pub fn find_by_index_mut(&mut self, index: usize) -> Option<&mut SceneNode> {
+        self.nodes
+            .iter_mut()
+            .find_map(|node| node.find_by_index_mut(index))
+    }
+
+    /// Lists every asset of the scene with its world transform, the scene being placed at `transform`.
+    pub fn assets(&self, transform: Transform) -> Vec<(&Asset, Transform)> {
+        let mut result = Vec::new();
+        for node in &self.nodes {
+            node.collect_assets(transform, &mut result);
+        }
+        result
+    }
+
+    /// Lists every light of the scene in world space, the scene being placed at `transform`.
+    pub fn lights(&self, transform: Transform) -> Vec<Light> {
+        let mut result = Vec::new();
+        for node in &self.nodes {
+            node.collect_lights(transform, &mut result);
+        }
+        result
+    }
+}
+
+impl SceneNode {
+    pub fn find_by_name(&self, name: &str) -> Option<&SceneNode> {
+        if self.name.as_deref() == Some(name) {
+            return Some(self);
+        }
+        self.children
+            .iter()
+            .find_map(|child| child.find_by_name(name))
+    }
+
+    pub fn find_by_name_mut(&mut self, name: &str) -> Option<&mut SceneNode> {
+        if self.name.as_deref() == Some(name) {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find_map(|child| child.find_by_name_mut(name))
+    }
+
+    pub fn find_by_index(&self, index: usize) -> Option<&SceneNode> {
+        if self.index == index {
+            return Some(self);
+        }
+        self.children
+            .iter()
+            .find_map(|child| child.find_by_index(index))
+    }
+
+    pub fn find_by_index_mut(&mut self, index: usize) -> Option<&mut SceneNode> {
+        if self.index == index {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find_map(|child| child.find_by_index_mut(index))
+    }
+
+    fn collect_assets<'a>(
+        &'a self,
+        parent_transform: Transform,
+        result: &mut Vec<(&'a Asset, Transform)>,
+    ) {
+        let transform = parent_transform.compose(&self.transform);
+        if let Some(asset) = &self.asset {
+            result.push((asset, transform));
+        }
+        for child in &self.children {
+            child.collect_assets(transform, result);
+        }
+    }
+
+    fn collect_lights(&self, parent_transform: Transform, result: &mut Vec<Light>) {
+        let transform = parent_transform.compose(&self.transform);
+        if let Some(light) = &self.light {
+            result.push(light.transform(&transform));
+        }
+        for child in &self.children {
+            child.collect_lights(transform, result);
+        }
+    }
+}
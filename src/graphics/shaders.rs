@@ -19,50 +19,114 @@ pub mod fragment_shader {
     }
 }
 
-pub mod unindex_uvec4_shader {
+pub mod shadow_vertex_shader {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/graphics/shaders/shadow_vertex.glsl",
+    }
+}
+
+pub mod shadow_animated_vertex_shader {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/graphics/shaders/shadow_animated_vertex.glsl",
+    }
+}
+
+pub mod skybox_vertex_shader {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/graphics/shaders/skybox_vertex.glsl",
+    }
+}
+
+pub mod skybox_fragment_shader {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/graphics/shaders/skybox_fragment.glsl",
+    }
+}
+
+pub mod fullscreen_vertex_shader {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/graphics/shaders/fullscreen_vertex.glsl",
+    }
+}
+
+pub mod tonemap_fragment_shader {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/graphics/shaders/tonemap_fragment.glsl",
+    }
+}
+
+pub mod fxaa_fragment_shader {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/graphics/shaders/fxaa_fragment.glsl",
+    }
+}
+
+pub mod map_joints_shader {
     vulkano_shaders::shader! {
         ty: "compute",
-        path: "src/graphics/shaders/unindex_uvec4.glsl"
+        path: "src/graphics/shaders/map_joints.glsl"
     }
 }
-pub mod unindex_vec4_shader {
+
+pub mod cull_instances_shader {
     vulkano_shaders::shader! {
         ty: "compute",
-        path: "src/graphics/shaders/unindex_vec4.glsl"
+        path: "src/graphics/shaders/cull_instances.glsl"
     }
 }
 
-pub mod unindex_vec3_shader {
+pub mod spread_instance_count_shader {
     vulkano_shaders::shader! {
         ty: "compute",
-        path: "src/graphics/shaders/unindex_vec3.glsl"
+        path: "src/graphics/shaders/spread_instance_count.glsl"
     }
 }
 
-pub mod unindex_vec2_shader {
+pub mod equirectangular_to_cube_shader {
     vulkano_shaders::shader! {
         ty: "compute",
-        path: "src/graphics/shaders/unindex_vec2.glsl"
+        path: "src/graphics/shaders/equirectangular_to_cube.glsl"
     }
 }
 
-pub mod normal_shader {
+pub mod irradiance_shader {
     vulkano_shaders::shader! {
         ty: "compute",
-        path: "src/graphics/shaders/normal.glsl"
+        path: "src/graphics/shaders/irradiance.glsl"
     }
 }
 
-pub mod tangent_simple_shader {
+pub mod prefilter_shader {
     vulkano_shaders::shader! {
         ty: "compute",
-        path: "src/graphics/shaders/tangent_simple.glsl"
+        path: "src/graphics/shaders/prefilter.glsl"
     }
 }
 
-pub mod map_joints_shader {
+pub mod brdf_lut_shader {
     vulkano_shaders::shader! {
         ty: "compute",
-        path: "src/graphics/shaders/map_joints.glsl"
+        path: "src/graphics/shaders/brdf_lut.glsl"
+    }
+}
+
+pub mod bloom_downsample_shader {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/graphics/shaders/bloom_downsample.glsl"
+    }
+}
+
+pub mod bloom_upsample_shader {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/graphics/shaders/bloom_upsample.glsl"
     }
 }
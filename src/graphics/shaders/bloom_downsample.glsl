@@ -0,0 +1,33 @@
+#version 450
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(binding = 0) uniform sampler2D source;
+layout(binding = 1, rgba16f) uniform writeonly image2D destination;
+
+layout(push_constant) uniform PushConstants {
+    // only the colors brighter than it bloom, 0 past the first level
+    float threshold;
+} push;
+
+void main() {
+    ivec2 texel = ivec2(gl_GlobalInvocationID.xy);
+    ivec2 size = imageSize(destination);
+    if (any(greaterThanEqual(texel, size))) {
+        return;
+    }
+    vec2 texel_size = 1.0 / vec2(size);
+    vec2 uv = (vec2(texel) + 0.5) * texel_size;
+    // each bilinear tap averages four source texels
+    vec3 color = 0.25 * (
+        textureLod(source, uv + vec2(-0.5, -0.5) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(0.5, -0.5) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(-0.5, 0.5) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(0.5, 0.5) * texel_size, 0.0).rgb
+    );
+    if (push.threshold > 0.0) {
+        float brightness = max(color.r, max(color.g, color.b));
+        color *= max(brightness - push.threshold, 0.0) / max(brightness, 0.0001);
+    }
+    imageStore(destination, texel, vec4(color, 1.0));
+}
@@ -0,0 +1,30 @@
+#version 450
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(binding = 0) uniform sampler2D source;
+layout(binding = 1, rgba16f) uniform image2D destination;
+
+void main() {
+    ivec2 texel = ivec2(gl_GlobalInvocationID.xy);
+    ivec2 size = imageSize(destination);
+    if (any(greaterThanEqual(texel, size))) {
+        return;
+    }
+    vec2 uv = (vec2(texel) + 0.5) / vec2(size);
+    vec2 texel_size = 1.0 / vec2(textureSize(source, 0));
+    // 3x3 tent filter over the smaller level
+    vec3 color = 4.0 * textureLod(source, uv, 0.0).rgb;
+    color += 2.0 * (
+        textureLod(source, uv + vec2(-1.0, 0.0) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(1.0, 0.0) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(0.0, -1.0) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(0.0, 1.0) * texel_size, 0.0).rgb
+    );
+    color += textureLod(source, uv + vec2(-1.0, -1.0) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(1.0, -1.0) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(-1.0, 1.0) * texel_size, 0.0).rgb
+        + textureLod(source, uv + vec2(1.0, 1.0) * texel_size, 0.0).rgb;
+    vec3 previous = imageLoad(destination, texel).rgb;
+    imageStore(destination, texel, vec4(previous + color / 16.0, 1.0));
+}
@@ -0,0 +1,53 @@
+#version 450
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(binding = 0, rgba16f) uniform writeonly image2D brdf_lut;
+
+const float PI = 3.14159265359;
+const uint SAMPLE_COUNT = 512;
+
+vec2 hammersley(uint i) {
+    return vec2(float(i) / float(SAMPLE_COUNT), float(bitfieldReverse(i)) * 2.3283064365386963e-10);
+}
+
+float geometry_schlick(float NX, float k) {
+    return NX / (NX * (1.0 - k) + k);
+}
+
+// x: scale and y: bias of the specular reflectance at normal incidence
+vec2 integrate_brdf(float NV, float roughness) {
+    vec3 view_direction = vec3(sqrt(1.0 - NV * NV), 0.0, NV);
+    float alpha = roughness * roughness;
+    float k = alpha / 2.0;
+    vec2 result = vec2(0.0);
+    for (uint i = 0; i < SAMPLE_COUNT; i++) {
+        vec2 xi = hammersley(i);
+        float phi = 2.0 * PI * xi.x;
+        float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+        float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+        vec3 half_direction = vec3(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+        vec3 light_direction = normalize(2.0 * dot(view_direction, half_direction) * half_direction - view_direction);
+        float NL = max(light_direction.z, 0.0);
+        if (NL <= 0.0) {
+            continue;
+        }
+        float NH = max(half_direction.z, 0.0);
+        float VH = max(dot(view_direction, half_direction), 0.0);
+        float geometry = geometry_schlick(NV, k) * geometry_schlick(NL, k);
+        float visibility = geometry * VH / (NH * NV);
+        float fresnel = pow(1.0 - VH, 5.0);
+        result += vec2((1.0 - fresnel) * visibility, fresnel * visibility);
+    }
+    return result / float(SAMPLE_COUNT);
+}
+
+void main() {
+    ivec2 size = imageSize(brdf_lut);
+    if (gl_GlobalInvocationID.x >= size.x || gl_GlobalInvocationID.y >= size.y) {
+        return;
+    }
+    // x: cosine of the view angle, y: perceptual roughness
+    vec2 uv = (vec2(gl_GlobalInvocationID.xy) + 0.5) / vec2(size);
+    imageStore(brdf_lut, ivec2(gl_GlobalInvocationID.xy), vec4(integrate_brdf(uv.x, uv.y), 0.0, 1.0));
+}
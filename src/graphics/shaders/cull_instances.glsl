@@ -0,0 +1,88 @@
+#version 450
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+struct DrawIndexedIndirectCommand {
+    uint index_count;
+    uint instance_count;
+    uint first_index;
+    int vertex_offset;
+    uint first_instance;
+};
+
+layout(set = 0, binding = 0) uniform Culling {
+    // (a, b, c, d) for the world points where a * x + b * y + c * z + d >= 0
+    vec4 planes[6];
+    vec3 bounds_min;
+    uint instance_count;
+    vec3 bounds_max;
+    // joints of each instance, 0 when they are not posed
+    uint transform_length;
+} culling;
+
+layout(set = 0, binding = 1) readonly buffer Models {
+    mat4 models[];
+};
+
+layout(set = 0, binding = 2) readonly buffer Poses {
+    mat4 poses[];
+};
+
+layout(set = 0, binding = 3) writeonly buffer VisibleModels {
+    mat4 visible_models[];
+};
+
+layout(set = 0, binding = 4) writeonly buffer VisiblePoses {
+    mat4 visible_poses[];
+};
+
+// one per primitive, the first one counts the visible instances for all of them
+layout(set = 0, binding = 5) buffer Commands {
+    DrawIndexedIndirectCommand commands[];
+};
+
+vec3 corner(vec3 bounds_min, vec3 bounds_max, int i) {
+    return mix(bounds_min, bounds_max, vec3(i & 1, (i >> 1) & 1, (i >> 2) & 1));
+}
+
+void main() {
+    uint instance = gl_GlobalInvocationID.x;
+    if (instance >= culling.instance_count) {
+        return;
+    }
+    uint first_joint = instance * culling.transform_length;
+    vec3 bounds_min = culling.bounds_min;
+    vec3 bounds_max = culling.bounds_max;
+    // skinned vertices are weighted averages of the vertex moved by each joint,
+    // they stay within the box around the corners moved by every joint
+    if (culling.transform_length > 0) {
+        bounds_min = vec3(3.4e38);
+        bounds_max = vec3(-3.4e38);
+        for (uint joint = first_joint; joint < first_joint + culling.transform_length; joint++) {
+            for (int i = 0; i < 8; i++) {
+                vec3 moved_corner = (poses[joint] * vec4(corner(culling.bounds_min, culling.bounds_max, i), 1.0)).xyz;
+                bounds_min = min(bounds_min, moved_corner);
+                bounds_max = max(bounds_max, moved_corner);
+            }
+        }
+    }
+    mat4 model = models[instance];
+    vec4 corners[8];
+    for (int i = 0; i < 8; i++) {
+        corners[i] = model * vec4(corner(bounds_min, bounds_max, i), 1.0);
+    }
+    for (int plane = 0; plane < 6; plane++) {
+        bool outside = true;
+        for (int i = 0; i < 8; i++) {
+            outside = outside && dot(culling.planes[plane], corners[i]) < 0.0;
+        }
+        if (outside) {
+            return;
+        }
+    }
+    uint slot = atomicAdd(commands[0].instance_count, 1u);
+    visible_models[slot] = model;
+    for (uint joint = 0; joint < culling.transform_length; joint++) {
+        visible_poses[slot * culling.transform_length + joint] = poses[first_joint + joint];
+    }
+}
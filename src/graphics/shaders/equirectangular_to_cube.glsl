@@ -0,0 +1,31 @@
+#version 450
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(binding = 0) uniform sampler2D equirectangular;
+layout(binding = 1, rgba16f) uniform writeonly image2DArray cube;
+
+const float PI = 3.14159265359;
+
+// direction of a texel of a cube face, faces ordered +X, -X, +Y, -Y, +Z, -Z
+vec3 cube_direction(uvec3 texel, int size) {
+    vec2 st = (vec2(texel.xy) + 0.5) / float(size) * 2.0 - 1.0;
+    switch (texel.z) {
+        case 0: return normalize(vec3(1.0, -st.y, -st.x));
+        case 1: return normalize(vec3(-1.0, -st.y, st.x));
+        case 2: return normalize(vec3(st.x, 1.0, st.y));
+        case 3: return normalize(vec3(st.x, -1.0, -st.y));
+        case 4: return normalize(vec3(st.x, -st.y, 1.0));
+        default: return normalize(vec3(-st.x, -st.y, -1.0));
+    }
+}
+
+void main() {
+    int size = imageSize(cube).x;
+    if (gl_GlobalInvocationID.x >= size || gl_GlobalInvocationID.y >= size) {
+        return;
+    }
+    vec3 direction = cube_direction(gl_GlobalInvocationID, size);
+    vec2 uv = vec2(atan(direction.z, direction.x) / (2.0 * PI) + 0.5, acos(direction.y) / PI);
+    imageStore(cube, ivec3(gl_GlobalInvocationID), vec4(textureLod(equirectangular, uv, 0.0).rgb, 1.0));
+}
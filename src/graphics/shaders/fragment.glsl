@@ -1,42 +1,73 @@
 #version 450
 
-layout(binding = 1) uniform UniformBufferObject {
+// per draw, shared with the animated vertex shader
+layout(push_constant) uniform PushConstants {
     vec4 color;
+    vec3 emissive;
     float metalness;
     float roughness;
-} ubo;
-layout(binding = 3) uniform sampler2D tex;
-layout(binding = 4) uniform sampler2D tex_metal;
-layout(binding = 5) uniform sampler2D tex_normal;
+    float occlusion_strength;
+    float alpha_cutoff;
+    uint transform_length;
+    // dithers the fragments out when cross-fading levels of detail, 0 to keep them
+    float lod_fade;
+} push;
 
+// the set 0 is bound once per view, the set 1 is the material of the primitive
+layout(set = 1, binding = 0) uniform sampler2D tex;
+layout(set = 1, binding = 1) uniform sampler2D tex_metal;
+layout(set = 1, binding = 2) uniform sampler2D tex_normal;
+layout(set = 1, binding = 3) uniform sampler2D tex_occlusion;
+layout(set = 1, binding = 4) uniform sampler2D tex_emissive;
 
-layout(location = 0) in vec3 light_direction;
+struct Light {
+    // range is 0 for an infinite range
+    vec4 position_range;
+    // kind is one of the *_LIGHT constants
+    vec4 direction_kind;
+    vec4 color_intensity;
+    // x: scale and y: offset of the spot attenuation
+    vec4 cone;
+    // x: first shadow layer and y: shadow layer count, 0 without shadows
+    ivec4 shadow;
+};
+layout(set = 0, binding = 1) readonly buffer Lights {
+    Light lights[];
+};
+layout(set = 0, binding = 2) uniform sampler2DArrayShadow shadow_map;
+layout(set = 0, binding = 3) readonly buffer ShadowLayers {
+    mat4 shadow_view_proj[];
+};
+layout(set = 0, binding = 4) uniform samplerCube irradiance;
+layout(set = 0, binding = 5) uniform samplerCube prefiltered;
+layout(set = 0, binding = 6) uniform sampler2D brdf_lut;
+
+
+layout(location = 0) in vec3 world_position;
 layout(location = 1) in vec3 camera_direction;
 layout(location = 2) in vec3 normal_direction;
-layout(location = 3) in vec3 tangent_direction;
+layout(location = 3) in vec4 tangent_direction;
 layout(location = 4) in vec2 tex_coords;
 layout(location = 5) in vec2 tex_metal_coords;
 layout(location = 6) in vec2 tex_normal_coords;
+layout(location = 7) in vec2 tex_occlusion_coords;
+layout(location = 8) in vec2 tex_emissive_coords;
 
 layout(location = 0) out vec4 f_color;
 
 const float lambertian_diffuse = 0.31830988618; // 1/pi
-const float ambient_light = 0.01;
+const float POINT_LIGHT = 0.0;
+const float SPOT_LIGHT = 1.0;
+const float DIRECTIONAL_LIGHT = 2.0;
+const float SHADOW_NORMAL_OFFSET = 0.02;
 
-void main() {
-    vec3 bitangent = cross(normal_direction, tangent_direction);
-    vec4 tex_normal = texture(tex_normal, tex_normal_coords) * 2.0 - 1.0;
-    vec3 normal = normalize(tex_normal.r * tangent_direction + tex_normal.g * bitangent + tex_normal.b * normal_direction);
-    vec4 tex_color = texture(tex, tex_coords) * ubo.color;
-    vec4 tex_metal = texture(tex_metal, tex_metal_coords);
-    float metalness = ubo.metalness * tex_metal.x;
-    float roughness = ubo.roughness * tex_metal.y;
+// returns the coefficients of the colored part and of the white specular part
+vec2 shade(vec3 normal, vec3 light_direction, float metalness, float roughness) {
     vec3 half_direction = normalize(camera_direction + light_direction);
     float NL = dot(normal, light_direction);
     float NV = dot(normal, camera_direction);
     float NH = dot(normal, half_direction);
     float non_metalness = 1 - metalness;
-    roughness = roughness * roughness * roughness * roughness;
     float non_roughness = 1 - roughness;
     float microfacet_distribution_coeff = 1 - non_roughness * NH * NH;
     float visibility_coeff = (abs(NL) + sqrt(roughness + non_roughness * NL * NL)) * (abs(NV) + sqrt(roughness + non_roughness * NV * NV));
@@ -51,7 +82,91 @@ void main() {
     float fresnel_mix_coeff = 0.04 + 0.96 * schlick_coeff;
     float albedo_coeff = max(NL, 0.0);
     float white_coeff = lambertian_diffuse * albedo_coeff * (metalness * specular * schlick_coeff + non_metalness * specular * fresnel_mix_coeff);
-    float colored_coeff = lambertian_diffuse * (albedo_coeff * (metalness * specular * (1 - schlick_coeff) + non_metalness * (1 - fresnel_mix_coeff)) + ambient_light);
-    vec3 color_temp = tex_color.rgb * colored_coeff + vec3(white_coeff);
+    float colored_coeff = lambertian_diffuse * albedo_coeff * (metalness * specular * (1 - schlick_coeff) + non_metalness * (1 - fresnel_mix_coeff));
+    return vec2(colored_coeff, white_coeff);
+}
+
+// returns the lit fraction of the fragment, filtered over 3x3 texels
+float shadow(Light light, vec3 position) {
+    for (int layer = light.shadow.x; layer < light.shadow.x + light.shadow.y; layer++) {
+        vec4 shadow_position = shadow_view_proj[layer] * vec4(position, 1.0);
+        vec3 shadow_coords = shadow_position.xyz / shadow_position.w;
+        vec2 uv = shadow_coords.xy * 0.5 + 0.5;
+        // cascades are sorted from the nearest, the first one containing the fragment is the sharpest
+        if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0))) || shadow_coords.z > 1.0) {
+            continue;
+        }
+        vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0).xy);
+        float lit = 0.0;
+        for (int x = -1; x <= 1; x++) {
+            for (int y = -1; y <= 1; y++) {
+                lit += texture(shadow_map, vec4(uv + vec2(x, y) * texel, layer, shadow_coords.z));
+            }
+        }
+        return lit / 9.0;
+    }
+    return 1.0;
+}
+
+void main() {
+    // cross-fading levels of detail keep complementary fragments, below lod_fade or from -lod_fade up
+    float dither = fract(52.9829189 * fract(dot(gl_FragCoord.xy, vec2(0.06711056, 0.00583715))));
+    if (push.lod_fade > 0.0 && dither >= push.lod_fade || push.lod_fade < 0.0 && dither < -push.lod_fade) {
+        discard;
+    }
+    // back faces are only drawn for double-sided materials, and are lit from their side with the whole TBN mirrored
+    float facing = gl_FrontFacing ? 1.0 : -1.0;
+    vec3 bitangent = cross(normal_direction, tangent_direction.xyz) * tangent_direction.w * facing;
+    vec3 face_normal = normal_direction * facing;
+    vec3 face_tangent = tangent_direction.xyz * facing;
+    vec4 tex_normal = texture(tex_normal, tex_normal_coords) * 2.0 - 1.0;
+    vec3 normal = normalize(tex_normal.r * face_tangent + tex_normal.g * bitangent + tex_normal.b * face_normal);
+    vec4 tex_color = texture(tex, tex_coords) * push.color;
+    if (tex_color.a < push.alpha_cutoff) {
+        discard;
+    }
+    vec4 tex_metal = texture(tex_metal, tex_metal_coords);
+    float metalness = push.metalness * tex_metal.x;
+    float perceptual_roughness = push.roughness * tex_metal.y;
+    float roughness = perceptual_roughness * perceptual_roughness;
+    roughness = roughness * roughness;
+    float occlusion = 1.0 + push.occlusion_strength * (texture(tex_occlusion, tex_occlusion_coords).r - 1.0);
+    vec3 emissive = texture(tex_emissive, tex_emissive_coords).rgb * push.emissive;
+    // image based ambient light, with the split sum approximation for the specular part
+    float NV = clamp(dot(normal, camera_direction), 0.0, 1.0);
+    vec3 reflectance = mix(vec3(0.04), tex_color.rgb, metalness);
+    vec2 brdf = texture(brdf_lut, vec2(NV, perceptual_roughness)).rg;
+    float max_lod = float(textureQueryLevels(prefiltered) - 1);
+    vec3 reflection = reflect(-camera_direction, normal);
+    vec3 ambient_specular = textureLod(prefiltered, reflection, perceptual_roughness * max_lod).rgb * (reflectance * brdf.x + brdf.y);
+    vec3 ambient_diffuse = texture(irradiance, normal).rgb * tex_color.rgb * (1.0 - metalness);
+    vec3 color_temp = (ambient_diffuse + ambient_specular) * occlusion + emissive;
+    // offsetting along the normal keeps surfaces from shadowing themselves
+    vec3 shadow_position = world_position + face_normal * SHADOW_NORMAL_OFFSET;
+    for (int i = 0; i < lights.length(); i++) {
+        Light light = lights[i];
+        vec3 light_direction = -light.direction_kind.xyz;
+        float attenuation = light.color_intensity.w;
+        if (light.direction_kind.w != DIRECTIONAL_LIGHT) {
+            vec3 offset = light.position_range.xyz - world_position;
+            float distance_squared = max(dot(offset, offset), 0.0001);
+            light_direction = offset * inversesqrt(distance_squared);
+            attenuation /= distance_squared;
+            float range = light.position_range.w;
+            if (range > 0.0) {
+                float range_ratio = distance_squared / (range * range);
+                attenuation *= clamp(1.0 - range_ratio * range_ratio, 0.0, 1.0);
+            }
+        }
+        if (light.direction_kind.w == SPOT_LIGHT) {
+            float cone_attenuation = clamp(dot(light.direction_kind.xyz, -light_direction) * light.cone.x + light.cone.y, 0.0, 1.0);
+            attenuation *= cone_attenuation * cone_attenuation;
+        }
+        if (light.shadow.y > 0) {
+            attenuation *= shadow(light, shadow_position);
+        }
+        vec2 coeffs = shade(normal, light_direction, metalness, roughness);
+        color_temp += light.color_intensity.rgb * attenuation * (tex_color.rgb * coeffs.x + vec3(coeffs.y));
+    }
     f_color = vec4(color_temp, tex_color.a);
-}
\ No newline at end of file
+}
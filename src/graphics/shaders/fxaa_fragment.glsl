@@ -0,0 +1,56 @@
+#version 450
+
+const float REDUCE_MIN = 1.0 / 128.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float SPAN_MAX = 8.0;
+
+layout(binding = 0) uniform sampler2D ldr;
+
+layout(location = 0) in vec2 uv;
+
+layout(location = 0) out vec4 f_color;
+
+// perceptual luma of a linear color
+float luma(vec3 color) {
+    return sqrt(dot(color, vec3(0.299, 0.587, 0.114)));
+}
+
+void main() {
+    vec2 texel_size = 1.0 / vec2(textureSize(ldr, 0));
+    vec3 color_nw = texture(ldr, uv + vec2(-1.0, -1.0) * texel_size).rgb;
+    vec3 color_ne = texture(ldr, uv + vec2(1.0, -1.0) * texel_size).rgb;
+    vec3 color_sw = texture(ldr, uv + vec2(-1.0, 1.0) * texel_size).rgb;
+    vec3 color_se = texture(ldr, uv + vec2(1.0, 1.0) * texel_size).rgb;
+    vec3 color_m = texture(ldr, uv).rgb;
+    float luma_nw = luma(color_nw);
+    float luma_ne = luma(color_ne);
+    float luma_sw = luma(color_sw);
+    float luma_se = luma(color_se);
+    float luma_m = luma(color_m);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // blurs along the edge, perpendicular to the luma gradient
+    vec2 direction = vec2(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float direction_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL,
+        REDUCE_MIN
+    );
+    float inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * inverse_direction_min, -SPAN_MAX, SPAN_MAX) * texel_size;
+
+    vec3 color_a = 0.5 * (
+        texture(ldr, uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + texture(ldr, uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 color_b = 0.5 * color_a + 0.25 * (
+        texture(ldr, uv - direction * 0.5).rgb
+        + texture(ldr, uv + direction * 0.5).rgb
+    );
+    // the wider blur overshoots when it crosses another edge
+    float luma_b = luma(color_b);
+    f_color = vec4(luma_b < luma_min || luma_b > luma_max ? color_a : color_b, 1.0);
+}
@@ -0,0 +1,46 @@
+#version 450
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(binding = 0) uniform samplerCube environment;
+layout(binding = 1, rgba16f) uniform writeonly image2DArray irradiance;
+
+const float PI = 3.14159265359;
+const float SAMPLE_DELTA = 0.05;
+// mip level of the environment whose faces are about 32 texels wide
+const float SOURCE_SIZE_LOG2 = 5.0;
+
+vec3 cube_direction(uvec3 texel, int size) {
+    vec2 st = (vec2(texel.xy) + 0.5) / float(size) * 2.0 - 1.0;
+    switch (texel.z) {
+        case 0: return normalize(vec3(1.0, -st.y, -st.x));
+        case 1: return normalize(vec3(-1.0, -st.y, st.x));
+        case 2: return normalize(vec3(st.x, 1.0, st.y));
+        case 3: return normalize(vec3(st.x, -1.0, -st.y));
+        case 4: return normalize(vec3(st.x, -st.y, 1.0));
+        default: return normalize(vec3(-st.x, -st.y, -1.0));
+    }
+}
+
+void main() {
+    int size = imageSize(irradiance).x;
+    if (gl_GlobalInvocationID.x >= size || gl_GlobalInvocationID.y >= size) {
+        return;
+    }
+    vec3 normal = cube_direction(gl_GlobalInvocationID, size);
+    vec3 up = abs(normal.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 right = normalize(cross(up, normal));
+    up = cross(normal, right);
+    float lod = max(log2(float(textureSize(environment, 0).x)) - SOURCE_SIZE_LOG2, 0.0);
+    vec3 sum = vec3(0.0);
+    float sample_count = 0.0;
+    for (float phi = 0.0; phi < 2.0 * PI; phi += SAMPLE_DELTA) {
+        for (float theta = 0.0; theta < 0.5 * PI; theta += SAMPLE_DELTA) {
+            vec3 direction = sin(theta) * (cos(phi) * right + sin(phi) * up) + cos(theta) * normal;
+            sum += textureLod(environment, direction, lod).rgb * cos(theta) * sin(theta);
+            sample_count += 1.0;
+        }
+    }
+    // stored divided by pi, so that the diffuse light is the albedo times the irradiance
+    imageStore(irradiance, ivec3(gl_GlobalInvocationID), vec4(PI * sum / sample_count, 1.0));
+}
@@ -0,0 +1,71 @@
+#version 450
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(binding = 0) uniform samplerCube environment;
+layout(binding = 1, rgba16f) uniform writeonly image2DArray prefiltered;
+layout(push_constant) uniform PushConstants {
+    float roughness;
+} push;
+
+const float PI = 3.14159265359;
+const uint SAMPLE_COUNT = 256;
+
+vec3 cube_direction(uvec3 texel, int size) {
+    vec2 st = (vec2(texel.xy) + 0.5) / float(size) * 2.0 - 1.0;
+    switch (texel.z) {
+        case 0: return normalize(vec3(1.0, -st.y, -st.x));
+        case 1: return normalize(vec3(-1.0, -st.y, st.x));
+        case 2: return normalize(vec3(st.x, 1.0, st.y));
+        case 3: return normalize(vec3(st.x, -1.0, -st.y));
+        case 4: return normalize(vec3(st.x, -st.y, 1.0));
+        default: return normalize(vec3(-st.x, -st.y, -1.0));
+    }
+}
+
+vec2 hammersley(uint i) {
+    return vec2(float(i) / float(SAMPLE_COUNT), float(bitfieldReverse(i)) * 2.3283064365386963e-10);
+}
+
+vec3 importance_sample_ggx(vec2 xi, vec3 normal, float alpha) {
+    float phi = 2.0 * PI * xi.x;
+    float cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    float sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+    vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+    return normalize(sin_theta * (cos(phi) * tangent + sin(phi) * bitangent) + cos_theta * normal);
+}
+
+void main() {
+    int size = imageSize(prefiltered).x;
+    if (gl_GlobalInvocationID.x >= size || gl_GlobalInvocationID.y >= size) {
+        return;
+    }
+    // the view and reflection directions are assumed equal to the normal
+    vec3 normal = cube_direction(gl_GlobalInvocationID, size);
+    float alpha = push.roughness * push.roughness;
+    float source_size = float(textureSize(environment, 0).x);
+    float texel_solid_angle = 4.0 * PI / (6.0 * source_size * source_size);
+    vec3 sum = vec3(0.0);
+    float total_weight = 0.0;
+    for (uint i = 0; i < SAMPLE_COUNT; i++) {
+        vec3 half_direction = importance_sample_ggx(hammersley(i), normal, alpha);
+        vec3 light_direction = normalize(2.0 * dot(normal, half_direction) * half_direction - normal);
+        float NL = dot(normal, light_direction);
+        if (NL <= 0.0) {
+            continue;
+        }
+        // sampling a blurrier mip for unlikely directions avoids bright dots
+        float NH = max(dot(normal, half_direction), 0.0);
+        float alpha_squared = alpha * alpha;
+        float denominator = NH * NH * (alpha_squared - 1.0) + 1.0;
+        float distribution = alpha_squared / (PI * denominator * denominator);
+        float pdf = distribution / 4.0 + 0.0001;
+        float sample_solid_angle = 1.0 / (float(SAMPLE_COUNT) * pdf + 0.0001);
+        float lod = push.roughness == 0.0 ? 0.0 : max(0.5 * log2(sample_solid_angle / texel_solid_angle), 0.0);
+        sum += textureLod(environment, light_direction, lod).rgb * NL;
+        total_weight += NL;
+    }
+    imageStore(prefiltered, ivec3(gl_GlobalInvocationID), vec4(sum / total_weight, 1.0));
+}
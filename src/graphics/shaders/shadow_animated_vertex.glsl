@@ -0,0 +1,24 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    mat4 view_proj;
+    uint transform_length;
+} push;
+
+layout(binding = 0) buffer Transforms {
+    mat4 transforms[];
+};
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in mat4 model;
+layout(location = 5) in vec4 weights;
+layout(location = 6) in uvec4 joints;
+
+
+void main() {
+    mat4 animated_transform = transforms[joints.x + push.transform_length * gl_InstanceIndex] * weights.x
+        + transforms[joints.y + push.transform_length * gl_InstanceIndex] * weights.y
+        + transforms[joints.z + push.transform_length * gl_InstanceIndex] * weights.z
+        + transforms[joints.w + push.transform_length * gl_InstanceIndex] * weights.w;
+    gl_Position = push.view_proj * model * animated_transform * vec4(position, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    mat4 view_proj;
+} push;
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in mat4 model;
+
+
+void main() {
+    gl_Position = push.view_proj * model * vec4(position, 1.0);
+}
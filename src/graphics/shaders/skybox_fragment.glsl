@@ -0,0 +1,11 @@
+#version 450
+
+layout(binding = 0) uniform samplerCube skybox;
+
+layout(location = 0) in vec3 direction;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = vec4(texture(skybox, direction).rgb, 1.0);
+}
@@ -0,0 +1,18 @@
+#version 450
+
+layout(push_constant) uniform PushConstants {
+    mat4 camera_rotation;
+    // tangents of the half field of view, horizontally and vertically
+    vec2 view_scale;
+    float far_depth;
+} push;
+
+layout(location = 0) out vec3 direction;
+
+
+void main() {
+    // a single triangle covering the screen, on the far plane
+    vec2 position = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2) * 2.0 - 1.0;
+    gl_Position = vec4(position, push.far_depth, 1.0);
+    direction = (push.camera_rotation * vec4(-position * push.view_scale, 1.0, 0.0)).xyz;
+}
@@ -0,0 +1,28 @@
+#version 450
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+struct DrawIndexedIndirectCommand {
+    uint index_count;
+    uint instance_count;
+    uint first_index;
+    int vertex_offset;
+    uint first_instance;
+};
+
+layout(push_constant) uniform PushConstants {
+    uint command_count;
+} push;
+
+// the culling pass only counts the visible instances in the first command
+layout(set = 0, binding = 0) buffer Commands {
+    DrawIndexedIndirectCommand commands[];
+};
+
+void main() {
+    uint command = gl_GlobalInvocationID.x + 1;
+    if (command >= push.command_count) {
+        return;
+    }
+    commands[command].instance_count = commands[0].instance_count;
+}
@@ -0,0 +1,135 @@
+#version 450
+
+const uint TONEMAPPING_ACES = 0;
+const uint TONEMAPPING_REINHARD = 1;
+const uint TONEMAPPING_AGX = 2;
+
+layout(binding = 0) uniform sampler2D hdr;
+layout(binding = 1) uniform sampler2D bloom;
+// 2D strip of the slices of a 3D LUT, sorted by increasing blue
+layout(binding = 2) uniform sampler2D color_grading_lut;
+
+layout(push_constant) uniform PushConstants {
+    float exposure;
+    uint tonemapping;
+    float bloom_intensity;
+    uint color_grading;
+    float vignette_intensity;
+    float vignette_smoothness;
+} push;
+
+layout(location = 0) in vec2 uv;
+
+layout(location = 0) out vec4 f_color;
+
+// Stephen Hill's fit of the ACES reference rendering and output transforms
+vec3 aces(vec3 color) {
+    const mat3 input_matrix = mat3(
+        0.59719, 0.07600, 0.02840,
+        0.35458, 0.90834, 0.13383,
+        0.04823, 0.01566, 0.83777
+    );
+    const mat3 output_matrix = mat3(
+        1.60475, -0.10208, -0.00327,
+        -0.53108, 1.10813, -0.07276,
+        -0.07367, -0.00605, 1.07602
+    );
+    color = input_matrix * color;
+    vec3 a = color * (color + 0.0245786) - 0.000090537;
+    vec3 b = color * (0.983729 * color + 0.4329510) + 0.238081;
+    return output_matrix * (a / b);
+}
+
+vec3 reinhard(vec3 color) {
+    return color / (1.0 + color);
+}
+
+// polynomial fit of the default AgX contrast curve
+vec3 agx_contrast(vec3 x) {
+    vec3 x2 = x * x;
+    vec3 x4 = x2 * x2;
+    return 15.5 * x4 * x2
+        - 40.14 * x4 * x
+        + 31.96 * x4
+        - 6.868 * x2 * x
+        + 0.4298 * x2
+        + 0.1191 * x
+        - 0.00232;
+}
+
+vec3 agx(vec3 color) {
+    const mat3 inset = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104
+    );
+    const mat3 outset = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+    );
+    const float min_ev = -12.47393;
+    const float max_ev = 4.026069;
+    color = clamp(log2(max(inset * color, 1e-10)), min_ev, max_ev);
+    color = agx_contrast((color - min_ev) / (max_ev - min_ev));
+    // the curve outputs display encoded values, the sRGB target encodes them again
+    return pow(max(outset * color, 0.0), vec3(2.2));
+}
+
+vec3 srgb_encode(vec3 color) {
+    return mix(
+        color * 12.92,
+        1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055,
+        greaterThan(color, vec3(0.0031308))
+    );
+}
+
+vec3 srgb_decode(vec3 color) {
+    return mix(
+        color / 12.92,
+        pow((color + 0.055) / 1.055, vec3(2.4)),
+        greaterThan(color, vec3(0.04045))
+    );
+}
+
+// the LUT maps display encoded colors
+vec3 color_grade(vec3 color) {
+    float size = float(textureSize(color_grading_lut, 0).y);
+    vec3 encoded = srgb_encode(color) * (size - 1.0);
+    float slice = floor(encoded.b);
+    vec2 slice_uv = (encoded.rg + 0.5) / vec2(size * size, size);
+    vec3 low = texture(color_grading_lut, slice_uv + vec2(slice / size, 0.0)).rgb;
+    vec3 high = texture(color_grading_lut, slice_uv + vec2(min(slice + 1.0, size - 1.0) / size, 0.0)).rgb;
+    return srgb_decode(mix(low, high, encoded.b - slice));
+}
+
+float vignette() {
+    // 1 in the corners
+    float distance = length(uv - 0.5) * sqrt(2.0);
+    return 1.0 - push.vignette_intensity * smoothstep(1.0 - push.vignette_smoothness, 1.0, distance);
+}
+
+void main() {
+    vec3 color = texture(hdr, uv).rgb;
+    // the bloom image isn't written to while the bloom is off
+    if (push.bloom_intensity > 0.0) {
+        color += texture(bloom, uv).rgb * push.bloom_intensity;
+    }
+    color *= push.exposure;
+    switch (push.tonemapping) {
+        case TONEMAPPING_ACES:
+            color = aces(color);
+            break;
+        case TONEMAPPING_REINHARD:
+            color = reinhard(color);
+            break;
+        case TONEMAPPING_AGX:
+            color = agx(color);
+            break;
+    }
+    color = clamp(color, 0.0, 1.0);
+    if (push.color_grading != 0) {
+        color = color_grade(color);
+    }
+    f_color = vec4(color * vignette(), 1.0);
+}
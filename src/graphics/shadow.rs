@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use vulkano::{
+    device::Device,
+    format::Format,
+    image::{
+        sampler::{BorderColor, Filter, Sampler, SamplerAddressMode, SamplerCreateInfo},
+        view::{ImageView, ImageViewCreateInfo, ImageViewType},
+        Image, ImageAspects, ImageCreateInfo, ImageSubresourceRange, ImageType, ImageUsage,
+    },
+    memory::allocator::{AllocationCreateInfo, MemoryAllocator},
+    pipeline::graphics::depth_stencil::CompareOp,
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass},
+};
+
+use crate::{
+    geometry::{Transform, Vec3},
+    graphics::{
+        camera::{Camera, Projection},
+        light::Light,
+    },
+};
+
+pub const SHADOW_FORMAT: Format = Format::D32_SFLOAT;
+pub const SHADOW_MAP_SIZE: u32 = 2048;
+/// Shadow maps available per frame, lights beyond it are drawn without shadows, see `Engine::shadowless_lights`.
+pub const SHADOW_LAYER_COUNT: u32 = 16;
+pub const CASCADE_COUNT: usize = 4;
+/// Distance from the camera up to which directional lights cast shadows.
+pub const SHADOW_DISTANCE: f32 = 50.0;
+// how far behind a cascade casters are still taken into account
+const CASTER_DISTANCE: f32 = 50.0;
+const SPOT_NEAR: f32 = 0.05;
+
+pub struct ShadowMaps {
+    pub render_pass: Arc<RenderPass>,
+    /// One per layer of `view`.
+    pub framebuffers: Vec<Arc<Framebuffer>>,
+    pub view: Arc<ImageView>,
+    pub sampler: Arc<Sampler>,
+}
+
+impl ShadowMaps {
+    pub fn new(device: Arc<Device>, memory_allocator: Arc<dyn MemoryAllocator>) -> Self {
+        let render_pass = get_shadow_render_pass(device.clone());
+        let image = Image::new(
+            memory_allocator,
+            ImageCreateInfo {
+                image_type: ImageType::Dim2d,
+                format: SHADOW_FORMAT,
+                extent: [SHADOW_MAP_SIZE, SHADOW_MAP_SIZE, 1],
+                array_layers: SHADOW_LAYER_COUNT,
+                usage: ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::SAMPLED,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .unwrap();
+        let framebuffers = (0..SHADOW_LAYER_COUNT)
+            .map(|layer| {
+                let view = ImageView::new(
+                    image.clone(),
+                    ImageViewCreateInfo {
+                        view_type: ImageViewType::Dim2d,
+                        subresource_range: ImageSubresourceRange {
+                            aspects: ImageAspects::DEPTH,
+                            mip_levels: 0..1,
+                            array_layers: layer..layer + 1,
+                        },
+                        ..ImageViewCreateInfo::from_image(&image)
+                    },
+                )
+                .unwrap();
+                Framebuffer::new(
+                    render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![view],
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+            })
+            .collect();
+        let view = ImageView::new(
+            image.clone(),
+            ImageViewCreateInfo {
+                view_type: ImageViewType::Dim2dArray,
+                ..ImageViewCreateInfo::from_image(&image)
+            },
+        )
+        .unwrap();
+        let sampler = Sampler::new(
+            device,
+            SamplerCreateInfo {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                address_mode: [SamplerAddressMode::ClampToBorder; 3],
+                // everything outside of a shadow map is lit
+                border_color: BorderColor::FloatOpaqueWhite,
+                compare: Some(CompareOp::LessOrEqual),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        ShadowMaps {
+            render_pass,
+            framebuffers,
+            view,
+            sampler,
+        }
+    }
+}
+
+/// Computes the cameras drawing the shadow maps of a light, one per cascade for directional lights,
+/// their views are square. Returns nothing for lights without shadows.
+pub fn light_cameras(light: &Light, camera: &Camera, aspect: f32) -> Vec<Camera> {
+    match *light {
+        Light::Directional {
+            direction,
+            cast_shadows: true,
+            ..
+        } => {
+            let splits = cascade_splits(camera.near(), SHADOW_DISTANCE.min(camera.far()));
+            splits
+                .windows(2)
+                .map(|split| {
+                    let corners = camera.frustum_corners(aspect, split[0], split[1]);
+                    cascade_camera(direction, &corners)
+                })
+                .collect()
+        }
+        Light::Spot {
+            position,
+            direction,
+            range,
+            outer_cone_angle,
+            cast_shadows: true,
+            ..
+        } => {
+            let [x, y, z] = position;
+            let [dx, dy, dz] = direction;
+            vec![Camera {
+                transform: Transform::look_at(position, [x + dx, y + dy, z + dz]),
+                projection: Projection::Perspective {
+                    fov: (2.0 * outer_cone_angle).min(3.0),
+                    near: SPOT_NEAR,
+                    far: Some(range.unwrap_or(SHADOW_DISTANCE)),
+                },
+                reverse_z: false,
+            }]
+        }
+        _ => Vec::new(),
+    }
+}
+
+// blends logarithmic and uniform splits, the first giving more resolution close to the camera
+fn cascade_splits(near: f32, far: f32) -> [f32; CASCADE_COUNT + 1] {
+    let mut splits = [near; CASCADE_COUNT + 1];
+    for (i, split) in splits.iter_mut().enumerate().skip(1) {
+        let ratio = i as f32 / CASCADE_COUNT as f32;
+        let logarithmic = near * (far / near).powf(ratio);
+        let uniform = near + (far - near) * ratio;
+        *split = 0.5 * (logarithmic + uniform);
+    }
+    splits
+}
+
+fn cascade_camera(direction: [f32; 3], corners: &[Vec3]) -> Camera {
+    let center = corners
+        .iter()
+        .fold(Vec3::from([0.0; 3]), |sum, &corner| sum + corner)
+        * (1.0 / corners.len() as f32);
+    let radius = corners
+        .iter()
+        .map(|&corner| {
+            let offset = corner - center;
+            offset.dot(offset).sqrt()
+        })
+        .fold(0.0, f32::max);
+    // a bounding sphere and a center snapped to texels keep the shadows from shimmering when the camera moves
+    let radius = (radius * 16.0).ceil() / 16.0;
+    let texel_size = 2.0 * radius / SHADOW_MAP_SIZE as f32;
+    let rotation = Transform::look_at([0.0; 3], direction);
+    let [x, y, z] = rotation.reverse().apply_point(center.into());
+    Camera {
+        transform: rotation.translate([
+            (x / texel_size).round() * texel_size,
+            (y / texel_size).round() * texel_size,
+            z - radius - CASTER_DISTANCE,
+        ]),
+        projection: Projection::Orthographic {
+            size: radius,
+            near: 0.0,
+            far: 2.0 * radius + CASTER_DISTANCE,
+        },
+        reverse_z: false,
+    }
+}
+
+fn get_shadow_render_pass(device: Arc<Device>) -> Arc<RenderPass> {
+    vulkano::single_pass_renderpass!(
+        device,
+        attachments: {
+            depth_stencil: {
+                format: SHADOW_FORMAT,
+                samples: 1,
+                load_op: Clear,
+                store_op: Store,
+            }
+        },
+        pass: {
+            color: [],
+            depth_stencil: {depth_stencil}
+        }
+    )
+    .unwrap()
+}
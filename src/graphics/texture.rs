@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+use vulkano::{
+    command_buffer::{
+        AutoCommandBufferBuilder, BlitImageInfo, ImageBlit, PrimaryAutoCommandBuffer,
+    },
+    device::Device,
+    image::{
+        sampler::{
+            Filter, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode, LOD_CLAMP_NONE,
+        },
+        Image, ImageAspects, ImageSubresourceLayers,
+    },
+};
+
+use crate::graphics::load_error::LoadError;
+
+/// What a texture of a primitive holds, which decides the formats it is uploaded in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureUsage {
+    /// Base color and emissive textures, in sRGB.
+    Color,
+    /// Metalness in the blue channel and roughness in the green one.
+    MetalRoughness,
+    Normal,
+    Occlusion,
+}
+
+/// How a texture is filtered and wrapped, the engine shares a sampler between the textures with the same one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureSampler {
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    /// `None` to sample the full size image only.
+    pub mipmap_mode: Option<SamplerMipmapMode>,
+    /// Along U then V.
+    pub address_mode: [SamplerAddressMode; 2],
+    /// Texels blended along the slope of surfaces seen at grazing angles, 1 to turn it off.
+    pub anisotropy: u32,
+}
+
+impl Default for TextureSampler {
+    /// Trilinear filtering, repeated along U and V.
+    fn default() -> Self {
+        TextureSampler {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_mode: Some(SamplerMipmapMode::Linear),
+            address_mode: [SamplerAddressMode::Repeat; 2],
+            anisotropy: 1,
+        }
+    }
+}
+
+impl TextureSampler {
+    /// Filters left undefined by the file are linear.
+    /// `anisotropy` is only applied to textures filtered linearly between mip levels,
+    /// an `anisotropy` number in the extras of the texture overrides it, 1 turning it off.
+    pub fn from_gltf(texture: &gltf::Texture, anisotropy: Option<u32>) -> Self {
+        let sampler = texture.sampler();
+        let mag_filter = match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => Filter::Nearest,
+            Some(MagFilter::Linear) | None => Filter::Linear,
+        };
+        let (min_filter, mipmap_mode) = match sampler.min_filter() {
+            Some(MinFilter::Nearest) => (Filter::Nearest, None),
+            Some(MinFilter::Linear) => (Filter::Linear, None),
+            Some(MinFilter::NearestMipmapNearest) => {
+                (Filter::Nearest, Some(SamplerMipmapMode::Nearest))
+            }
+            Some(MinFilter::LinearMipmapNearest) => {
+                (Filter::Linear, Some(SamplerMipmapMode::Nearest))
+            }
+            Some(MinFilter::NearestMipmapLinear) => {
+                (Filter::Nearest, Some(SamplerMipmapMode::Linear))
+            }
+            Some(MinFilter::LinearMipmapLinear) | None => {
+                (Filter::Linear, Some(SamplerMipmapMode::Linear))
+            }
+        };
+        let trilinear = mag_filter == Filter::Linear
+            && min_filter == Filter::Linear
+            && mipmap_mode == Some(SamplerMipmapMode::Linear);
+        TextureSampler {
+            mag_filter,
+            min_filter,
+            mipmap_mode,
+            address_mode: [sampler.wrap_s(), sampler.wrap_t()].map(address_mode),
+            anisotropy: match extras_anisotropy(texture).or(anisotropy) {
+                Some(anisotropy) if trilinear => anisotropy.max(1),
+                _ => 1,
+            },
+        }
+    }
+
+    /// Clamps the anisotropy to what `device` supports, turning it off without the `sampler_anisotropy` feature.
+    pub fn create_info(&self, device: &Device) -> SamplerCreateInfo {
+        let max_anisotropy = device
+            .enabled_features()
+            .sampler_anisotropy
+            .then(|| device.physical_device().properties().max_sampler_anisotropy);
+        self.create_info_with_max_anisotropy(max_anisotropy)
+    }
+
+    /// Clamps the anisotropy to `max_anisotropy`, turning it off when there is none.
+    pub fn create_info_with_max_anisotropy(
+        &self,
+        max_anisotropy: Option<f32>,
+    ) -> SamplerCreateInfo {
+        let anisotropy = max_anisotropy
+            .filter(|_| self.anisotropy > 1)
+            .map(|max_anisotropy| (self.anisotropy as f32).min(max_anisotropy));
+        let [u, v] = self.address_mode;
+        SamplerCreateInfo {
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_mode: self.mipmap_mode.unwrap_or(SamplerMipmapMode::Nearest),
+            address_mode: [u, v, SamplerAddressMode::Repeat],
+            anisotropy,
+            // a maximum above 0 still tells minification from magnification
+            lod: match self.mipmap_mode {
+                Some(_) => 0.0..=LOD_CLAMP_NONE,
+                None => 0.0..=0.25,
+            },
+            ..Default::default()
+        }
+    }
+}
+
+fn extras_anisotropy(texture: &gltf::Texture) -> Option<u32> {
+    let extras: gltf::json::Value =
+        gltf::json::deserialize::from_str(texture.extras().as_ref()?.get()).ok()?;
+    Some(extras.get("anisotropy")?.as_u64()? as u32)
+}
+
+/// Levels of a full mip chain for an image whose largest side is `size`.
+pub fn mip_level_count(size: u32) -> u32 {
+    u32::BITS - size.leading_zeros()
+}
+
+/// Fills the mip levels after the first one by blitting each level into the next.
+pub fn generate_mipmaps(
+    builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+    image: Arc<Image>,
+) -> Result<(), LoadError> {
+    let [width, height, _] = image.extent();
+    for level in 1..image.mip_levels() {
+        let layers = ImageSubresourceLayers {
+            aspects: ImageAspects::COLOR,
+            mip_level: level - 1,
+            array_layers: 0..image.array_layers(),
+        };
+        builder
+            .blit_image(BlitImageInfo {
+                regions: [ImageBlit {
+                    src_subresource: layers.clone(),
+                    src_offsets: [
+                        [0, 0, 0],
+                        [
+                            (width >> (level - 1)).max(1),
+                            (height >> (level - 1)).max(1),
+                            1,
+                        ],
+                    ],
+                    dst_subresource: ImageSubresourceLayers {
+                        mip_level: level,
+                        ..layers
+                    },
+                    dst_offsets: [
+                        [0, 0, 0],
+                        [(width >> level).max(1), (height >> level).max(1), 1],
+                    ],
+                    ..Default::default()
+                }]
+                .into(),
+                filter: Filter::Linear,
+                ..BlitImageInfo::images(image.clone(), image.clone())
+            })
+            .map_err(LoadError::gpu_upload)?;
+    }
+    Ok(())
+}
+
+fn address_mode(wrapping_mode: WrappingMode) -> SamplerAddressMode {
+    match wrapping_mode {
+        WrappingMode::ClampToEdge => SamplerAddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => SamplerAddressMode::MirroredRepeat,
+        WrappingMode::Repeat => SamplerAddressMode::Repeat,
+    }
+}
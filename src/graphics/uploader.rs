@@ -0,0 +1,124 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use vulkano::{
+    command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer},
+    descriptor_set::{layout::DescriptorSetLayout, PersistentDescriptorSet, WriteDescriptorSet},
+    device::{Device, Queue},
+    image::{
+        sampler::{Sampler, SamplerCreateInfo},
+        view::ImageView,
+    },
+    sync::{self, GpuFuture},
+};
+
+use crate::graphics::{
+    allocators::AllocatorCollection, engine::Texture, environment::EnvironmentPipelines,
+    load_error::LoadError, texture::TextureSampler,
+};
+
+/// What loading assets needs of the engine, cloned into the threads loading in the background.
+#[derive(Clone)]
+pub struct Uploader {
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
+    pub allocators: Arc<AllocatorCollection>,
+    /// Of the descriptor sets binding the textures of a primitive.
+    pub material_layout: Arc<DescriptorSetLayout>,
+    /// Samples the default textures.
+    pub sampler: Arc<Sampler>,
+    /// Shared by the loaded textures sampled alike.
+    pub samplers: Arc<Mutex<HashMap<TextureSampler, Arc<Sampler>>>>,
+    pub environment_pipelines: EnvironmentPipelines,
+    /// Uploaded once by `new`.
+    default_images: Option<DefaultImages>,
+}
+
+/// Images of the textures a primitive doesn't have, shared by all of them.
+#[derive(Clone)]
+pub struct DefaultImages {
+    /// White, also for the emissive texture, as the emissive factor alone then applies.
+    pub color: Arc<ImageView>,
+    pub metal: Arc<ImageView>,
+    /// Flat.
+    pub normal: Arc<ImageView>,
+    pub occlusion: Arc<ImageView>,
+}
+
+impl Uploader {
+    pub fn new(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        allocators: Arc<AllocatorCollection>,
+        material_layout: Arc<DescriptorSetLayout>,
+        environment_pipelines: EnvironmentPipelines,
+    ) -> Self {
+        let sampler =
+            Sampler::new(device.clone(), SamplerCreateInfo::simple_repeat_linear()).unwrap();
+        let mut uploader = Uploader {
+            device,
+            queue,
+            allocators,
+            material_layout,
+            sampler,
+            samplers: Arc::new(Mutex::new(HashMap::new())),
+            environment_pipelines,
+            default_images: None,
+        };
+        let default_images = uploader
+            .batch(|builder| uploader.upload_default_images(builder))
+            .unwrap();
+        uploader.default_images = Some(default_images);
+        uploader
+    }
+
+    pub fn default_images(&self) -> &DefaultImages {
+        self.default_images
+            .as_ref()
+            .expect("the default images are uploaded by Uploader::new")
+    }
+
+    /// Submits the transfers `record` makes in one command buffer and waits for them,
+    /// nothing is submitted when it fails.
+    pub fn batch<T>(
+        &self,
+        record: impl FnOnce(
+            &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        ) -> Result<T, LoadError>,
+    ) -> Result<T, LoadError> {
+        let mut builder = AutoCommandBufferBuilder::primary(
+            &self.allocators.command_buffer,
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .map_err(LoadError::gpu_upload)?;
+        let loaded = record(&mut builder)?;
+        let command_buffer = builder.build().map_err(LoadError::gpu_upload)?;
+        let future = sync::now(self.device.clone())
+            .then_execute(self.queue.clone(), command_buffer)
+            .map_err(LoadError::gpu_upload)?
+            .then_signal_fence_and_flush()
+            .map_err(LoadError::gpu_upload)?;
+        future.wait(None).map_err(LoadError::gpu_upload)?;
+        Ok(loaded)
+    }
+
+    /// Binds the textures of a primitive, given in the order of `Primitive::textures`.
+    pub fn create_material(&self, textures: [&Texture; 5]) -> Arc<PersistentDescriptorSet> {
+        PersistentDescriptorSet::new(
+            &self.allocators.descriptor_set,
+            self.material_layout.clone(),
+            textures.into_iter().enumerate().map(|(binding, texture)| {
+                WriteDescriptorSet::image_view_sampler(
+                    binding as u32,
+                    texture.image.clone(),
+                    texture.sampler.clone(),
+                )
+            }),
+            [],
+        )
+        .unwrap()
+    }
+}
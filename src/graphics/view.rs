@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use vulkano::{
+    device::Device,
+    image::{view::ImageView, Image, ImageCreateInfo, ImageType, ImageUsage, SampleCount},
+    memory::allocator::{AllocationCreateInfo, MemoryAllocator},
+    render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass},
+};
+
+use crate::graphics::{
+    allocators::AllocatorCollection,
+    camera::Camera,
+    engine::{DEPTH_FORMAT, IMAGE_FORMAT},
+    pipeline::PipelineCollection,
+    post_process::{PostProcessTargets, HDR_FORMAT},
+};
+
+/// A camera and where it is drawn.
+#[derive(Clone)]
+pub struct View {
+    pub camera: Camera,
+    pub target: ViewTarget,
+}
+
+#[derive(Clone)]
+pub enum ViewTarget {
+    /// Rectangle of the window, in fractions of its size from its top left corner.
+    Screen {
+        offset: [f32; 2],
+        extent: [f32; 2],
+    },
+    Texture(RenderTexture),
+}
+
+/// Image views can be drawn to, made by `Loader::create_render_texture`.
+/// Put `image` in a `Texture` of a primitive then call `Loader::update_materials` to show it,
+/// views drawn earlier in the frame are seen.
+#[derive(Clone)]
+pub struct RenderTexture {
+    pub image: Arc<ImageView>,
+    pub framebuffer: Arc<Framebuffer>,
+}
+
+/// Images a view is rendered to before being post-processed to its target, sized like it.
+pub struct ViewTargets {
+    pub hdr_framebuffer: Arc<Framebuffer>,
+    pub hdr_image: Arc<ImageView>,
+    pub post_process: PostProcessTargets,
+}
+
+impl View {
+    /// Covers the whole window.
+    pub fn new(camera: Camera) -> Self {
+        View {
+            camera,
+            target: ViewTarget::Screen {
+                offset: [0.0, 0.0],
+                extent: [1.0, 1.0],
+            },
+        }
+    }
+}
+
+impl RenderTexture {
+    /// `render_pass` is the post-processing render pass, which draws the final image.
+    pub fn new(
+        memory_allocator: Arc<dyn MemoryAllocator>,
+        dimensions: [u32; 2],
+        render_pass: Arc<RenderPass>,
+    ) -> Self {
+        let image = ImageView::new_default(
+            Image::new(
+                memory_allocator,
+                ImageCreateInfo {
+                    image_type: ImageType::Dim2d,
+                    format: IMAGE_FORMAT,
+                    extent: [dimensions[0], dimensions[1], 1],
+                    usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let framebuffer = Framebuffer::new(
+            render_pass,
+            FramebufferCreateInfo {
+                attachments: vec![image.clone()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        RenderTexture { image, framebuffer }
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        let [width, height, _] = self.image.image().extent();
+        [width, height]
+    }
+}
+
+impl ViewTargets {
+    /// `color_grading_lut` is bound for the tonemapping, see `PostProcessTargets::set_color_grading_lut`.
+    pub fn new(
+        device: Arc<Device>,
+        allocators: &AllocatorCollection,
+        pipelines: &PipelineCollection,
+        dimensions: [u32; 2],
+        render_pass: Arc<RenderPass>,
+        post_process_render_pass: Arc<RenderPass>,
+        color_grading_lut: Option<Arc<ImageView>>,
+    ) -> Self {
+        let extent = [dimensions[0], dimensions[1], 1];
+        let (hdr_framebuffer, hdr_image) =
+            get_hdr_framebuffer(allocators.memory.clone(), extent, render_pass);
+        let post_process = PostProcessTargets::new(
+            device,
+            allocators,
+            pipelines,
+            &hdr_image,
+            extent,
+            post_process_render_pass,
+            color_grading_lut,
+        );
+        ViewTargets {
+            hdr_framebuffer,
+            hdr_image,
+            post_process,
+        }
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        let [width, height, _] = self.hdr_image.image().extent();
+        [width, height]
+    }
+}
+
+fn get_hdr_framebuffer(
+    memory_allocator: Arc<dyn MemoryAllocator>,
+    extent: [u32; 3],
+    render_pass: Arc<RenderPass>,
+) -> (Arc<Framebuffer>, Arc<ImageView>) {
+    let samples = render_pass.attachments()[0].samples;
+    let hdr_image = ImageView::new_default(
+        Image::new(
+            memory_allocator.clone(),
+            ImageCreateInfo {
+                image_type: ImageType::Dim2d,
+                format: HDR_FORMAT,
+                extent,
+                usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let depth_buffer = ImageView::new_default(
+        Image::new(
+            memory_allocator.clone(),
+            ImageCreateInfo {
+                image_type: ImageType::Dim2d,
+                format: DEPTH_FORMAT,
+                extent,
+                usage: ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+                samples,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let attachments = if samples == SampleCount::Sample1 {
+        vec![hdr_image.clone(), depth_buffer]
+    } else {
+        let intermediary = ImageView::new_default(
+            Image::new(
+                memory_allocator,
+                ImageCreateInfo {
+                    image_type: ImageType::Dim2d,
+                    format: HDR_FORMAT,
+                    extent,
+                    usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+                    samples,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        vec![intermediary, hdr_image.clone(), depth_buffer]
+    };
+    let framebuffer = Framebuffer::new(
+        render_pass,
+        FramebufferCreateInfo {
+            attachments,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    (framebuffer, hdr_image)
+}
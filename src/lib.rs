@@ -1,14 +1,30 @@
-use graphics::engine::Asset;
+use graphics::{
+    engine::Asset,
+    environment::Environment,
+    light::Light,
+    load_error::LoadError,
+    load_handle::LoadHandle,
+    load_options::LoadOptions,
+    post_process::{Bloom, Tonemapping, Vignette},
+    scene::Scene,
+};
 use input::Input;
 use std::{sync::Arc, time::Instant};
-use vulkano::{instance::InstanceExtensions, swapchain::Surface};
+use vulkano::{image::view::ImageView, instance::InstanceExtensions, swapchain::Surface};
 use winit::{
     event::{DeviceEvent, Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
 
-use crate::{geometry::Transform, graphics::engine::Engine};
+use crate::{
+    geometry::Transform,
+    graphics::{
+        camera::Camera,
+        engine::Engine,
+        view::{RenderTexture, View},
+    },
+};
 
 pub mod animation_system;
 pub mod geometry;
@@ -17,6 +33,9 @@ pub mod input;
 
 pub enum DisplayRequest<'a> {
     In3D(&'a Asset, &'a [Transform], Option<&'a [Transform]>),
+    Scene(&'a Scene, &'a [Transform]),
+    /// Lights the frame and is drawn as its background.
+    Environment(&'a Environment),
 }
 
 pub enum GameSceneState {
@@ -77,16 +96,73 @@ impl GameLoop {
     }
 }
 pub trait Loader {
-    fn load(&mut self, asset: &str, node: &str) -> Asset;
+    fn load_with_options(
+        &mut self,
+        asset: &str,
+        node: &str,
+        options: &LoadOptions,
+    ) -> Result<Asset, LoadError>;
+    fn load_scene_with_options(
+        &mut self,
+        asset: &str,
+        options: &LoadOptions,
+    ) -> Result<Scene, LoadError>;
+    /// Loads like `load_with_options` on another thread, polling the handle doesn't block the frames.
+    /// The transfers of the asset are submitted together once it is read.
+    fn load_in_background(
+        &mut self,
+        asset: &str,
+        node: &str,
+        options: &LoadOptions,
+    ) -> LoadHandle<Asset>;
+    /// Loads like `load_scene_with_options` on another thread, see `load_in_background`.
+    fn load_scene_in_background(&mut self, asset: &str, options: &LoadOptions)
+        -> LoadHandle<Scene>;
+    /// Loads an equirectangular image, usually HDR.
+    fn load_environment(&mut self, asset: &str) -> Result<Environment, LoadError>;
+    /// Loads a color grading LUT of size N stored as N slices of N² texels side by side, by increasing blue.
+    fn load_color_grading_lut(&mut self, asset: &str) -> Result<Arc<ImageView>, LoadError>;
+    /// Loads like `load_environment` on another thread, see `load_in_background`.
+    fn load_environment_in_background(&mut self, asset: &str) -> LoadHandle<Environment>;
+    /// Loads like `load_color_grading_lut` on another thread, see `load_in_background`.
+    fn load_color_grading_lut_in_background(&mut self, asset: &str) -> LoadHandle<Arc<ImageView>>;
+    /// Binds the textures of the primitives of `asset` again, after some of their images were replaced.
+    fn update_materials(&mut self, asset: &mut Asset);
+    /// Makes an image views can be drawn to, then used as the texture of primitives.
+    fn create_render_texture(&mut self, dimensions: [u32; 2]) -> RenderTexture;
+
+    fn load(&mut self, asset: &str, node: &str) -> Result<Asset, LoadError> {
+        self.load_with_options(asset, node, &LoadOptions::default())
+    }
+
+    fn load_scene(&mut self, asset: &str) -> Result<Scene, LoadError> {
+        self.load_scene_with_options(asset, &LoadOptions::default())
+    }
 }
 
 pub trait Drawer {
-    fn draw(
-        &mut self,
-        camera_transform: Transform,
-        light_position: [f32; 3],
-        display_request: &[DisplayRequest],
-    );
+    /// Draws the views in order, those drawing to a render texture should come before the ones showing it.
+    fn draw_views(&mut self, views: &[View], lights: &[Light], display_request: &[DisplayRequest]);
+    /// Applies to the following frames, 1 by default.
+    fn set_exposure(&mut self, exposure: f32);
+    /// Applies to the following frames, ACES by default.
+    fn set_tonemapping(&mut self, tonemapping: Tonemapping);
+    /// Turns the 4x multisampling on or off, it is on by default.
+    fn set_msaa(&mut self, msaa: bool);
+    fn set_bloom(&mut self, bloom: Option<Bloom>);
+    /// Antialiases the final frame, usually in place of the multisampling.
+    fn set_fxaa(&mut self, fxaa: bool);
+    /// Takes a LUT made by `Loader::load_color_grading_lut`.
+    fn set_color_grading_lut(&mut self, color_grading_lut: Option<Arc<ImageView>>);
+    fn set_vignette(&mut self, vignette: Option<Vignette>);
+    /// Dithers each level of detail out for the next one while its screen coverage is less than
+    /// `1 + lod_fade` times the one it switches at, off by default.
+    fn set_lod_fade(&mut self, lod_fade: Option<f32>);
+
+    /// Draws `camera` to the whole window.
+    fn draw(&mut self, camera: Camera, lights: &[Light], display_request: &[DisplayRequest]) {
+        self.draw_views(&[View::new(camera)], lights, display_request);
+    }
 }
 
 pub fn run(event_loop: EventLoop<()>, window: Window, gamescene: Box<dyn GameScene>) {
@@ -3,8 +3,16 @@ use std::{f32::consts::TAU, time::Instant};
 use winit::{event::VirtualKeyCode, event_loop::EventLoop, window::Icon, window::WindowBuilder};
 
 use kor_engine::{
-    geometry::Transform, graphics::engine::Asset, input::Input, run, DisplayRequest, Drawer,
-    GameScene, GameSceneState, Loader,
+    geometry::Transform,
+    graphics::{
+        camera::Camera,
+        engine::Asset,
+        light::Light,
+        load_handle::{LoadHandle, LoadStatus},
+        load_options::LoadOptions,
+    },
+    input::Input,
+    run, DisplayRequest, Drawer, GameScene, GameSceneState, Loader,
 };
 
 const SIZE: usize = 10;
@@ -13,15 +21,21 @@ const ROTATION_SPEED: f32 = 0.5;
 const TRANSLATION_SPEED: f32 = 5.0;
 const FRAME_TIME: f32 = 1.0 / 60.0;
 const ANIMATION_LOOP_TIME: f32 = 1.1;
+const SUN: Light = Light::Directional {
+    direction: [0.0, -1.0, 1.0],
+    color: [1.0; 3],
+    intensity: 1.0,
+    cast_shadows: true,
+};
 
 struct Scene {
     frequency: f32,
     start_time: Instant,
     angle: f32,
     camera: Transform,
-    fox: Option<Asset>,
-    monkey: Option<Asset>,
-    helmet: Option<Asset>,
+    fox: Option<LoadHandle<Asset>>,
+    monkey: Option<LoadHandle<Asset>>,
+    helmet: Option<LoadHandle<Asset>>,
 }
 
 impl Scene {
@@ -43,9 +57,14 @@ impl Scene {
 
 impl GameScene for Scene {
     fn load(&mut self, loader: &mut dyn Loader) {
-        self.fox = Some(loader.load("./Fox.glb", "fox"));
-        self.monkey = Some(loader.load("./monkey.glb", "Suzanne"));
-        self.helmet = Some(loader.load("./DamagedHelmet.glb", "node_damagedHelmet_-6514"));
+        let options = LoadOptions::default();
+        self.fox = Some(loader.load_in_background("./Fox.glb", "fox", &options));
+        self.monkey = Some(loader.load_in_background("./monkey.glb", "Suzanne", &options));
+        self.helmet = Some(loader.load_in_background(
+            "./DamagedHelmet.glb",
+            "node_damagedHelmet_-6514",
+            &options,
+        ));
     }
 
     fn update(&mut self, input: &Input) -> GameSceneState {
@@ -75,6 +94,26 @@ impl GameScene for Scene {
     }
 
     fn display(&mut self, drawer: &mut dyn Drawer) {
+        let loaded = (
+            self.fox.as_mut().map(LoadHandle::poll),
+            self.monkey.as_mut().map(LoadHandle::poll),
+            self.helmet.as_mut().map(LoadHandle::poll),
+        );
+        let (fox, monkey, helmet) = match loaded {
+            (
+                Some(LoadStatus::Ready(fox)),
+                Some(LoadStatus::Ready(monkey)),
+                Some(LoadStatus::Ready(helmet)),
+            ) => (fox, monkey, helmet),
+            (None, _, _) | (_, None, _) | (_, _, None) => panic!("scene not loaded"),
+            (Some(LoadStatus::Failed(error)), _, _)
+            | (_, Some(LoadStatus::Failed(error)), _)
+            | (_, _, Some(LoadStatus::Failed(error))) => {
+                panic!("failed to load the scene: {}", error)
+            }
+            // the empty scene can be looked around while the assets load
+            _ => return drawer.draw(Camera::new(self.camera), &[SUN], &[]),
+        };
         let duration = Instant::now().duration_since(self.start_time).as_millis();
         let t = (duration as f32) / 1000.0 % ANIMATION_LOOP_TIME;
         let mut foxes = Vec::with_capacity(SIZE * SIZE * SIZE);
@@ -88,7 +127,7 @@ impl GameScene for Scene {
                             .rotate_y(self.angle)
                             .scale([0.02; 3]),
                     );
-                    if let Some(Asset::Animated(_, animator)) = &mut self.fox {
+                    if let Asset::Animated(_, animator) = &mut *fox {
                         animator.reset();
                         animator.animate(2, t);
                         foxes_poses.extend(animator.compute_transforms());
@@ -98,33 +137,28 @@ impl GameScene for Scene {
                 }
             }
         }
-        match (&mut self.fox, &mut self.monkey, &mut self.helmet) {
-            (Some(fox), Some(monkey), Some(helmet)) => {
-                drawer.draw(
-                    self.camera,
-                    [0.0, 7000.0, -7000.0],
-                    &[
-                        DisplayRequest::In3D(fox, &foxes, Some(&foxes_poses)),
-                        DisplayRequest::In3D(
-                            monkey,
-                            &[Transform::new()
-                                .translate([-3.5, 0.0, 0.0])
-                                .rotate_y(self.angle)],
-                            None,
-                        ),
-                        DisplayRequest::In3D(
-                            helmet,
-                            &[Transform::new()
-                                .translate([-7.0, 0.0, 0.0])
-                                .rotate_y(self.angle)
-                                .rotate_x(1.57)],
-                            None,
-                        ),
-                    ],
-                );
-            }
-            _ => panic!("scene not fully loaded"),
-        }
+        drawer.draw(
+            Camera::new(self.camera),
+            &[SUN],
+            &[
+                DisplayRequest::In3D(fox, &foxes, Some(&foxes_poses)),
+                DisplayRequest::In3D(
+                    monkey,
+                    &[Transform::new()
+                        .translate([-3.5, 0.0, 0.0])
+                        .rotate_y(self.angle)],
+                    None,
+                ),
+                DisplayRequest::In3D(
+                    helmet,
+                    &[Transform::new()
+                        .translate([-7.0, 0.0, 0.0])
+                        .rotate_y(self.angle)
+                        .rotate_x(1.57)],
+                    None,
+                ),
+            ],
+        );
     }
 }
 
@@ -2,8 +2,16 @@ use image::io::Reader as ImageReader;
 use winit::{event::VirtualKeyCode, event_loop::EventLoop, window::Icon, window::WindowBuilder};
 
 use kor_engine::{
-    geometry::Transform, graphics::engine::Asset, input::Input, run, DisplayRequest, Drawer,
-    GameScene, GameSceneState, Loader,
+    geometry::Transform,
+    graphics::{
+        camera::Camera,
+        engine::Asset,
+        light::Light,
+        load_handle::{LoadHandle, LoadStatus},
+        load_options::LoadOptions,
+    },
+    input::Input,
+    run, DisplayRequest, Drawer, GameScene, GameSceneState, Loader,
 };
 
 const ROTATION_SPEED: f32 = 0.5;
@@ -12,13 +20,19 @@ const FRAME_TIME: f32 = 1.0 / 60.0;
 const DISTANCE_MIN: f32 = 0.2;
 const DISTANCE_MAX: f32 = 20.0;
 const ANGLE_X_MAX: f32 = 0.8;
+const SUN: Light = Light::Directional {
+    direction: [0.0, -7.0, -2.0],
+    color: [1.0; 3],
+    intensity: 1.0,
+    cast_shadows: true,
+};
 
 struct Scene {
     angle: f32,
     camera_angle_y: f32,
     camera_angle_x: f32,
     distance: f32,
-    helmet: Option<Asset>,
+    helmet: Option<LoadHandle<Asset>>,
 }
 
 impl Scene {
@@ -35,7 +49,11 @@ impl Scene {
 
 impl GameScene for Scene {
     fn load(&mut self, loader: &mut dyn Loader) {
-        self.helmet = Some(loader.load("./DamagedHelmet.glb", "node_damagedHelmet_-6514"));
+        self.helmet = Some(loader.load_in_background(
+            "./DamagedHelmet.glb",
+            "node_damagedHelmet_-6514",
+            &LoadOptions::default(),
+        ));
     }
 
     fn update(&mut self, input: &Input) -> GameSceneState {
@@ -60,11 +78,11 @@ impl GameScene for Scene {
             .rotate_y(self.camera_angle_y)
             .rotate_x(self.camera_angle_x)
             .translate([0.0, 0.0, -self.distance]);
-        match &mut self.helmet {
-            Some(helmet) => {
+        match self.helmet.as_mut().map(LoadHandle::poll) {
+            Some(LoadStatus::Ready(helmet)) => {
                 drawer.draw(
-                    camera_transform,
-                    [0.0, 7000.0, 2000.0],
+                    Camera::new(camera_transform),
+                    &[SUN],
                     &[DisplayRequest::In3D(
                         helmet,
                         &[Transform::new().rotate_y(self.angle).rotate_x(1.57)],
@@ -72,7 +90,10 @@ impl GameScene for Scene {
                     )],
                 );
             }
-            _ => panic!("scene not fully loaded"),
+            // the empty scene can be looked around while the helmet loads
+            Some(LoadStatus::Pending) => drawer.draw(Camera::new(camera_transform), &[SUN], &[]),
+            Some(LoadStatus::Failed(error)) => panic!("failed to load the helmet: {}", error),
+            None => panic!("scene not loaded"),
         }
     }
 }
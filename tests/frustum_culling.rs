@@ -0,0 +1,97 @@
+use kor_engine::{
+    geometry::{BoundingBox, Transform},
+    graphics::camera::{Camera, Projection},
+};
+
+const ASPECT: f32 = 16.0 / 9.0;
+const UNIT_BOX: BoundingBox = BoundingBox {
+    min: [-0.5; 3],
+    max: [0.5; 3],
+};
+
+// cameras at the origin looking along +Z, seeing up to 100 units away
+fn cameras() -> Vec<Camera> {
+    let perspective = Camera::new(Transform::new());
+    let orthographic = Camera {
+        projection: Projection::Orthographic {
+            size: 5.0,
+            near: 0.1,
+            far: 100.0,
+        },
+        ..perspective
+    };
+    [perspective, orthographic]
+        .into_iter()
+        .flat_map(|camera| {
+            [false, true].map(|reverse_z| Camera {
+                reverse_z,
+                ..camera
+            })
+        })
+        .collect()
+}
+
+fn is_visible(camera: &Camera, position: [f32; 3]) -> bool {
+    camera
+        .frustum(ASPECT)
+        .intersects(&UNIT_BOX, &Transform::new().translate(position))
+}
+
+#[test]
+fn boxes_in_front_are_kept() {
+    for camera in cameras() {
+        assert!(is_visible(&camera, [0.0, 0.0, 5.0]));
+        assert!(is_visible(&camera, [2.0, -2.0, 50.0]));
+    }
+}
+
+#[test]
+fn boxes_behind_or_too_far_are_culled() {
+    for camera in cameras() {
+        assert!(!is_visible(&camera, [0.0, 0.0, -5.0]));
+        assert!(!is_visible(&camera, [0.0, 0.0, 150.0]));
+    }
+}
+
+#[test]
+fn boxes_on_the_sides_are_culled() {
+    for camera in cameras() {
+        assert!(!is_visible(&camera, [20.0, 0.0, 5.0]));
+        assert!(!is_visible(&camera, [0.0, -20.0, 5.0]));
+    }
+}
+
+#[test]
+fn boxes_crossing_an_edge_are_kept() {
+    for camera in cameras() {
+        // the perspective view is 5 units high at 5 units away, like the orthographic one
+        assert!(is_visible(&camera, [0.0, 5.3, 5.0]));
+    }
+}
+
+#[test]
+fn infinite_perspective_keeps_far_boxes() {
+    for reverse_z in [false, true] {
+        let camera = Camera {
+            projection: Projection::Perspective {
+                fov: 1.0,
+                near: 0.1,
+                far: None,
+            },
+            reverse_z,
+            ..Camera::new(Transform::new())
+        };
+        assert!(is_visible(&camera, [0.0, 0.0, 10000.0]));
+        assert!(!is_visible(&camera, [0.0, 0.0, -5.0]));
+    }
+}
+
+#[test]
+fn scaled_instances_grow_their_box() {
+    let camera = Camera::new(Transform::new());
+    let transform = Transform::new().translate([12.0, 0.0, 5.0]);
+    assert!(!camera.frustum(ASPECT).intersects(&UNIT_BOX, &transform));
+    assert!(camera
+        .frustum(ASPECT)
+        .intersects(&UNIT_BOX, &transform.scale([20.0; 3])));
+}
@@ -0,0 +1,177 @@
+use std::{env, fs, path::PathBuf};
+
+use image::{Rgba, RgbaImage};
+use kor_engine::{
+    geometry::Transform,
+    graphics::{
+        camera::Camera,
+        engine::{Asset, Engine},
+        light::Light,
+    },
+    DisplayRequest, Drawer, Loader,
+};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 180;
+const SUN: Light = Light::Directional {
+    direction: [0.0, -1.0, 1.0],
+    color: [1.0; 3],
+    intensity: 1.0,
+    cast_shadows: true,
+};
+const UPDATE_VARIABLE: &str = "KOR_UPDATE_GOLDEN";
+// YIQ distance above which two pixels are considered different, as a fraction of the maximum
+const PIXEL_THRESHOLD: f32 = 0.1;
+// share of differing pixels tolerated before the comparison fails
+const MAX_MISMATCH_RATIO: f32 = 0.002;
+const MAX_YIQ_DELTA: f32 = 35215.0;
+
+struct GoldenCase<'a> {
+    name: &'a str,
+    file: &'a str,
+    node: &'a str,
+    camera: Transform,
+    transform: Transform,
+    animation: Option<(usize, f32)>,
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn golden_fox() {
+    check_golden(&GoldenCase {
+        name: "fox",
+        file: "Fox.glb",
+        node: "fox",
+        camera: Transform::look_at([0.0, 1.5, -4.0], [0.0, 1.0, 0.0]),
+        transform: Transform::new().rotate_y(0.8).scale([0.02; 3]),
+        animation: Some((2, 0.5)),
+    });
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn golden_monkey() {
+    check_golden(&GoldenCase {
+        name: "monkey",
+        file: "monkey.glb",
+        node: "Suzanne",
+        camera: Transform::look_at([0.0, 0.0, -3.0], [0.0, 0.0, 0.0]),
+        transform: Transform::new().rotate_y(0.5),
+        animation: None,
+    });
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn golden_damaged_helmet() {
+    check_golden(&GoldenCase {
+        name: "damaged_helmet",
+        file: "DamagedHelmet.glb",
+        node: "node_damagedHelmet_-6514",
+        camera: Transform::look_at([0.0, 0.0, -3.0], [0.0, 0.0, 0.0]),
+        transform: Transform::new().rotate_y(0.4).rotate_x(1.57),
+        animation: None,
+    });
+}
+
+fn render(case: &GoldenCase) -> RgbaImage {
+    let mut engine = Engine::new_headless([WIDTH, HEIGHT]);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(case.file);
+    let mut asset = engine.load(path.to_str().unwrap(), case.node).unwrap();
+    let pose = match (&mut asset, case.animation) {
+        (Asset::Animated(_, animator), Some((animation, t))) => {
+            animator.reset();
+            animator.animate(animation, t);
+            Some(animator.compute_transforms())
+        }
+        _ => None,
+    };
+    engine.draw(
+        Camera::new(case.camera),
+        &[SUN],
+        &[DisplayRequest::In3D(
+            &asset,
+            &[case.transform],
+            pose.as_deref(),
+        )],
+    );
+    engine.read_frame()
+}
+
+fn check_golden(case: &GoldenCase) {
+    let actual = render(case);
+    let reference_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.png", case.name));
+    if env::var_os(UPDATE_VARIABLE).is_some() {
+        fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+        actual.save(&reference_path).unwrap();
+        return;
+    }
+    let Ok(reference) = image::open(&reference_path) else {
+        panic!(
+            "missing reference {}, run with {}=1 to create it",
+            reference_path.display(),
+            UPDATE_VARIABLE
+        );
+    };
+    let reference = reference.into_rgba8();
+    assert_eq!(
+        reference.dimensions(),
+        actual.dimensions(),
+        "reference {} has the wrong size",
+        reference_path.display()
+    );
+    let (diff, mismatch_count) = compare(&reference, &actual);
+    let mismatch_ratio = mismatch_count as f32 / (WIDTH * HEIGHT) as f32;
+    if mismatch_ratio > MAX_MISMATCH_RATIO {
+        let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        fs::create_dir_all(&output_dir).unwrap();
+        let actual_path = output_dir.join(format!("{}.actual.png", case.name));
+        let diff_path = output_dir.join(format!("{}.diff.png", case.name));
+        actual.save(&actual_path).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{}: {} pixels differ ({:.3}%), see {} and {}",
+            case.name,
+            mismatch_count,
+            100.0 * mismatch_ratio,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn compare(reference: &RgbaImage, actual: &RgbaImage) -> (RgbaImage, u32) {
+    let max_delta = MAX_YIQ_DELTA * PIXEL_THRESHOLD * PIXEL_THRESHOLD;
+    let mut mismatch_count = 0;
+    let diff = RgbaImage::from_fn(reference.width(), reference.height(), |x, y| {
+        let expected = reference.get_pixel(x, y);
+        if yiq_delta(expected, actual.get_pixel(x, y)) > max_delta {
+            mismatch_count += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            // faded reference so the differences stand out
+            let luma = (yiq(expected)[0] * 0.1 + 255.0 * 0.9) as u8;
+            Rgba([luma, luma, luma, 255])
+        }
+    });
+    (diff, mismatch_count)
+}
+
+fn yiq(pixel: &Rgba<u8>) -> [f32; 3] {
+    let [r, g, b] = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+    [
+        r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_23,
+        r * 0.595_977_97 - g * 0.274_176_1 - b * 0.321_801_9,
+        r * 0.211_470_17 - g * 0.522_617_1 + b * 0.311_146_94,
+    ]
+}
+
+fn yiq_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let [y1, i1, q1] = yiq(a);
+    let [y2, i2, q2] = yiq(b);
+    let (dy, di, dq) = (y1 - y2, i1 - i2, q1 - q2);
+    0.5053 * dy * dy + 0.299 * di * di + 0.1957 * dq * dq
+}
@@ -0,0 +1,237 @@
+use basis_universal::{BasisTextureFormat, Compressor, CompressorParams};
+use kor_engine::graphics::{format_converter::ktx2_texture, texture::TextureUsage};
+use vulkano::format::Format;
+
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const HEADER_LENGTH: usize = 80;
+const LEVEL_INDEX_LENGTH: usize = 24;
+// a basic data format descriptor without samples
+const DFD_LENGTH: usize = 28;
+const VK_FORMAT_BC7_SRGB_BLOCK: u32 = 146;
+const BASIS_LZ: u32 = 1;
+const COLOR_MODEL_ETC1S: u8 = 163;
+const COLOR_MODEL_UASTC: u8 = 166;
+const COLOR: [u8; 4] = [200, 100, 50, 255];
+// lengths in bytes of the .basis file header and of its descriptions of each slice
+const BASIS_HEADER_LENGTH: usize = 77;
+const BASIS_SLICE_LENGTH: usize = 23;
+
+struct Ktx2File {
+    format: u32,
+    size: u32,
+    layer_count: u32,
+    supercompression_scheme: u32,
+    color_model: u8,
+    global_data: Vec<u8>,
+    levels: Vec<Vec<u8>>,
+}
+
+impl Ktx2File {
+    /// An 8x8 BC7 texture with its two mip levels.
+    fn bc7() -> Self {
+        Ktx2File {
+            format: VK_FORMAT_BC7_SRGB_BLOCK,
+            size: 8,
+            layer_count: 0,
+            supercompression_scheme: 0,
+            color_model: 0,
+            global_data: Vec::new(),
+            levels: vec![vec![1; 64], vec![2; 16]],
+        }
+    }
+
+    /// An 8x8 texture of `COLOR` with its mip chain, encoded by the Basis Universal encoder
+    /// and moved from the .basis file it makes to a KTX2 file.
+    fn basis_universal(format: BasisTextureFormat) -> Self {
+        let mut params = CompressorParams::new();
+        params.set_basis_format(format);
+        params.set_generate_mipmaps(true);
+        params.source_image_mut(0).init(&COLOR.repeat(64), 8, 8, 4);
+        let mut compressor = Compressor::new(1);
+        unsafe {
+            assert!(compressor.init(&params));
+            compressor.process().unwrap();
+        }
+        let basis = compressor.basis_file();
+        let read = |offset: usize, size: usize| {
+            basis[offset..offset + size]
+                .iter()
+                .rev()
+                .fold(0, |value, &byte| value << 8 | byte as usize)
+        };
+        let slice_count = read(14, 3);
+        let levels: Vec<_> = (0..slice_count)
+            .map(|slice| {
+                let slice = BASIS_HEADER_LENGTH + slice * BASIS_SLICE_LENGTH;
+                let offset = read(slice + 13, 4);
+                basis[offset..offset + read(slice + 17, 4)].to_vec()
+            })
+            .collect();
+        match format {
+            BasisTextureFormat::UASTC4x4 => Ktx2File {
+                format: 0,
+                supercompression_scheme: 0,
+                color_model: COLOR_MODEL_UASTC,
+                levels,
+                ..Ktx2File::bc7()
+            },
+            BasisTextureFormat::ETC1S => {
+                // the header of the global data, the description of the color slice of each level, then the codebooks
+                let [endpoints, selectors, tables] =
+                    [(41, 45, 3), (50, 54, 3), (57, 61, 4)].map(|(offset, length, length_size)| {
+                        let offset = read(offset, 4);
+                        &basis[offset..offset + read(length, length_size)]
+                    });
+                let mut global_data = Vec::new();
+                global_data.extend_from_slice(&basis[39..41]);
+                global_data.extend_from_slice(&basis[48..50]);
+                for length in [endpoints.len(), selectors.len(), tables.len(), 0] {
+                    global_data.extend_from_slice(&(length as u32).to_le_bytes());
+                }
+                for level in &levels {
+                    for value in [0, 0, level.len() as u32, 0, 0] {
+                        global_data.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                for codebook in [endpoints, selectors, tables] {
+                    global_data.extend_from_slice(codebook);
+                }
+                Ktx2File {
+                    format: 0,
+                    supercompression_scheme: BASIS_LZ,
+                    color_model: COLOR_MODEL_ETC1S,
+                    global_data,
+                    levels,
+                    ..Ktx2File::bc7()
+                }
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let dfd_offset = HEADER_LENGTH + self.levels.len() * LEVEL_INDEX_LENGTH;
+        let global_data_offset = dfd_offset + DFD_LENGTH;
+        let mut bytes = KTX2_MAGIC.to_vec();
+        for value in [
+            self.format,
+            1,
+            self.size,
+            self.size,
+            0,
+            self.layer_count,
+            1,
+            self.levels.len() as u32,
+            self.supercompression_scheme,
+            dfd_offset as u32,
+            DFD_LENGTH as u32,
+            0,
+            0,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [global_data_offset, self.global_data.len()] {
+            bytes.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        let mut level_offset = global_data_offset + self.global_data.len();
+        for level in &self.levels {
+            for value in [level_offset, level.len(), level.len()] {
+                bytes.extend_from_slice(&(value as u64).to_le_bytes());
+            }
+            level_offset += level.len();
+        }
+        bytes.extend_from_slice(&(DFD_LENGTH as u32).to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(2u32 | ((DFD_LENGTH as u32 - 4) << 16)).to_le_bytes());
+        bytes.push(self.color_model);
+        bytes.extend_from_slice(&[0; 15]);
+        bytes.extend_from_slice(&self.global_data);
+        for level in &self.levels {
+            bytes.extend_from_slice(level);
+        }
+        bytes
+    }
+}
+
+#[test]
+fn block_compressed_textures_keep_their_format_and_levels() {
+    let file = Ktx2File::bc7();
+    let image = ktx2_texture::convert_texture(&file.to_bytes(), TextureUsage::Color, |_| true)
+        .unwrap()
+        .unwrap();
+    assert_eq!(image.format, Format::BC7_SRGB_BLOCK);
+    assert_eq!(image.extent, [8, 8, 1]);
+    assert_eq!(image.levels, file.levels);
+}
+
+#[test]
+fn block_compressed_formats_the_device_cannot_sample_are_skipped() {
+    let image =
+        ktx2_texture::convert_texture(&Ktx2File::bc7().to_bytes(), TextureUsage::Color, |format| {
+            format == Format::R8G8B8A8_SRGB
+        })
+        .unwrap();
+    assert!(image.is_none());
+}
+
+#[test]
+fn basis_universal_textures_are_transcoded_to_block_compressed_formats() {
+    for format in [BasisTextureFormat::UASTC4x4, BasisTextureFormat::ETC1S] {
+        let bytes = Ktx2File::basis_universal(format).to_bytes();
+        let image = ktx2_texture::convert_texture(&bytes, TextureUsage::Color, |_| true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(image.format, Format::BC7_SRGB_BLOCK);
+        assert_eq!(image.extent, [8, 8, 1]);
+        let level_lengths: Vec<_> = image.levels.iter().map(Vec::len).collect();
+        assert_eq!(level_lengths, [64, 16, 16, 16]);
+    }
+}
+
+#[test]
+fn basis_universal_textures_fall_back_to_rgba8() {
+    for format in [BasisTextureFormat::UASTC4x4, BasisTextureFormat::ETC1S] {
+        let bytes = Ktx2File::basis_universal(format).to_bytes();
+        let image = ktx2_texture::convert_texture(&bytes, TextureUsage::Occlusion, |format| {
+            format == Format::R8G8B8A8_UNORM
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(image.format, Format::R8G8B8A8_UNORM);
+        let level_lengths: Vec<_> = image.levels.iter().map(Vec::len).collect();
+        assert_eq!(level_lengths, [256, 64, 16, 4]);
+        for texel in image.levels[0].chunks(4) {
+            for (channel, expected) in texel.iter().zip(COLOR) {
+                assert!(channel.abs_diff(expected) <= 8, "{:?}", texel);
+            }
+        }
+    }
+}
+
+#[test]
+fn etc1s_textures_must_be_basis_lz_supercompressed() {
+    let file = Ktx2File {
+        supercompression_scheme: 0,
+        ..Ktx2File::basis_universal(BasisTextureFormat::ETC1S)
+    };
+    assert!(
+        ktx2_texture::convert_texture(&file.to_bytes(), TextureUsage::Color, |_| true).is_err()
+    );
+}
+
+#[test]
+fn texture_arrays_are_rejected() {
+    let file = Ktx2File {
+        layer_count: 2,
+        ..Ktx2File::bc7()
+    };
+    assert!(
+        ktx2_texture::convert_texture(&file.to_bytes(), TextureUsage::Color, |_| true).is_err()
+    );
+}
+
+#[test]
+fn other_files_are_rejected() {
+    assert!(ktx2_texture::convert_texture(b"\x89PNG", TextureUsage::Color, |_| true).is_err());
+}
@@ -0,0 +1,38 @@
+use std::sync::mpsc;
+
+use kor_engine::graphics::{
+    load_error::LoadError,
+    load_handle::{LoadHandle, LoadStatus},
+};
+
+#[test]
+fn loads_are_pending_until_their_thread_ends() {
+    let (sender, receiver) = mpsc::channel::<()>();
+    let mut handle = LoadHandle::spawn(move || {
+        receiver.recv().unwrap();
+        Ok(1)
+    });
+    assert!(matches!(handle.poll(), LoadStatus::Pending));
+    sender.send(()).unwrap();
+    assert_eq!(handle.wait().unwrap(), 1);
+}
+
+#[test]
+fn polling_gives_the_loaded_value() {
+    let mut handle = LoadHandle::spawn(|| Ok(vec![1, 2]));
+    while let LoadStatus::Pending = handle.poll() {}
+    assert!(matches!(handle.poll(), LoadStatus::Ready(loaded) if *loaded == [1, 2]));
+}
+
+#[test]
+fn errors_fail_the_load() {
+    let handle = LoadHandle::<()>::spawn(|| Err(LoadError::NodeNotFound("fox".to_string())));
+    assert!(matches!(handle.wait(), Err(LoadError::NodeNotFound(node)) if node == "fox"));
+}
+
+#[test]
+#[should_panic]
+fn panics_of_the_loading_thread_are_resumed() {
+    let handle = LoadHandle::<()>::spawn(|| panic!("loading failed"));
+    let _ = handle.wait();
+}
@@ -0,0 +1,90 @@
+use kor_engine::{
+    geometry::{BoundingBox, Transform},
+    graphics::{
+        camera::{Camera, Projection},
+        lod::{self, LodDraw},
+    },
+};
+
+const SCREEN_COVERAGES: [f32; 3] = [0.5, 0.1, 0.02];
+
+fn draw(level: usize) -> Vec<LodDraw> {
+    vec![LodDraw { level, fade: 0.0 }]
+}
+
+#[test]
+fn levels_follow_the_coverage() {
+    assert_eq!(lod::select(&SCREEN_COVERAGES, 2.0, None), draw(0));
+    assert_eq!(lod::select(&SCREEN_COVERAGES, 0.5, None), draw(0));
+    assert_eq!(lod::select(&SCREEN_COVERAGES, 0.3, None), draw(1));
+    assert_eq!(lod::select(&SCREEN_COVERAGES, 0.05, None), draw(2));
+}
+
+#[test]
+fn instances_below_the_last_level_are_not_drawn() {
+    assert!(lod::select(&SCREEN_COVERAGES, 0.01, None).is_empty());
+    assert_eq!(lod::select(&[0.5, 0.0], 0.0, None), draw(1));
+}
+
+#[test]
+fn cross_fading_levels_keep_complementary_fragments() {
+    // halfway through the band above the switch to the level 1
+    let draws = lod::select(&SCREEN_COVERAGES, 0.55, Some(0.2));
+    assert_eq!(draws.len(), 2);
+    assert_eq!(draws[0].level, 0);
+    assert_eq!(draws[1].level, 1);
+    assert!((draws[0].fade - 0.5).abs() < 1e-5);
+    assert_eq!(draws[1].fade, -draws[0].fade);
+    // past the band
+    assert_eq!(lod::select(&SCREEN_COVERAGES, 0.61, Some(0.2)), draw(0));
+}
+
+#[test]
+fn the_last_level_fades_out_alone() {
+    let draws = lod::select(&SCREEN_COVERAGES, 0.021, Some(0.2));
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].level, 2);
+    assert!(draws[0].fade > 0.0 && draws[0].fade < 1.0);
+}
+
+#[test]
+fn coverage_shrinks_with_distance_and_grows_with_scale() {
+    let camera = Camera::new(Transform::new());
+    let bounds = BoundingBox {
+        min: [-0.5; 3],
+        max: [0.5; 3],
+    };
+    let near = camera.screen_coverage(&bounds, &Transform::new().translate([0.0, 0.0, 5.0]));
+    let far = camera.screen_coverage(&bounds, &Transform::new().translate([0.0, 0.0, 10.0]));
+    let scaled = camera.screen_coverage(
+        &bounds,
+        &Transform::new().translate([0.0, 0.0, 10.0]).scale([2.0; 3]),
+    );
+    // radius of sqrt(3) / 2 seen 5 units away with a 90° field of view
+    assert!((near - 3f32.sqrt() / 10.0).abs() < 1e-5);
+    assert!((far - near / 2.0).abs() < 1e-5);
+    assert!((scaled - near).abs() < 1e-5);
+    assert_eq!(
+        camera.screen_coverage(&bounds, &Transform::new()),
+        f32::INFINITY
+    );
+}
+
+#[test]
+fn orthographic_coverage_ignores_distance() {
+    let camera = Camera {
+        projection: Projection::Orthographic {
+            size: 5.0,
+            near: 0.1,
+            far: 100.0,
+        },
+        ..Camera::new(Transform::new())
+    };
+    let bounds = BoundingBox {
+        min: [-0.5; 3],
+        max: [0.5; 3],
+    };
+    let near = camera.screen_coverage(&bounds, &Transform::new().translate([0.0, 0.0, 5.0]));
+    let far = camera.screen_coverage(&bounds, &Transform::new().translate([0.0, 0.0, 50.0]));
+    assert!((near - far).abs() < 1e-5);
+}
@@ -0,0 +1,62 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+use kor_engine::graphics::attribute_generator::normal;
+
+// two unwelded triangles folded at a right angle along the x axis
+const POSITIONS: [[f32; 3]; 6] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [0.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0],
+];
+const INDICES: [u32; 6] = [0, 1, 2, 3, 4, 5];
+const FOLD_NORMAL: [f32; 3] = [0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2];
+
+#[test]
+fn flat_normals_follow_faces() {
+    let normals = normal::flat(&POSITIONS, &INDICES);
+    assert_normals(
+        &normals,
+        &[[0.0, 0.0, 1.0]; 3]
+            .into_iter()
+            .chain([[0.0, 1.0, 0.0]; 3])
+            .collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn smooth_normals_average_shared_positions() {
+    let normals = normal::smooth(&POSITIONS, &INDICES, PI);
+    assert_normals(
+        &normals,
+        &[
+            FOLD_NORMAL,
+            FOLD_NORMAL,
+            [0.0, 0.0, 1.0],
+            FOLD_NORMAL,
+            FOLD_NORMAL,
+            [0.0, 1.0, 0.0],
+        ],
+    );
+}
+
+#[test]
+fn smooth_normals_keep_creases() {
+    let normals = normal::smooth(&POSITIONS, &INDICES, PI / 4.0);
+    assert_normals(&normals, &normal::flat(&POSITIONS, &INDICES));
+}
+
+fn assert_normals(actual: &[[f32; 3]], expected: &[[f32; 3]]) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            a.iter().zip(e).all(|(a, e)| (a - e).abs() < 1e-5),
+            "vertex {}: expected {:?}, got {:?}",
+            i,
+            e,
+            a
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_3};
+
+use kor_engine::geometry::Transform;
+
+const ASPECT: f32 = 2.0;
+const NEAR: f32 = 0.1;
+const FAR: f32 = 100.0;
+
+#[test]
+fn the_edges_of_the_field_of_view_reach_the_edges_of_the_screen() {
+    for fov in [FRAC_PI_3, FRAC_PI_2, 2.0 * FRAC_PI_3] {
+        let projection = Transform::new().project_perspective(fov, ASPECT, NEAR, FAR);
+        let half_height = 10.0 * (fov / 2.0).tan();
+        let [x, y, _] = project(projection, [half_height * ASPECT, half_height, 10.0]);
+        assert!((x.abs() - 1.0).abs() < 1e-5, "fov {}: x is {}", fov, x);
+        assert!((y.abs() - 1.0).abs() < 1e-5, "fov {}: y is {}", fov, y);
+    }
+}
+
+#[test]
+fn depth_goes_from_0_at_the_near_plane_to_1_at_the_far_plane() {
+    let projection = Transform::new().project_perspective(FRAC_PI_3, ASPECT, NEAR, FAR);
+    let [_, _, near_depth] = project(projection, [0.0, 0.0, NEAR]);
+    let [_, _, far_depth] = project(projection, [0.0, 0.0, FAR]);
+    assert!(near_depth.abs() < 1e-5, "{}", near_depth);
+    assert!((far_depth - 1.0).abs() < 1e-5, "{}", far_depth);
+}
+
+/// Normalized device coordinates of `point`, the matrix being column major.
+fn project(matrix: [[f32; 4]; 4], point: [f32; 3]) -> [f32; 3] {
+    let [x, y, z] = point;
+    let clip: [f32; 4] = std::array::from_fn(|row| {
+        [x, y, z, 1.0]
+            .iter()
+            .zip(matrix)
+            .map(|(coordinate, column)| coordinate * column[row])
+            .sum()
+    });
+    [0, 1, 2].map(|i| clip[i] / clip[3])
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use kor_engine::graphics::{attribute_generator::tangent, mesh_data::MeshData};
+
+// a unit quad facing +z as two unwelded triangles, textured with u along x and v along y
+const POSITIONS: [[f32; 3]; 6] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0],
+];
+const NORMALS: [[f32; 3]; 6] = [[0.0, 0.0, 1.0]; 6];
+const INDICES: [u32; 6] = [0, 1, 2, 3, 4, 5];
+
+#[test]
+fn fallback_tangents_are_unit_and_orthogonal() {
+    let normals = [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, -1.0],
+        [0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2],
+        [-FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2],
+    ];
+    let tangents = tangent::fallback(&normals);
+    assert_eq!(tangents.len(), normals.len());
+    for (normal, tangent) in normals.iter().zip(&tangents) {
+        let length = (0..3).map(|i| tangent[i] * tangent[i]).sum::<f32>().sqrt();
+        let dot: f32 = (0..3).map(|i| tangent[i] * normal[i]).sum();
+        assert!(
+            (length - 1.0).abs() < 1e-5,
+            "normal {:?}: tangent {:?} is not unit",
+            normal,
+            tangent
+        );
+        assert!(
+            dot.abs() < 1e-5,
+            "normal {:?}: tangent {:?} is not orthogonal",
+            normal,
+            tangent
+        );
+        assert_eq!(tangent[3], 1.0);
+    }
+}
+
+#[test]
+fn mikktspace_tangents_follow_the_u_direction() {
+    let tex_coords = POSITIONS.map(|[x, y, _]| [x, y]);
+    let tangents = tangent::mikktspace(&POSITIONS, &NORMALS, &tex_coords, &INDICES);
+    assert_tangents(&tangents, [1.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn mikktspace_tangents_of_mirrored_texture_coordinates_flip_the_bitangent() {
+    let tex_coords = POSITIONS.map(|[x, y, _]| [1.0 - x, y]);
+    let tangents = tangent::mikktspace(&POSITIONS, &NORMALS, &tex_coords, &INDICES);
+    assert_tangents(&tangents, [-1.0, 0.0, 0.0, -1.0]);
+}
+
+#[test]
+fn authored_tangents_keep_their_sign_through_reading_and_welding() {
+    let glb = quad_glb([1.0, 0.0, 0.0, -1.0]);
+    let (document, buffers, _) = gltf::import_slice(&glb).unwrap();
+    let primitive = document
+        .meshes()
+        .next()
+        .unwrap()
+        .primitives()
+        .next()
+        .unwrap();
+    let mut mesh_data =
+        MeshData::read(&primitive.reader(|buffer| Some(&buffers[buffer.index()]))).unwrap();
+    assert_eq!(
+        mesh_data.tangents.as_deref(),
+        Some(&[[1.0, 0.0, 0.0, -1.0]; 4][..])
+    );
+    mesh_data.unweld();
+    mesh_data.weld();
+    assert_eq!(mesh_data.vertex_count(), 4);
+    assert_eq!(
+        mesh_data.tangents.as_deref(),
+        Some(&[[1.0, 0.0, 0.0, -1.0]; 4][..])
+    );
+}
+
+fn assert_tangents(tangents: &[[f32; 4]], expected: [f32; 4]) {
+    assert_eq!(tangents.len(), POSITIONS.len());
+    for (i, tangent) in tangents.iter().enumerate() {
+        assert!(
+            tangent
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() < 1e-5),
+            "vertex {}: expected {:?}, got {:?}",
+            i,
+            expected,
+            tangent
+        );
+    }
+}
+
+/// A binary glTF of the welded quad, with `tangent` as its TANGENT attribute.
+fn quad_glb(tangent: [f32; 4]) -> Vec<u8> {
+    let mut bin = Vec::new();
+    for [x, y, z] in [POSITIONS[0], POSITIONS[1], POSITIONS[2], POSITIONS[5]] {
+        bin.extend([x, y, z].iter().flat_map(|value| value.to_le_bytes()));
+    }
+    for _ in 0..4 {
+        bin.extend(
+            [0.0f32, 0.0, 1.0]
+                .iter()
+                .flat_map(|value| value.to_le_bytes()),
+        );
+    }
+    for _ in 0..4 {
+        bin.extend(tangent.iter().flat_map(|value| value.to_le_bytes()));
+    }
+    bin.extend(
+        [0u16, 1, 2, 0, 2, 3]
+            .iter()
+            .flat_map(|index| index.to_le_bytes()),
+    );
+    let json = format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "buffers": [{{ "byteLength": {} }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 48 }},
+                {{ "buffer": 0, "byteOffset": 48, "byteLength": 48 }},
+                {{ "buffer": 0, "byteOffset": 96, "byteLength": 64 }},
+                {{ "buffer": 0, "byteOffset": 160, "byteLength": 12 }}
+            ],
+            "accessors": [
+                {{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                   "min": [0, 0, 0], "max": [1, 1, 0] }},
+                {{ "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC3" }},
+                {{ "bufferView": 2, "componentType": 5126, "count": 4, "type": "VEC4" }},
+                {{ "bufferView": 3, "componentType": 5123, "count": 6, "type": "SCALAR" }}
+            ],
+            "meshes": [{{ "primitives": [{{
+                "attributes": {{ "POSITION": 0, "NORMAL": 1, "TANGENT": 2 }},
+                "indices": 3
+            }}] }}]
+        }}"#,
+        bin.len()
+    );
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let mut glb = b"glTF".to_vec();
+    glb.extend(2u32.to_le_bytes());
+    glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+    for (chunk, kind) in [(json, b"JSON"), (bin, b"BIN\0")] {
+        glb.extend((chunk.len() as u32).to_le_bytes());
+        glb.extend(kind);
+        glb.extend(chunk);
+    }
+    glb
+}
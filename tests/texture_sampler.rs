@@ -0,0 +1,115 @@
+use kor_engine::graphics::texture::{self, TextureSampler};
+use vulkano::image::sampler::{Filter, SamplerAddressMode, SamplerMipmapMode};
+
+// NEAREST, LINEAR_MIPMAP_NEAREST, CLAMP_TO_EDGE and MIRRORED_REPEAT from the glTF specification
+const SAMPLERS: &str = r#"{
+    "asset": { "version": "2.0" },
+    "samplers": [
+        {},
+        { "magFilter": 9728, "minFilter": 9728 },
+        { "minFilter": 9985, "wrapS": 33071, "wrapT": 33648 }
+    ],
+    "textures": [
+        { "sampler": 0 },
+        { "sampler": 1 },
+        { "sampler": 2 },
+        { "sampler": 0, "extras": { "anisotropy": 4 } },
+        { "sampler": 0, "extras": { "anisotropy": 1 } },
+        { "sampler": 0, "extras": { "anisotropy": 16 } }
+    ]
+}"#;
+
+fn samplers(anisotropy: Option<u32>) -> Vec<TextureSampler> {
+    let gltf = gltf::Gltf::from_slice(SAMPLERS.as_bytes()).unwrap();
+    gltf.textures()
+        .map(|texture| TextureSampler::from_gltf(&texture, anisotropy))
+        .collect()
+}
+
+#[test]
+fn undefined_filters_are_trilinear_and_repeated() {
+    assert_eq!(
+        samplers(None)[0],
+        TextureSampler {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_mode: Some(SamplerMipmapMode::Linear),
+            address_mode: [SamplerAddressMode::Repeat; 2],
+            anisotropy: 1,
+        }
+    );
+    assert_eq!(samplers(None)[0], TextureSampler::default());
+}
+
+#[test]
+fn filters_without_mipmaps_sample_the_full_size_image() {
+    let sampler = samplers(None)[1];
+    assert_eq!(sampler.mag_filter, Filter::Nearest);
+    assert_eq!(sampler.min_filter, Filter::Nearest);
+    assert_eq!(sampler.mipmap_mode, None);
+}
+
+#[test]
+fn wrap_modes_follow_the_file() {
+    let sampler = samplers(None)[2];
+    assert_eq!(sampler.min_filter, Filter::Linear);
+    assert_eq!(sampler.mipmap_mode, Some(SamplerMipmapMode::Nearest));
+    assert_eq!(
+        sampler.address_mode,
+        [
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::MirroredRepeat
+        ]
+    );
+}
+
+#[test]
+fn anisotropy_only_applies_to_trilinear_textures() {
+    let anisotropies: Vec<_> = samplers(Some(8))[..3]
+        .iter()
+        .map(|sampler| sampler.anisotropy)
+        .collect();
+    assert_eq!(anisotropies, [8, 1, 1]);
+}
+
+#[test]
+fn texture_extras_override_the_anisotropy() {
+    assert_eq!(samplers(None)[3].anisotropy, 4);
+    assert_eq!(samplers(Some(8))[3].anisotropy, 4);
+    assert_eq!(samplers(Some(8))[4].anisotropy, 1);
+}
+
+#[test]
+fn anisotropy_is_clamped_to_the_device_limit() {
+    let sampler = samplers(None)[5];
+    assert_eq!(sampler.anisotropy, 16);
+    assert_eq!(
+        sampler
+            .create_info_with_max_anisotropy(Some(8.0))
+            .anisotropy,
+        Some(8.0)
+    );
+    assert_eq!(
+        samplers(None)[3]
+            .create_info_with_max_anisotropy(Some(8.0))
+            .anisotropy,
+        Some(4.0)
+    );
+    assert_eq!(
+        sampler.create_info_with_max_anisotropy(None).anisotropy,
+        None
+    );
+    assert_eq!(
+        samplers(None)[4]
+            .create_info_with_max_anisotropy(Some(8.0))
+            .anisotropy,
+        None
+    );
+}
+
+#[test]
+fn mip_chains_go_down_to_one_texel() {
+    assert_eq!(texture::mip_level_count(1), 1);
+    assert_eq!(texture::mip_level_count(256), 9);
+    assert_eq!(texture::mip_level_count(300), 9);
+}